@@ -1,7 +1,6 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::{AcceptType, FollowType},
@@ -16,6 +15,7 @@ use web::AppState;
 
 use crate::{
     activities::{follow::Follow, generate_accept_activity_id, is_duplicate},
+    common::delivery::queue_activity,
     objects::user::ApUser,
 };
 
@@ -1,7 +1,6 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     traits::{ActivityHandler, Object},
 };
@@ -12,7 +11,7 @@ use web::AppState;
 pub use crate::objects::announce::Announce;
 use crate::{
     activities::is_duplicate,
-    common::notifications,
+    common::{delivery::queue_activity, notifications, streaming},
     objects::{announce::ApAnnounce, user::ApUser},
 };
 
@@ -54,7 +53,8 @@ impl ActivityHandler for Announce {
 
         let actor = self.actor.dereference(data).await?;
         let post = self.object.dereference(data).await?;
-        ApAnnounce::from_json(self, data).await?;
+        let boost = ApAnnounce::from_json(self, data).await?.0;
+        streaming::process_boost(&boost, &post, &data.db_pool).await?;
         notifications::process_boost(
             &post,
             &actor,
@@ -1,7 +1,6 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::CreateType,
@@ -14,7 +13,7 @@ use web::AppState;
 
 use crate::{
     activities::is_duplicate,
-    common::notifications,
+    common::{delivery::queue_activity, notifications, streaming},
     objects::{
         note::{ApNote, Note},
         user::ApUser,
@@ -90,6 +89,7 @@ impl ActivityHandler for CreateNote {
 
         let note = ApNote::from_json(self.object, data).await?;
         notifications::process_post(&note, &data.db_pool).await?;
+        streaming::process_post(&note, &data.db_pool).await?;
 
         Ok(())
     }
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use activitypub_federation::{
+    config::Data,
+    fetch::object_id::ObjectId,
+    kinds::activity::DeleteType,
+    protocol::helpers::deserialize_one_or_many,
+    traits::{ActivityHandler, Actor, Object},
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use web::AppState;
+
+use crate::{
+    activities::{generate_activity_id, is_duplicate},
+    common::delivery::queue_activity,
+    objects::user::ApUser,
+    PUBLIC,
+};
+
+/// `Delete` of an actor, sent when an account is deleted
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteUser {
+    pub actor: ObjectId<ApUser>,
+    pub object: ObjectId<ApUser>,
+    #[serde(deserialize_with = "deserialize_one_or_many", default)]
+    pub to: Vec<Url>,
+    #[serde(rename = "type")]
+    pub kind: DeleteType,
+    pub id: Url,
+}
+
+impl DeleteUser {
+    /// Has to be sent before the user is purged, as the inboxes are those of their followers
+    pub async fn send(actor: &ApUser, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+        let activity = DeleteUser {
+            actor: actor.id().into(),
+            object: actor.id().into(),
+            to: vec![Url::parse(PUBLIC)?],
+            kind: Default::default(),
+            id: generate_activity_id(&actor.ap_id, DeleteType::Delete)?,
+        };
+
+        let inboxes = actor
+            .reached_inboxes(&data.db_pool)
+            .await?
+            .into_iter()
+            .map(|inbox| Url::parse(&inbox))
+            .collect::<Result<Vec<Url>, url::ParseError>>()?;
+        queue_activity(&activity, actor, inboxes, data).await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for DeleteUser {
+    type DataType = Arc<AppState>;
+    type Error = anyhow::Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        self.actor.inner()
+    }
+
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if self.actor != self.object {
+            return Err(anyhow!("Invalid Delete activity..."));
+        }
+
+        Ok(())
+    }
+
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if is_duplicate(&self.id, data).await? {
+            return Ok(());
+        }
+
+        // The actor is gone already, so it's not fetched again
+        match ApUser::read_from_id(self.object.into_inner(), data).await? {
+            Some(user) if !user.local => user.delete(data).await,
+            _ => Ok(()),
+        }
+    }
+}
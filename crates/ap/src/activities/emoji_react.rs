@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use activitypub_federation::{
+    config::Data,
+    fetch::object_id::ObjectId,
+    kinds::kind,
+    protocol::helpers::deserialize_one_or_many,
+    traits::{ActivityHandler, Actor},
+};
+use async_trait::async_trait;
+use db::models::CustomEmoji;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use web::AppState;
+
+use crate::{
+    activities::{generate_activity_id, is_duplicate},
+    common::{delivery::queue_activity, reactions},
+    objects::{
+        emoji::{Emoji, EmojiTag},
+        note::ApNote,
+        user::ApUser,
+    },
+};
+
+kind!(EmojiReactType, EmojiReact);
+
+/// Emoji reaction as sent by Pleroma. `content` is either an Unicode emoji or `:shortcode:` of a
+/// custom emoji from `tag`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmojiReact {
+    pub actor: ObjectId<ApUser>,
+    pub object: ObjectId<ApNote>,
+    #[serde(rename = "type")]
+    pub kind: EmojiReactType,
+    pub id: Url,
+    pub content: String,
+    #[serde(rename = "_misskey_reaction", skip_serializing_if = "Option::is_none")]
+    pub misskey_reaction: Option<String>,
+    #[serde(deserialize_with = "deserialize_one_or_many", default)]
+    pub tag: Vec<EmojiTag>,
+}
+
+impl EmojiReact {
+    pub(crate) fn new(
+        id: Url,
+        actor: &ApUser,
+        note: &ApNote,
+        name: &str,
+        emoji: Option<&CustomEmoji>,
+    ) -> anyhow::Result<EmojiReact> {
+        let (content, tag) = match emoji {
+            Some(emoji) => (
+                format!(":{}:", emoji.shortcode),
+                vec![EmojiTag::Emoji(Emoji::try_from(emoji)?)],
+            ),
+            None => (name.to_string(), vec![]),
+        };
+
+        Ok(EmojiReact {
+            actor: actor.id().into(),
+            object: note.id().into(),
+            kind: Default::default(),
+            id,
+            misskey_reaction: Some(content.clone()),
+            content,
+            tag,
+        })
+    }
+
+    pub async fn send(
+        actor: &ApUser,
+        author: &ApUser,
+        note: &ApNote,
+        name: &str,
+        emoji: Option<&CustomEmoji>,
+        data: &Data<Arc<AppState>>,
+    ) -> anyhow::Result<Url> {
+        let id = generate_activity_id(&actor.ap_id, EmojiReactType::EmojiReact)?;
+        let activity = EmojiReact::new(id.clone(), actor, note, name, emoji)?;
+
+        let inboxes = vec![author.shared_inbox_or_inbox()];
+        queue_activity(&activity, actor, inboxes, data).await?;
+
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for EmojiReact {
+    type DataType = Arc<AppState>;
+    type Error = anyhow::Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        self.actor.inner()
+    }
+
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if is_duplicate(&self.id, data).await? {
+            return Ok(());
+        }
+
+        let actor = self.actor.dereference(data).await?;
+        let post = self.object.dereference(data).await?;
+
+        match reactions::resolve_incoming(&self.content, &self.tag, &actor.instance, &data.db_pool)
+            .await?
+        {
+            Some((name, emoji)) => {
+                reactions::receive(&self.id, &actor, &post, name, emoji, &data.db_pool).await
+            },
+            None => Err(anyhow::anyhow!("Unknown reaction {}", self.content)),
+        }
+    }
+}
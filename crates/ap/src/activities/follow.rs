@@ -1,7 +1,6 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::FollowType,
@@ -17,7 +16,7 @@ use web::AppState;
 
 use crate::{
     activities::{accept::follow::AcceptFollow, generate_activity_id, is_duplicate},
-    common::notifications,
+    common::{delivery::queue_activity, notifications},
     objects::user::ApUser,
 };
 
@@ -1,10 +1,10 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::LikeType,
+    protocol::helpers::deserialize_one_or_many,
     traits::{ActivityHandler, Actor},
 };
 use async_trait::async_trait;
@@ -15,8 +15,8 @@ use web::AppState;
 
 use crate::{
     activities::{generate_activity_id, is_duplicate},
-    common::notifications,
-    objects::{note::ApNote, user::ApUser},
+    common::{delivery::queue_activity, notifications, reactions},
+    objects::{emoji::EmojiTag, note::ApNote, user::ApUser},
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -27,6 +27,18 @@ pub struct Like {
     #[serde(rename = "type")]
     pub kind: LikeType,
     pub id: Url,
+
+    /// Misskey sends reactions as likes with the emoji in these fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(rename = "_misskey_reaction", skip_serializing_if = "Option::is_none")]
+    pub misskey_reaction: Option<String>,
+    #[serde(
+        deserialize_with = "deserialize_one_or_many",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tag: Vec<EmojiTag>,
 }
 
 impl Like {
@@ -36,9 +48,19 @@ impl Like {
             object: note.id().into(),
             kind: Default::default(),
             id,
+            content: None,
+            misskey_reaction: None,
+            tag: vec![],
         }
     }
 
+    pub fn reaction(&self) -> Option<&str> {
+        self.misskey_reaction
+            .as_deref()
+            .or(self.content.as_deref())
+            .filter(|reaction| !reaction.trim().is_empty())
+    }
+
     pub async fn send(
         actor: &ApUser,
         author: &ApUser,
@@ -80,6 +102,17 @@ impl ActivityHandler for Like {
         let actor = self.actor.dereference(data).await?;
         let post = self.object.dereference(data).await?;
 
+        if let Some(reaction) = self.reaction() {
+            // Reactions with custom emojis we couldn't resolve are treated as plain likes
+            if let Some((name, emoji)) =
+                reactions::resolve_incoming(reaction, &self.tag, &actor.instance, &data.db_pool)
+                    .await?
+            {
+                return reactions::receive(&self.id, &actor, &post, name, emoji, &data.db_pool)
+                    .await;
+            }
+        }
+
         if PostLike::create(Some(self.id.to_string()), &post, &actor, &data.db_pool).await? {
             notifications::process_like(
                 &post,
@@ -10,8 +10,11 @@ use web::AppState;
 pub mod accept;
 pub mod announce;
 pub mod create;
+pub mod delete;
+pub mod emoji_react;
 pub mod follow;
 pub mod like;
+pub mod move_account;
 pub mod reject;
 pub mod undo;
 pub mod update;
@@ -25,12 +28,18 @@ pub enum Inbox {
     AcceptFollow(accept::follow::AcceptFollow),
     UndoFollow(undo::follow::UndoFollow),
     RejectFollow(reject::follow::RejectFollow),
+    /// `Create` of both `Note` and `Article`
     CreateNote(create::note::CreateNote),
     Like(like::Like),
     UndoLike(undo::like::UndoLike),
+    EmojiReact(emoji_react::EmojiReact),
+    UndoEmojiReact(undo::emoji_react::UndoEmojiReact),
     Announce(announce::Announce),
     UndoAnnounce(undo::announce::UndoAnnounce),
     Update(update::Update),
+    UpdateNote(update::UpdateNote),
+    Move(move_account::Move),
+    DeleteUser(delete::DeleteUser),
 }
 
 pub fn generate_activity_id<T>(ap_id: &str, kind: T) -> Result<Url, ParseError>
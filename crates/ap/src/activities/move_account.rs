@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use activitypub_federation::{
+    config::Data,
+    fetch::object_id::ObjectId,
+    kinds::activity::MoveType,
+    traits::{ActivityHandler, Actor},
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use web::AppState;
+
+use crate::{
+    activities::{generate_activity_id, is_duplicate},
+    common::{delivery::queue_activity, follows},
+    objects::user::ApUser,
+};
+
+/// Sent by an account that has moved to `target`, like in Mastodon
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Move {
+    pub actor: ObjectId<ApUser>,
+    pub object: ObjectId<ApUser>,
+    pub target: ObjectId<ApUser>,
+    #[serde(rename = "type")]
+    pub kind: MoveType,
+    pub id: Url,
+}
+
+impl Move {
+    pub async fn send(
+        actor: &ApUser,
+        target: &ApUser,
+        data: &Data<Arc<AppState>>,
+    ) -> anyhow::Result<Url> {
+        let id = generate_activity_id(&actor.ap_id, MoveType::Move)?;
+        let activity = Move {
+            actor: actor.id().into(),
+            object: actor.id().into(),
+            target: target.id().into(),
+            kind: Default::default(),
+            id: id.clone(),
+        };
+
+        let inboxes = actor
+            .reached_inboxes(&data.db_pool)
+            .await?
+            .into_iter()
+            .map(|inbox| Url::parse(&inbox))
+            .collect::<Result<Vec<Url>, url::ParseError>>()?;
+        queue_activity(&activity, actor, inboxes, data).await?;
+
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for Move {
+    type DataType = Arc<AppState>;
+    type Error = anyhow::Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        self.actor.inner()
+    }
+
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if self.actor != self.object {
+            return Err(anyhow!("Invalid Move activity..."));
+        }
+
+        Ok(())
+    }
+
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if is_duplicate(&self.id, data).await? {
+            return Ok(());
+        }
+
+        let actor = self.actor.dereference(data).await?;
+        // The target is fetched again, as it has to list the actor as an alias by now
+        let target = self.target.dereference_forced(data).await?;
+        if !target.also_known_as().contains(&actor.ap_id) {
+            return Err(anyhow!(
+                "Move target {} doesn't have {} as an alias",
+                target.ap_id,
+                actor.ap_id
+            ));
+        }
+
+        follows::move_followers(&actor, &target, data).await?;
+
+        Ok(())
+    }
+}
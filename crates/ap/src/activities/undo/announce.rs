@@ -11,7 +11,7 @@ use web::AppState;
 
 use crate::{
     activities::{announce::Announce, is_duplicate},
-    common::notifications,
+    common::{notifications, streaming},
     objects::user::ApUser,
 };
 
@@ -58,7 +58,8 @@ impl ActivityHandler for UndoAnnounce {
         let actor = self.actor.dereference(data).await?;
         let post = self.object.object.dereference(data).await?;
 
-        if PostBoost::delete(&actor, &post, &data.db_pool).await? {
+        if let Some(boost) = PostBoost::delete(&actor, &post, &data.db_pool).await? {
+            streaming::process_unboost(&boost, &post, &data.db_pool).await?;
             notifications::process_boost(
                 &post,
                 &actor,
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use activitypub_federation::{
+    config::Data,
+    fetch::object_id::ObjectId,
+    kinds::activity::UndoType,
+    traits::{ActivityHandler, Actor},
+};
+use async_trait::async_trait;
+use db::models::CustomEmoji;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use web::AppState;
+
+use crate::{
+    activities::{
+        emoji_react::{EmojiReact, EmojiReactType},
+        generate_undo_activity_id, is_duplicate,
+    },
+    common::{delivery::queue_activity, reactions},
+    objects::{note::ApNote, user::ApUser},
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoEmojiReact {
+    pub actor: ObjectId<ApUser>,
+    pub object: EmojiReact,
+    #[serde(rename = "type")]
+    pub kind: UndoType,
+    pub id: Url,
+}
+
+impl UndoEmojiReact {
+    pub async fn send(
+        react_id: Url,
+        actor: &ApUser,
+        author: &ApUser,
+        note: &ApNote,
+        name: &str,
+        emoji: Option<&CustomEmoji>,
+        data: &Data<Arc<AppState>>,
+    ) -> anyhow::Result<Url> {
+        let id = generate_undo_activity_id(&actor.ap_id, EmojiReactType::EmojiReact)?;
+        let activity = UndoEmojiReact {
+            actor: actor.id().into(),
+            object: EmojiReact::new(react_id, actor, note, name, emoji)?,
+            kind: Default::default(),
+            id: id.clone(),
+        };
+
+        let inboxes = vec![author.shared_inbox_or_inbox()];
+        queue_activity(&activity, actor, inboxes, data).await?;
+
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for UndoEmojiReact {
+    type DataType = Arc<AppState>;
+    type Error = anyhow::Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        self.actor.inner()
+    }
+
+    async fn verify(&self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        let actor_undo = self.actor.dereference(data).await?;
+        let actor_react = self.object.actor.dereference(data).await?;
+
+        if actor_undo.id != actor_react.id {
+            return Err(anyhow::anyhow!("Invalid Undo activity..."));
+        }
+
+        self.object.verify(data).await?;
+        Ok(())
+    }
+
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if is_duplicate(&self.id, data).await? {
+            return Ok(());
+        }
+
+        let actor = self.actor.dereference(data).await?;
+        reactions::receive_undo(&self.object.id, &actor, &data.db_pool).await?;
+
+        Ok(())
+    }
+}
@@ -1,7 +1,6 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::{FollowType, UndoType},
@@ -16,7 +15,7 @@ use web::AppState;
 
 use crate::{
     activities::{follow::Follow, generate_undo_activity_id, is_duplicate},
-    common::notifications,
+    common::{delivery::queue_activity, notifications},
     objects::user::ApUser,
 };
 
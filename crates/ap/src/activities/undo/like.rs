@@ -1,7 +1,6 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::{LikeType, UndoType},
@@ -15,7 +14,7 @@ use web::AppState;
 
 use crate::{
     activities::{generate_undo_activity_id, is_duplicate, like::Like},
-    common::notifications,
+    common::{delivery::queue_activity, notifications, reactions},
     objects::{note::ApNote, user::ApUser},
 };
 
@@ -83,6 +82,10 @@ impl ActivityHandler for UndoLike {
         }
 
         let actor = self.actor.dereference(data).await?;
+        if reactions::receive_undo(&self.object.id, &actor, &data.db_pool).await? {
+            return Ok(());
+        }
+
         let post = self.object.object.dereference(data).await?;
 
         if PostLike::delete(
@@ -1,3 +1,4 @@
 pub mod announce;
+pub mod emoji_react;
 pub mod follow;
 pub mod like;
@@ -16,7 +16,11 @@ use web::AppState;
 
 use crate::{
     activities::is_duplicate,
-    objects::user::{ApUser, Person},
+    common::streaming,
+    objects::{
+        note::{ApNote, Note},
+        user::{ApUser, Person},
+    },
     PUBLIC,
 };
 
@@ -81,3 +85,54 @@ impl ActivityHandler for Update {
         Ok(())
     }
 }
+
+/// `Update` of a `Note` or an `Article`, sent when a post is edited
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNote {
+    pub actor: ObjectId<ApUser>,
+    pub object: Note,
+    #[serde(rename = "type")]
+    pub kind: UpdateType,
+    pub id: Url,
+}
+
+#[async_trait]
+impl ActivityHandler for UpdateNote {
+    type DataType = Arc<AppState>;
+    type Error = anyhow::Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        self.actor.inner()
+    }
+
+    async fn verify(&self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if self.actor != self.object.attributed_to {
+            return Err(anyhow::anyhow!("Invalid Update activity..."));
+        }
+
+        ApNote::verify(&self.object, self.actor.inner(), data).await?;
+        Ok(())
+    }
+
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if is_duplicate(&self.id, data).await? {
+            return Ok(());
+        }
+        // Edits of posts that were never received are of no interest
+        if ApNote::read_from_id(self.object.id.inner().clone(), data)
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        let note = ApNote::from_json(self.object, data).await?;
+        streaming::process_post_update(&note, &data.db_pool).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    io::{Cursor, Write},
+    sync::Arc,
+};
+
+use activitypub_federation::{config::Data, protocol::context::WithContext, traits::Object};
+use db::models::{Post, User};
+use serde::Serialize;
+use serde_json::json;
+use web::AppState;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    activities::create::note::CreateNote,
+    objects::{
+        announce::{Announce, ApAnnounce},
+        note::ApNote,
+        user::ApUser,
+    },
+};
+
+const ACTIVITYSTREAMS_CONTEXT: &str = "https://www.w3.org/ns/activitystreams";
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ArchiveActivity {
+    Create(CreateNote),
+    Announce(Announce),
+}
+
+/// Collection of the archive, identified by its file name like in Mastodon
+fn collection<T: Serialize>(file: &str, items: Vec<T>) -> serde_json::Value {
+    json!({
+        "@context": ACTIVITYSTREAMS_CONTEXT,
+        "id": file,
+        "type": "OrderedCollection",
+        "totalItems": items.len(),
+        "orderedItems": items,
+    })
+}
+
+fn post_ids(posts: Vec<Post>) -> Vec<String> {
+    posts.into_iter().map(|post| post.ap_id).collect()
+}
+
+/// ZIP archive of the user's account with the same layout as Mastodon's: `actor.json`,
+/// `outbox.json` with `Create` and `Announce` activities, `likes.json` and `bookmarks.json`.
+/// Cryap doesn't store uploaded media, so there are no media files to include
+pub async fn build(user: &User, data: &Data<Arc<AppState>>) -> anyhow::Result<Vec<u8>> {
+    let actor = WithContext::new_default(ApUser(user.clone()).into_json(data).await?);
+
+    let mut activities = vec![];
+    for post in user.all_posts(&data.db_pool).await? {
+        let published = post.published;
+        let note = ApNote(post).into_json(data).await?;
+        activities.push((published, ArchiveActivity::Create(CreateNote::from(note))));
+    }
+    for boost in user.all_boosts(&data.db_pool).await? {
+        let published = boost.published;
+        let announce = ApAnnounce(boost).into_json(data).await?;
+        activities.push((published, ArchiveActivity::Announce(announce)));
+    }
+    activities.sort_by_key(|(published, _)| *published);
+    let outbox = collection(
+        "outbox.json",
+        activities
+            .into_iter()
+            .map(|(_, activity)| activity)
+            .collect(),
+    );
+
+    let likes = collection(
+        "likes.json",
+        post_ids(user.all_liked_posts(&data.db_pool).await?),
+    );
+    let bookmarks = collection(
+        "bookmarks.json",
+        post_ids(user.all_bookmarked_posts(&data.db_pool).await?),
+    );
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (file, content) in [
+        ("actor.json", serde_json::to_vec_pretty(&actor)?),
+        ("outbox.json", serde_json::to_vec_pretty(&outbox)?),
+        ("likes.json", serde_json::to_vec_pretty(&likes)?),
+        ("bookmarks.json", serde_json::to_vec_pretty(&bookmarks)?),
+    ] {
+        zip.start_file(file, options)?;
+        zip.write_all(&content)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
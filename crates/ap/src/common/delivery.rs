@@ -0,0 +1,119 @@
+use std::{collections::HashSet, sync::Arc};
+
+use activitypub_federation::{
+    activity_sending::SendActivityTask, config::Data, traits::ActivityHandler,
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use db::models::{OutgoingDelivery, User};
+use lazy_static::lazy_static;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use tokio::sync::Notify;
+use url::Url;
+use web::AppState;
+
+use crate::objects::user::ApUser;
+
+lazy_static! {
+    /// Wakes the delivery worker of the API up when there's something new to send
+    pub static ref NEW_DELIVERIES: Notify = Notify::new();
+}
+
+/// Stores the activity for every remote inbox, the delivery worker sends it from there. Unlike
+/// the queue of `activitypub_federation`, deliveries survive restarts
+pub async fn queue_activity<Activity: Serialize>(
+    activity: &Activity,
+    actor: &ApUser,
+    inboxes: Vec<Url>,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    let mut seen = HashSet::new();
+    let inboxes = inboxes
+        .into_iter()
+        .filter(|inbox| seen.insert(inbox.clone()))
+        .filter_map(|inbox| {
+            let instance = inbox.domain()?.to_string();
+            (instance != data.domain()).then(|| (inbox.to_string(), instance))
+        })
+        .collect::<Vec<(String, String)>>();
+    if inboxes.is_empty() {
+        return Ok(());
+    }
+
+    OutgoingDelivery::create(
+        &actor.id,
+        inboxes,
+        serde_json::to_value(activity)?,
+        &data.db_pool,
+    )
+    .await?;
+    NEW_DELIVERIES.notify_one();
+
+    Ok(())
+}
+
+/// Activity as it was queued, it's only ever sent
+#[derive(Debug)]
+struct QueuedActivity {
+    id: Url,
+    actor: Url,
+    activity: Value,
+}
+
+impl Serialize for QueuedActivity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.activity.serialize(serializer)
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for QueuedActivity {
+    type DataType = Arc<AppState>;
+    type Error = anyhow::Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        Err(anyhow!("Queued activities can't be received"))
+    }
+
+    async fn receive(self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        Err(anyhow!("Queued activities can't be received"))
+    }
+}
+
+/// Signs the activity and sends it to the inbox. An error means that it should be tried again,
+/// inboxes that reject the activity aren't, as in `activitypub_federation`
+pub async fn deliver(
+    delivery: &OutgoingDelivery,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    let actor = match User::by_id(&delivery.actor_id, &data.db_pool).await? {
+        Some(user) => ApUser(user),
+        None => return Err(anyhow!("The actor doesn't exist anymore")),
+    };
+    let id = delivery
+        .activity
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("The activity has no ID"))?;
+    let activity = QueuedActivity {
+        id: Url::parse(id)?,
+        actor: Url::parse(&actor.ap_id)?,
+        activity: delivery.activity.clone(),
+    };
+
+    let inbox = Url::parse(&delivery.inbox)?;
+    for task in SendActivityTask::prepare(&activity, &actor, vec![inbox], data).await? {
+        task.sign_and_send(data).await?;
+    }
+
+    Ok(())
+}
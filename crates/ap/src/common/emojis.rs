@@ -0,0 +1,122 @@
+use chrono::Utc;
+use db::{
+    models::{CustomEmoji, UserEmoji},
+    types::DbId,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::objects::emoji::Emoji;
+
+/// Remote objects may contain many emojis, but there has to be some limit
+const MAX_REMOTE_EMOJIS: usize = 50;
+
+lazy_static! {
+    static ref SHORTCODE_RE: Regex = Regex::new(r":(?P<shortcode>[\w+\-]{1,100}):").unwrap();
+    static ref VALID_SHORTCODE_RE: Regex = Regex::new(r"^[\w+\-]{1,100}$").unwrap();
+}
+
+/// Shortcodes (without colons) used in the texts, without duplicates
+pub fn shortcodes(texts: &[&str]) -> Vec<String> {
+    let mut shortcodes: Vec<String> = vec![];
+    for text in texts {
+        for captures in SHORTCODE_RE.captures_iter(text) {
+            let shortcode = captures["shortcode"].to_string();
+            if !shortcodes.contains(&shortcode) {
+                shortcodes.push(shortcode);
+            }
+        }
+    }
+
+    shortcodes
+}
+
+/// Local emojis used in the texts
+pub async fn local(
+    texts: &[&str],
+    domain: &str,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Vec<CustomEmoji>> {
+    CustomEmoji::by_shortcodes(&shortcodes(texts), domain, db_pool).await
+}
+
+/// Same as [`local`], but in the form stored on users
+pub async fn local_for_user(
+    texts: &[&str],
+    domain: &str,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<serde_json::Value> {
+    let emojis: Vec<UserEmoji> = local(texts, domain, db_pool)
+        .await?
+        .iter()
+        .map(UserEmoji::from)
+        .collect();
+    Ok(serde_json::to_value(emojis)?)
+}
+
+/// Caches emojis received from `instance`. Emojis are always stored under the instance of the
+/// object they came with, so that a server can't replace emojis of other servers
+pub async fn save_remote(
+    tags: Vec<&Emoji>,
+    instance: &str,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Vec<CustomEmoji>> {
+    let mut emojis = vec![];
+    for tag in tags.into_iter().take(MAX_REMOTE_EMOJIS) {
+        let (shortcode, icon) = match (tag.shortcode(), &tag.icon) {
+            (Some(shortcode), Some(icon)) => (shortcode, icon),
+            _ => continue,
+        };
+        if !VALID_SHORTCODE_RE.is_match(shortcode)
+            || !matches!(icon.url.scheme(), "http" | "https")
+            || icon.url.as_str().len() > 500
+        {
+            continue;
+        }
+
+        let ap_id = tag.id.as_ref().unwrap_or(&icon.url).to_string();
+        if ap_id.len() > 200 {
+            continue;
+        }
+
+        emojis.push(
+            CustomEmoji::upsert_remote(
+                CustomEmoji {
+                    id: DbId::default(),
+                    shortcode: shortcode.to_string(),
+                    instance: instance.to_string(),
+                    local: false,
+                    ap_id,
+                    image_url: icon.url.to_string(),
+                    media_type: icon
+                        .media_type
+                        .clone()
+                        .filter(|media_type| media_type.len() <= 100),
+                    category: None,
+                    visible_in_picker: true,
+                    published: Utc::now(),
+                    updated: Some(tag.updated.unwrap_or(Utc::now())),
+                },
+                db_pool,
+            )
+            .await?,
+        );
+    }
+
+    Ok(emojis)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::emojis::shortcodes;
+
+    #[test]
+    fn shortcode_matching() {
+        assert_eq!(
+            shortcodes(&["Hello :blobcat: and :blob_fox::neocat:", ":blobcat: again"]),
+            vec!["blobcat", "blob_fox", "neocat"]
+        );
+        assert!(shortcodes(&["It's 12:30 now", ": not an emoji :"]).is_empty());
+    }
+}
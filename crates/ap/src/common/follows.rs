@@ -1,7 +1,15 @@
 use std::sync::Arc;
 
 use activitypub_federation::config::Data;
-use ap::{
+use db::models::{
+    user::{User, UserUpdate},
+    user_follow_request::UserFollowRequest,
+    user_follower::UserFollower,
+};
+use url::Url;
+use web::AppState;
+
+use crate::{
     activities::{
         accept::follow::AcceptFollow, follow::Follow, reject::follow::RejectFollow,
         undo::follow::UndoFollow,
@@ -9,9 +17,6 @@ use ap::{
     common::notifications,
     objects::user::ApUser,
 };
-use db::models::{user::User, user_follow_request::UserFollowRequest, user_follower::UserFollower};
-use url::Url;
-use web::AppState;
 
 pub async fn want_to_follow(
     by: &User,
@@ -114,3 +119,46 @@ pub async fn remove_from_followers(
 
     Ok(())
 }
+
+/// Marks `from` as moved to `to` and makes local followers of `from` follow `to` instead
+pub async fn move_followers(
+    from: &User,
+    to: &User,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    from.update(
+        UserUpdate {
+            moved_to_id: Some(Some(to.id.clone())),
+            ..Default::default()
+        },
+        &data.db_pool,
+    )
+    .await?;
+
+    for follower in from.local_followers(&data.db_pool).await? {
+        if follower.id == to.id {
+            continue;
+        }
+
+        let result = async {
+            if !follower.follows(to, &data.db_pool).await?
+                && !follower.wants_to_follow(to, &data.db_pool).await?
+            {
+                want_to_follow(&follower, to, data).await?;
+            }
+            unfollow(&follower, from, data).await
+        }
+        .await;
+        if let Err(err) = result {
+            log::error!(
+                "Failed to move {} from {} to {}: {:?}",
+                follower.ap_id,
+                from.ap_id,
+                to.ap_id,
+                err
+            );
+        }
+    }
+
+    Ok(())
+}
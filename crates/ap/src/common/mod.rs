@@ -1,3 +1,10 @@
+pub mod archive;
+pub mod delivery;
+pub mod emojis;
+pub mod follows;
 pub mod nodeinfo;
 pub mod notifications;
+pub mod profile_fields;
+pub mod push;
+pub mod reactions;
 pub mod streaming;
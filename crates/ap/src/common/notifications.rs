@@ -1,10 +1,21 @@
 use db::{
     models::{Notification, Post, User},
-    types::DbNotificationType,
+    types::{DbId, DbNotificationType},
 };
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
 
-use crate::common::streaming::{StreamingEvent, EVENT_BUS};
+use crate::common::{
+    push::PUSH_QUEUE,
+    streaming::{StreamingEvent, EVENT_BUS},
+};
+
+/// Streams the new notification to the receiver and queues it for Web Push
+async fn deliver(receiver_id: &DbId, notification: Notification) {
+    PUSH_QUEUE.push(notification.clone());
+    EVENT_BUS
+        .send(receiver_id, StreamingEvent::notification(notification))
+        .await;
+}
 
 pub async fn process_follow(
     by: &User,
@@ -21,9 +32,7 @@ pub async fn process_follow(
     } else {
         let notification =
             Notification::create(by, to, None, DbNotificationType::Follow, db_pool).await?;
-        EVENT_BUS
-            .send(&to.id, StreamingEvent::notification(notification))
-            .await;
+        deliver(&to.id, notification).await;
     }
 
     Ok(())
@@ -44,9 +53,7 @@ pub async fn process_follow_request(
     } else {
         let notification =
             Notification::create(by, to, None, DbNotificationType::FollowRequest, db_pool).await?;
-        EVENT_BUS
-            .send(&to.id, StreamingEvent::notification(notification))
-            .await;
+        deliver(&to.id, notification).await;
     }
 
     Ok(())
@@ -62,9 +69,25 @@ pub async fn process_post(post: &Post, db_pool: &Pool<AsyncPgConnection>) -> any
             db_pool,
         )
         .await?;
-        EVENT_BUS
-            .send(&user.id, StreamingEvent::notification(notification))
-            .await;
+        deliver(&user.id, notification).await;
+    }
+
+    if let Some(quote) = &post.quote {
+        let quote_author = match Post::by_id(quote, db_pool).await? {
+            Some(quote) => quote.author(db_pool).await?,
+            None => return Ok(()),
+        };
+        if quote_author.local && quote_author.id != post.author {
+            let notification = Notification::create_by_ids(
+                post.author.clone(),
+                quote_author.id.clone(),
+                Some(post.id.clone()),
+                DbNotificationType::Quote,
+                db_pool,
+            )
+            .await?;
+            deliver(&quote_author.id, notification).await;
+        }
     }
 
     Ok(())
@@ -99,9 +122,7 @@ pub async fn process_like(
             db_pool,
         )
         .await?;
-        EVENT_BUS
-            .send(&author.id, StreamingEvent::notification(notification))
-            .await;
+        deliver(&author.id, notification).await;
     }
 
     Ok(())
@@ -124,9 +145,30 @@ pub async fn process_boost(
         let notification =
             Notification::create(by, author, Some(post), DbNotificationType::Reblog, db_pool)
                 .await?;
-        EVENT_BUS
-            .send(&author.id, StreamingEvent::notification(notification))
-            .await;
+        deliver(&author.id, notification).await;
+    }
+
+    Ok(())
+}
+
+pub async fn process_reaction(
+    post: &Post,
+    by: &User,
+    author: &User,
+    reaction: &str,
+    do_opposite: bool,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    if !author.local || by.id == author.id {
+        return Ok(());
+    }
+
+    if do_opposite {
+        Notification::delete_reaction(by, author, post, reaction, db_pool).await?;
+    } else {
+        let notification =
+            Notification::create_reaction(by, author, post, reaction, db_pool).await?;
+        deliver(&author.id, notification).await;
     }
 
     Ok(())
@@ -0,0 +1,370 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use chrono::Utc;
+use db::{
+    models::{User, UserField},
+    types::DbId,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::{header::LOCATION, redirect::Policy};
+use url::{Host, Url};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_PAGE_SIZE: usize = 1024 * 1024;
+const MAX_REDIRECTS: usize = 3;
+/// Fields of a user are verified at most this often, however often their profile is updated
+const VERIFICATION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+lazy_static! {
+    static ref LAST_VERIFICATIONS: Mutex<HashMap<DbId, Instant>> = Mutex::new(HashMap::new());
+    static ref LINK_TAG_RE: Regex = Regex::new(r"(?i)<(?:a|link)\s[^>]*>").unwrap();
+    static ref REL_RE: Regex =
+        Regex::new(r#"(?i)\srel\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap();
+    static ref HREF_RE: Regex =
+        Regex::new(r#"(?i)\shref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap();
+    static ref HTML_TAG_RE: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// Link the field points to. Values of remote users are HTML, so tags are stripped first
+pub fn field_url(value: &str) -> Option<Url> {
+    let text = HTML_TAG_RE.replace_all(value, "");
+    let text = html_escape::decode_html_entities(text.trim());
+    let url = Url::parse(&text).ok()?;
+    match url.scheme() {
+        "http" | "https" => Some(url),
+        _ => None,
+    }
+}
+
+/// Renders a field value of a local user to HTML, turning links into `rel="me"` anchors
+pub fn value_to_html(value: &str) -> String {
+    let text = html_escape::encode_text(value);
+    match field_url(value) {
+        Some(url) => format!(
+            "<a href=\"{}\" target=\"_blank\" rel=\"nofollow noopener noreferrer me\" translate=\"no\">{}</a>",
+            html_escape::encode_double_quoted_attribute(url.as_str()),
+            text
+        ),
+        None => text.to_string(),
+    }
+}
+
+fn attribute(tag: &str, attribute_re: &Regex) -> Option<String> {
+    attribute_re
+        .captures(tag)
+        .and_then(|captures| captures.get(1).or(captures.get(2)).or(captures.get(3)))
+        .map(|value| html_escape::decode_html_entities(value.as_str()).to_string())
+}
+
+/// Whether the page contains a `rel="me"` link pointing to `profile_url`
+pub fn links_back(html: &str, profile_url: &str) -> bool {
+    LINK_TAG_RE.find_iter(html).any(|tag| {
+        let tag = tag.as_str();
+        let is_me = attribute(tag, &REL_RE)
+            .map(|rel| {
+                rel.split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("me"))
+            })
+            .unwrap_or(false);
+        is_me
+            && attribute(tag, &HREF_RE)
+                .map(|href| href.trim_end_matches('/') == profile_url.trim_end_matches('/'))
+                .unwrap_or(false)
+    })
+}
+
+/// Whether the address is reachable from the internet. Links to anything else aren't fetched, so
+/// that remote users can't make the server probe its own network
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || first == 0
+                // Shared address space, 100.64.0.0/10
+                || (first == 100 && second & 0xc0 == 64))
+        },
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local, fc00::/7
+                    || first & 0xfe00 == 0xfc00
+                    // Link-local, fe80::/10
+                    || first & 0xffc0 == 0xfe80)
+            },
+        },
+    }
+}
+
+/// Resolves the host of the link to an address that may be fetched. Requests should be sent to
+/// this address instead of looking the host up again
+pub async fn resolve(url: &Url, allow_private_addresses: bool) -> anyhow::Result<SocketAddr> {
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| anyhow!("Link has no port"))?;
+    let addresses: Vec<SocketAddr> = match url.host() {
+        Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port)).await?.collect(),
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+        None => return Err(anyhow!("Link has no host")),
+    };
+
+    addresses
+        .into_iter()
+        .find(|address| allow_private_addresses || is_public(address.ip()))
+        .ok_or_else(|| anyhow!("Link doesn't point to a public address"))
+}
+
+/// Fetches the page, following redirects by hand so that every hop is checked. Connections go to
+/// the checked address, the host isn't looked up again
+async fn fetch_page(mut url: Url, allow_private_addresses: bool) -> anyhow::Result<String> {
+    for _ in 0..=MAX_REDIRECTS {
+        let address = resolve(&url, allow_private_addresses).await?;
+        let mut client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .user_agent(format!("Cryap/{}", env!("CARGO_PKG_VERSION")))
+            .redirect(Policy::none());
+        if let Some(domain) = url.domain() {
+            client = client.resolve(domain, address);
+        }
+
+        let mut response = client.build()?.get(url.clone()).send().await?;
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .ok_or_else(|| anyhow!("Redirect has no location"))?;
+            url = url.join(location)?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(anyhow!("Redirect to an unsupported scheme"));
+            }
+            continue;
+        }
+
+        response = response.error_for_status()?;
+        let mut page = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            page.extend_from_slice(&chunk);
+            if page.len() > MAX_PAGE_SIZE {
+                return Err(anyhow!("Linked page is too big"));
+            }
+        }
+        return Ok(String::from_utf8_lossy(&page).to_string());
+    }
+
+    Err(anyhow!("Too many redirects"))
+}
+
+fn is_unchanged(field: &UserField, previous_fields: &[UserField]) -> bool {
+    previous_fields
+        .iter()
+        .any(|previous| previous.name == field.name && previous.value == field.value)
+}
+
+/// Sets `verified_at` on unverified fields whose pages link back to `profile_url`. Fields that
+/// are in `previous_fields` with the same name and value were already checked and are skipped.
+/// Returns `true` if any field was verified
+pub async fn verify_fields(
+    fields: &mut [UserField],
+    previous_fields: &[UserField],
+    profile_url: &str,
+    allow_private_addresses: bool,
+) -> bool {
+    let mut verified_any = false;
+    for field in fields.iter_mut() {
+        if field.verified_at.is_some() || is_unchanged(field, previous_fields) {
+            continue;
+        }
+
+        let url = match field_url(&field.value) {
+            Some(url) => url,
+            None => continue,
+        };
+
+        match fetch_page(url, allow_private_addresses).await {
+            Ok(page) if links_back(&page, profile_url) => {
+                field.verified_at = Some(Utc::now());
+                verified_any = true;
+            },
+            Ok(_) => {},
+            Err(err) => log::debug!("Failed to fetch profile field link: {:?}", err),
+        }
+    }
+
+    verified_any
+}
+
+/// Whether the fields of the user may be verified now, remembering the verification if so
+fn claim_verification(user_id: &DbId) -> bool {
+    let now = Instant::now();
+    let mut last_verifications = LAST_VERIFICATIONS.lock().unwrap();
+    last_verifications.retain(|_, verified_at| now - *verified_at < VERIFICATION_INTERVAL);
+    if last_verifications.contains_key(user_id) {
+        return false;
+    }
+
+    last_verifications.insert(user_id.clone(), now);
+    true
+}
+
+/// Verifies fields that were added or changed since `previous_fields`
+pub async fn verify(
+    user: &User,
+    previous_fields: &[UserField],
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    let mut fields = user.fields();
+    let changed = fields
+        .iter()
+        .any(|field| field.verified_at.is_none() && !is_unchanged(field, previous_fields));
+    if !changed || !claim_verification(&user.id) {
+        return Ok(());
+    }
+
+    if verify_fields(&mut fields, previous_fields, &user.ap_id, false).await {
+        // If the user has changed their fields in the meantime, the result is simply dropped
+        user.replace_fields(&fields, db_pool).await?;
+    }
+
+    Ok(())
+}
+
+/// Verifies fields in the background so that profile updates aren't slowed down by it
+pub fn spawn_verification(
+    user: User,
+    previous_fields: Vec<UserField>,
+    db_pool: &Pool<AsyncPgConnection>,
+) {
+    let db_pool = db_pool.clone();
+    tokio::spawn(async move {
+        if let Err(err) = verify(&user, &previous_fields, &db_pool).await {
+            log::error!(
+                "Failed to verify profile fields of {}: {:?}",
+                user.ap_id,
+                err
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+
+    use axum::{response::Html, routing::get, Router};
+    use db::models::UserField;
+
+    use crate::common::profile_fields::{is_public, links_back, value_to_html, verify_fields};
+
+    const PROFILE_URL: &str = "https://cryap.example/u/vector1dev";
+
+    fn field(value: String) -> UserField {
+        UserField {
+            name: String::from("Website"),
+            value,
+            verified_at: None,
+        }
+    }
+
+    #[test]
+    fn rel_me_links() {
+        assert!(links_back(
+            "<a href=\"https://cryap.example/u/vector1dev\" rel=\"me\">Fediverse</a>",
+            PROFILE_URL
+        ));
+        assert!(links_back(
+            "<link rel='me nofollow' href='https://cryap.example/u/vector1dev/'>",
+            PROFILE_URL
+        ));
+        assert!(!links_back(
+            "<a href=\"https://cryap.example/u/vector1dev\">Fediverse</a>",
+            PROFILE_URL
+        ));
+        assert!(!links_back(
+            "<a href=\"https://cryap.example/u/someone\" rel=\"me\">Fediverse</a>",
+            PROFILE_URL
+        ));
+    }
+
+    #[test]
+    fn html_values() {
+        assert_eq!(value_to_html("<b>cat</b>"), "&lt;b&gt;cat&lt;/b&gt;");
+        assert!(
+            value_to_html("https://example.com").starts_with("<a href=\"https://example.com/\"")
+        );
+    }
+
+    #[test]
+    fn public_addresses() {
+        let is_public = |address: &str| is_public(address.parse::<IpAddr>().unwrap());
+        assert!(is_public("93.184.216.34"));
+        assert!(is_public("2606:2800:220:1::1"));
+        assert!(!is_public("127.0.0.1"));
+        assert!(!is_public("10.1.2.3"));
+        assert!(!is_public("192.168.0.1"));
+        assert!(!is_public("169.254.169.254"));
+        assert!(!is_public("100.64.0.1"));
+        assert!(!is_public("0.0.0.0"));
+        assert!(!is_public("::1"));
+        assert!(!is_public("fd00::1"));
+        assert!(!is_public("fe80::1"));
+        assert!(!is_public("::ffff:127.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn verification() {
+        let app = Router::new()
+            .route(
+                "/verified",
+                get(|| async {
+                    Html(format!(
+                        "<html><head><link rel=\"me\" href=\"{PROFILE_URL}\"></head></html>"
+                    ))
+                }),
+            )
+            .route(
+                "/unverified",
+                get(|| async { Html("<html><body>Nothing here</body></html>") }),
+            );
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let address = server.local_addr();
+        tokio::spawn(server);
+
+        let mut fields = vec![
+            field(format!("http://{address}/verified")),
+            field(format!("http://{address}/unverified")),
+            field(format!("http://{address}/missing")),
+            field(String::from("Not a link")),
+        ];
+        // The test server is on the loopback address, which is refused unless explicitly allowed
+        assert!(!verify_fields(&mut fields.clone(), &[], PROFILE_URL, false).await);
+        // Fields that haven't changed aren't checked again
+        assert!(!verify_fields(&mut fields.clone(), &fields, PROFILE_URL, true).await);
+        assert!(verify_fields(&mut fields, &[], PROFILE_URL, true).await);
+
+        assert!(fields[0].verified_at.is_some());
+        assert!(fields[1].verified_at.is_none());
+        assert!(fields[2].verified_at.is_none());
+        assert!(fields[3].verified_at.is_none());
+    }
+}
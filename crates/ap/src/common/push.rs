@@ -0,0 +1,38 @@
+use db::models::Notification;
+use lazy_static::lazy_static;
+use tokio::sync::{mpsc, Mutex};
+
+lazy_static! {
+    /// Notifications waiting to be sent as Web Push messages by the push worker of the API
+    pub static ref PUSH_QUEUE: PushQueue = PushQueue::new();
+}
+
+pub struct PushQueue {
+    sender: mpsc::UnboundedSender<Notification>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<Notification>>>,
+}
+
+impl PushQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    pub fn push(&self, notification: Notification) {
+        let _ = self.sender.send(notification);
+    }
+
+    /// There's a single push worker, only the first call gets the receiver
+    pub async fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<Notification>> {
+        self.receiver.lock().await.take()
+    }
+}
+
+impl Default for PushQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
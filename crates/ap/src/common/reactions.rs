@@ -0,0 +1,139 @@
+use chrono::Utc;
+use db::{
+    models::{CustomEmoji, Post, PostReaction, User},
+    types::DbId,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use url::Url;
+
+use crate::{
+    common::{emojis, notifications},
+    objects::emoji::EmojiTag,
+};
+
+/// Whether `name` looks like a single Unicode emoji. There is no list of all emojis here, so
+/// this only rules out things that are certainly not emojis
+pub fn is_unicode_emoji(name: &str) -> bool {
+    let length = name.chars().count();
+    length > 0
+        && length <= 16
+        && !name.is_ascii()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_alphabetic() || c == ':' || c == '@')
+}
+
+/// Name a reaction with a custom emoji is stored under, `shortcode@instance` for remote emojis
+pub fn custom_emoji_name(emoji: &CustomEmoji) -> String {
+    if emoji.local {
+        emoji.shortcode.clone()
+    } else {
+        format!("{}@{}", emoji.shortcode, emoji.instance)
+    }
+}
+
+/// Resolves `content` of an incoming reaction to its name and custom emoji, if it's one. Returns
+/// `None` for custom emojis that don't come with an `Emoji` tag
+pub async fn resolve_incoming(
+    content: &str,
+    tags: &[EmojiTag],
+    instance: &str,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Option<(String, Option<CustomEmoji>)>> {
+    let content = content.trim();
+    if is_unicode_emoji(content) {
+        return Ok(Some((content.to_string(), None)));
+    }
+
+    let shortcode = content.trim_matches(':');
+    let tags = EmojiTag::emojis(tags)
+        .into_iter()
+        .filter(|emoji| emoji.shortcode() == Some(shortcode))
+        .collect();
+    Ok(emojis::save_remote(tags, instance, db_pool)
+        .await?
+        .into_iter()
+        .next()
+        .map(|emoji| (custom_emoji_name(&emoji), Some(emoji))))
+}
+
+pub async fn receive(
+    ap_id: &Url,
+    actor: &User,
+    post: &Post,
+    name: String,
+    emoji: Option<CustomEmoji>,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    let created = PostReaction::create(
+        PostReaction {
+            id: DbId::default(),
+            ap_id: Some(ap_id.to_string()),
+            post_id: post.id.clone(),
+            actor_id: actor.id.clone(),
+            name: name.clone(),
+            emoji_id: emoji.map(|emoji| emoji.id),
+            published: Utc::now(),
+        },
+        db_pool,
+    )
+    .await?;
+
+    if created {
+        notifications::process_reaction(
+            post,
+            actor,
+            &post.author(db_pool).await?,
+            &name,
+            false,
+            db_pool,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Returns `false` if there is no reaction with such ID by the actor
+pub async fn receive_undo(
+    ap_id: &Url,
+    actor: &User,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<bool> {
+    let reaction = match PostReaction::by_ap_id(ap_id.as_str(), db_pool).await? {
+        Some(reaction) if reaction.actor_id == actor.id => reaction,
+        _ => return Ok(false),
+    };
+
+    if reaction.delete(db_pool).await? {
+        if let Some(post) = Post::by_id(&reaction.post_id, db_pool).await? {
+            notifications::process_reaction(
+                &post,
+                actor,
+                &post.author(db_pool).await?,
+                &reaction.name,
+                true,
+                db_pool,
+            )
+            .await?;
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::reactions::is_unicode_emoji;
+
+    #[test]
+    fn unicode_emojis() {
+        assert!(is_unicode_emoji("👍"));
+        assert!(is_unicode_emoji("❤️"));
+        assert!(is_unicode_emoji("👩‍👩‍👧"));
+        assert!(!is_unicode_emoji(":blobcat:"));
+        assert!(!is_unicode_emoji("like"));
+        assert!(!is_unicode_emoji("😀 😀"));
+        assert!(!is_unicode_emoji(""));
+    }
+}
@@ -1,20 +1,30 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+    time::Duration,
+};
 
 use db::{
-    models::{Notification, Post},
-    types::DbId,
+    common::timelines::TimelineEntry,
+    models::{Notification, Post, PostBoost, Tag},
+    types::{DbId, DbVisibility},
 };
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use futures::StreamExt;
 use lazy_static::lazy_static;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, RwLock};
 
 #[derive(Clone)]
 pub enum StreamingEvent {
     Update {
-        payload: Post,
+        payload: TimelineEntry,
         categories: Vec<StreamingCategory>,
     },
+    /// ID of the deleted post or boost
     Delete {
-        payload: Post,
+        payload: DbId,
         categories: Vec<StreamingCategory>,
     },
     Notification {
@@ -29,18 +39,190 @@ pub enum StreamingEvent {
         payload: Post,
         categories: Vec<StreamingCategory>,
     },
+    /// Closes the connections opened with the revoked sessions, isn't sent to clients
+    SessionsRevoked {
+        payload: Vec<DbId>,
+        categories: Vec<StreamingCategory>,
+    },
 }
 
 impl StreamingEvent {
+    pub fn update(entry: TimelineEntry) -> Self {
+        Self::Update {
+            payload: entry,
+            categories: vec![StreamingCategory::User],
+        }
+    }
+
+    pub fn delete(id: DbId) -> Self {
+        Self::Delete {
+            payload: id,
+            categories: vec![StreamingCategory::User],
+        }
+    }
+
+    pub fn status_update(post: Post) -> Self {
+        Self::StatusUpdate {
+            payload: post,
+            categories: vec![StreamingCategory::User],
+        }
+    }
+
     pub fn notification(notification: Notification) -> Self {
         Self::Notification {
             payload: notification,
             categories: vec![StreamingCategory::User, StreamingCategory::UserNotification],
         }
     }
+
+    pub fn sessions_revoked(session_ids: Vec<DbId>) -> Self {
+        Self::SessionsRevoked {
+            payload: session_ids,
+            categories: vec![],
+        }
+    }
+
+    pub fn categories(&self) -> &[StreamingCategory] {
+        match self {
+            Self::Update { categories, .. }
+            | Self::Delete { categories, .. }
+            | Self::Notification { categories, .. }
+            | Self::FiltersChanged { categories, .. }
+            | Self::StatusUpdate { categories, .. }
+            | Self::SessionsRevoked { categories, .. } => categories,
+        }
+    }
+
+    fn to_redis(&self) -> RedisEvent {
+        match self {
+            Self::Update { payload, .. } => match payload {
+                TimelineEntry::Post(post) => RedisEvent::Update {
+                    post_id: post.id.to_string(),
+                    boost_id: None,
+                },
+                TimelineEntry::Boost(boost, post) => RedisEvent::Update {
+                    post_id: post.id.to_string(),
+                    boost_id: Some(boost.id.to_string()),
+                },
+            },
+            Self::Delete { payload, .. } => RedisEvent::Delete {
+                id: payload.to_string(),
+            },
+            Self::Notification { payload, .. } => RedisEvent::Notification {
+                id: payload.id.to_string(),
+            },
+            Self::FiltersChanged { .. } => RedisEvent::FiltersChanged,
+            Self::StatusUpdate { payload, .. } => RedisEvent::StatusUpdate {
+                post_id: payload.id.to_string(),
+            },
+            Self::SessionsRevoked { payload, .. } => RedisEvent::SessionsRevoked {
+                session_ids: payload.iter().map(DbId::to_string).collect(),
+            },
+        }
+    }
+
+    /// Loads the records of an event received from Redis, `None` if they were deleted since
+    async fn from_redis(
+        event: RedisEvent,
+        categories: Vec<StreamingCategory>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        Ok(Some(match event {
+            RedisEvent::Update { post_id, boost_id } => {
+                let post = match Post::by_id(&DbId::from(post_id), db_pool).await? {
+                    Some(post) => post,
+                    None => return Ok(None),
+                };
+                let payload = match boost_id {
+                    Some(boost_id) => match PostBoost::by_id(&DbId::from(boost_id), db_pool).await?
+                    {
+                        Some(boost) => TimelineEntry::Boost(boost, post),
+                        None => return Ok(None),
+                    },
+                    None => TimelineEntry::Post(post),
+                };
+                Self::Update {
+                    payload,
+                    categories,
+                }
+            },
+            RedisEvent::Delete { id } => Self::Delete {
+                payload: DbId::from(id),
+                categories,
+            },
+            RedisEvent::Notification { id } => {
+                match Notification::by_id(&DbId::from(id), db_pool).await? {
+                    Some(notification) => Self::Notification {
+                        payload: notification,
+                        categories,
+                    },
+                    None => return Ok(None),
+                }
+            },
+            RedisEvent::FiltersChanged => Self::FiltersChanged {
+                payload: (),
+                categories,
+            },
+            RedisEvent::StatusUpdate { post_id } => {
+                match Post::by_id(&DbId::from(post_id), db_pool).await? {
+                    Some(post) => Self::StatusUpdate {
+                        payload: post,
+                        categories,
+                    },
+                    None => return Ok(None),
+                }
+            },
+            RedisEvent::SessionsRevoked { session_ids } => Self::SessionsRevoked {
+                payload: session_ids.into_iter().map(DbId::from).collect(),
+                categories,
+            },
+        }))
+    }
+}
+
+/// Event as it's published on Redis. Records are referenced by ID, every process loads them
+/// again when it has connections that get the event
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RedisEvent {
+    Update {
+        post_id: String,
+        boost_id: Option<String>,
+    },
+    Delete {
+        id: String,
+    },
+    Notification {
+        id: String,
+    },
+    FiltersChanged,
+    StatusUpdate {
+        post_id: String,
+    },
+    SessionsRevoked {
+        session_ids: Vec<String>,
+    },
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+/// Channel as it's published on Redis, topics are referenced by their `stream`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RedisChannel {
+    Account(String),
+    Topic(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct RedisMessage {
+    channel: RedisChannel,
+    event: RedisEvent,
+    categories: Vec<String>,
+}
+
+/// Redis channel every process publishes its streaming events on
+const REDIS_CHANNEL: &str = "cryap:streaming";
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum StreamingCategory {
     Public,
     PublicMedia,
@@ -93,54 +275,439 @@ impl StreamingCategory {
     }
 }
 
+/// Public stream, hashtag streams are one per hashtag
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct StreamingTopic {
+    pub category: StreamingCategory,
+    /// Normalized name of the hashtag
+    pub tag: Option<String>,
+}
+
+impl StreamingTopic {
+    /// `None` for the streams of an account, and for hashtag streams without a hashtag
+    pub fn new(category: StreamingCategory, tag: Option<&str>) -> Option<Self> {
+        match category {
+            StreamingCategory::Public
+            | StreamingCategory::PublicMedia
+            | StreamingCategory::PublicLocal
+            | StreamingCategory::PublicLocalMedia
+            | StreamingCategory::PublicRemote
+            | StreamingCategory::PublicRemoteMedia => Some(Self {
+                category,
+                tag: None,
+            }),
+            StreamingCategory::Hashtag | StreamingCategory::HashtagLocal => {
+                let tag = Tag::normalize_name(tag?);
+                if tag.is_empty() {
+                    return None;
+                }
+                Some(Self {
+                    category,
+                    tag: Some(tag),
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// `stream` of events as clients get them, e.g. `["hashtag", "cats"]`
+    pub fn stream(&self) -> Vec<String> {
+        let mut stream = vec![self.category.name()];
+        if let Some(tag) = &self.tag {
+            stream.push(tag.clone());
+        }
+        stream
+    }
+}
+
+/// Public streams a post goes to. Posts have no media attachments, so they never go to the
+/// `:media` streams
+pub async fn post_topics(
+    post: &Post,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Vec<StreamingTopic>> {
+    if post.visibility != DbVisibility::Public {
+        return Ok(vec![]);
+    }
+
+    let local = post.author(db_pool).await?.local;
+    let mut topics = vec![
+        StreamingCategory::Public,
+        if local {
+            StreamingCategory::PublicLocal
+        } else {
+            StreamingCategory::PublicRemote
+        },
+    ]
+    .into_iter()
+    .filter_map(|category| StreamingTopic::new(category, None))
+    .collect::<Vec<StreamingTopic>>();
+    for tag in Tag::by_post(&post.id, db_pool).await? {
+        topics.extend(StreamingTopic::new(
+            StreamingCategory::Hashtag,
+            Some(&tag.name),
+        ));
+        if local {
+            topics.extend(StreamingTopic::new(
+                StreamingCategory::HashtagLocal,
+                Some(&tag.name),
+            ));
+        }
+    }
+
+    Ok(topics)
+}
+
+fn deduplicate(mut user_ids: Vec<DbId>) -> Vec<DbId> {
+    let mut seen = HashSet::new();
+    user_ids.retain(|user_id| seen.insert(user_id.clone()));
+    user_ids
+}
+
+/// Local users whose home timeline has the post: the author, their local followers unless the
+/// post is direct, the mentioned local users and, for public posts, the followers of its hashtags
+pub async fn post_recipients(
+    post: &Post,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Vec<DbId>> {
+    let author = post.author(db_pool).await?;
+    let mut recipients = vec![];
+    if author.local {
+        recipients.push(author.id.clone());
+    }
+    if post.visibility != DbVisibility::Direct {
+        recipients.extend(
+            author
+                .local_followers(db_pool)
+                .await?
+                .into_iter()
+                .map(|user| user.id),
+        );
+    }
+    recipients.extend(
+        post.local_mentioned_users(db_pool)
+            .await?
+            .into_iter()
+            .map(|user| user.id),
+    );
+    if post.visibility == DbVisibility::Public {
+        recipients.extend(Tag::post_followers(&post.id, db_pool).await?);
+    }
+
+    Ok(deduplicate(recipients))
+}
+
+/// Local users whose home timeline has the boost: the booster and their local followers. Only
+/// public and unlisted posts are shown as boosted to followers
+pub async fn boost_recipients(
+    boost: &PostBoost,
+    post: &Post,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Vec<DbId>> {
+    let booster = boost.author(db_pool).await?;
+    let mut recipients = vec![];
+    if booster.local {
+        recipients.push(booster.id.clone());
+    }
+    if boost.visibility != DbVisibility::Direct
+        && matches!(
+            post.visibility,
+            DbVisibility::Public | DbVisibility::Unlisted
+        )
+    {
+        recipients.extend(
+            booster
+                .local_followers(db_pool)
+                .await?
+                .into_iter()
+                .map(|user| user.id),
+        );
+    }
+
+    Ok(deduplicate(recipients))
+}
+
+/// Sends a new post to the `user` stream of everyone who gets it in their home timeline, and
+/// public posts to the public streams
+pub async fn process_post(post: &Post, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+    for user_id in post_recipients(post, db_pool).await? {
+        EVENT_BUS
+            .send(
+                &user_id,
+                StreamingEvent::update(TimelineEntry::Post(post.clone())),
+            )
+            .await;
+    }
+    for topic in post_topics(post, db_pool).await? {
+        EVENT_BUS
+            .publish(
+                &topic,
+                StreamingEvent::Update {
+                    payload: TimelineEntry::Post(post.clone()),
+                    categories: vec![topic.category.clone()],
+                },
+            )
+            .await;
+    }
+
+    Ok(())
+}
+
+pub async fn process_post_update(
+    post: &Post,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    for user_id in post_recipients(post, db_pool).await? {
+        EVENT_BUS
+            .send(&user_id, StreamingEvent::status_update(post.clone()))
+            .await;
+    }
+    for topic in post_topics(post, db_pool).await? {
+        EVENT_BUS
+            .publish(
+                &topic,
+                StreamingEvent::StatusUpdate {
+                    payload: post.clone(),
+                    categories: vec![topic.category.clone()],
+                },
+            )
+            .await;
+    }
+
+    Ok(())
+}
+
+pub async fn process_boost(
+    boost: &PostBoost,
+    post: &Post,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    for user_id in boost_recipients(boost, post, db_pool).await? {
+        EVENT_BUS
+            .send(
+                &user_id,
+                StreamingEvent::update(TimelineEntry::Boost(boost.clone(), post.clone())),
+            )
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Has to be called with the boost that was just removed, so that clients drop it
+pub async fn process_unboost(
+    boost: &PostBoost,
+    post: &Post,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    for user_id in boost_recipients(boost, post, db_pool).await? {
+        EVENT_BUS
+            .send(&user_id, StreamingEvent::delete(boost.id.clone()))
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Events a connection can fall behind by before it skips some
+const CHANNEL_CAPACITY: usize = 64;
+
 lazy_static! {
     pub static ref EVENT_BUS: StreamingEventBus = StreamingEventBus::new();
 }
 
+/// Accounts get their own channel, public streams are shared by everyone subscribed to them
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum StreamingChannel {
+    Account(DbId),
+    Topic(StreamingTopic),
+}
+
+impl StreamingChannel {
+    fn to_redis(&self) -> RedisChannel {
+        match self {
+            Self::Account(account_id) => RedisChannel::Account(account_id.to_string()),
+            Self::Topic(topic) => RedisChannel::Topic(topic.stream()),
+        }
+    }
+
+    fn from_redis(channel: RedisChannel) -> Option<Self> {
+        match channel {
+            RedisChannel::Account(account_id) => Some(Self::Account(DbId::from(account_id))),
+            RedisChannel::Topic(stream) => StreamingTopic::new(
+                StreamingCategory::by_name(stream.first()?)?,
+                stream.get(1).map(String::as_str),
+            )
+            .map(Self::Topic),
+        }
+    }
+}
+
 pub struct StreamingEventBus {
-    channels: RwLock<HashMap<DbId, broadcast::Sender<StreamingEvent>>>,
+    channels: RwLock<HashMap<StreamingChannel, broadcast::Sender<StreamingEvent>>>,
+    /// Set when events go through Redis, see [`listen_redis`]
+    redis: OnceLock<ConnectionManager>,
 }
 
 impl StreamingEventBus {
     pub fn new() -> Self {
         Self {
             channels: RwLock::default(),
+            redis: OnceLock::new(),
         }
     }
 
-    pub async fn get_receiver(&self, account_id: &DbId) -> StreamingReceiverGuard {
+    /// Publishes events on Redis instead of delivering them right away, they come back to every
+    /// process through [`listen_redis`]
+    pub fn use_redis(&self, redis: ConnectionManager) {
+        let _ = self.redis.set(redis);
+    }
+
+    async fn subscribe(&self, channel: StreamingChannel) -> StreamingReceiverGuard {
         let channels = self.channels.read().await;
-        let sender = channels.get(account_id);
+        let sender = channels.get(&channel);
         if let Some(sender) = sender {
-            StreamingReceiverGuard::new(account_id.clone(), sender.subscribe())
+            StreamingReceiverGuard::new(channel, sender.subscribe())
         } else {
             drop(channels);
             let mut channels = self.channels.write().await;
-            let (sender, receiver) = broadcast::channel(10);
-            channels.insert(account_id.clone(), sender);
-            StreamingReceiverGuard::new(account_id.clone(), receiver)
+            // Another connection could have opened it in the meantime
+            let receiver = channels
+                .entry(channel.clone())
+                .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+                .subscribe();
+            StreamingReceiverGuard::new(channel, receiver)
         }
     }
 
-    pub async fn send(&self, account_id: &DbId, event: StreamingEvent) {
+    pub async fn get_receiver(&self, account_id: &DbId) -> StreamingReceiverGuard {
+        self.subscribe(StreamingChannel::Account(account_id.clone()))
+            .await
+    }
+
+    pub async fn get_topic_receiver(&self, topic: &StreamingTopic) -> StreamingReceiverGuard {
+        self.subscribe(StreamingChannel::Topic(topic.clone())).await
+    }
+
+    /// Sends the event to the connections of this process
+    async fn deliver(&self, channel: &StreamingChannel, event: StreamingEvent) {
         let channels = self.channels.read().await;
-        if let Some(channel) = channels.get(account_id) {
+        if let Some(channel) = channels.get(channel) {
             let _ = channel.send(event);
         }
     }
 
-    pub async fn close(&self, account_id: &DbId) {
-        let channels = self.channels.read().await;
-        let sender = channels.get(account_id);
-        let receiver_count = sender.map(|sender| sender.receiver_count()).unwrap_or(0);
-        if receiver_count <= 1 {
-            drop(channels);
-            let mut channels = self.channels.write().await;
-            let _ = channels.remove(account_id);
+    async fn has_channel(&self, channel: &StreamingChannel) -> bool {
+        self.channels.read().await.contains_key(channel)
+    }
+
+    async fn send_to(&self, channel: &StreamingChannel, event: StreamingEvent) {
+        let mut redis = match self.redis.get() {
+            Some(redis) => redis.clone(),
+            None => return self.deliver(channel, event).await,
+        };
+        let message = RedisMessage {
+            channel: channel.to_redis(),
+            event: event.to_redis(),
+            categories: event
+                .categories()
+                .iter()
+                .map(StreamingCategory::name)
+                .collect(),
+        };
+        let published = match serde_json::to_string(&message) {
+            Ok(message) => redis
+                .publish::<_, _, ()>(REDIS_CHANNEL, message)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = published {
+            // Connections of this process still get it
+            log::error!("Couldn't publish a streaming event on Redis, {:#?}", err);
+            self.deliver(channel, event).await;
+        }
+    }
+
+    pub async fn send(&self, account_id: &DbId, event: StreamingEvent) {
+        self.send_to(&StreamingChannel::Account(account_id.clone()), event)
+            .await
+    }
+
+    pub async fn publish(&self, topic: &StreamingTopic, event: StreamingEvent) {
+        self.send_to(&StreamingChannel::Topic(topic.clone()), event)
+            .await
+    }
+
+    /// Removes the channel once nobody is subscribed to it. The count is checked under the write
+    /// lock, so that a connection subscribing in the meantime keeps it open
+    pub async fn close(&self, channel: &StreamingChannel) {
+        let mut channels = self.channels.write().await;
+        if channels
+            .get(channel)
+            .is_some_and(|sender| sender.receiver_count() == 0)
+        {
+            channels.remove(channel);
         }
     }
 }
 
+/// Waits before connecting to Redis again
+const REDIS_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+async fn receive_redis(
+    client: &redis::Client,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(REDIS_CHANNEL).await?;
+    let mut messages = pubsub.on_message();
+
+    while let Some(message) = messages.next().await {
+        let message = match serde_json::from_str::<RedisMessage>(&message.get_payload::<String>()?)
+        {
+            Ok(message) => message,
+            Err(err) => {
+                log::warn!("Skipping an invalid streaming event from Redis, {:#?}", err);
+                continue;
+            },
+        };
+        let channel = match StreamingChannel::from_redis(message.channel) {
+            Some(channel) => channel,
+            None => continue,
+        };
+        // Nobody to load the records for
+        if !EVENT_BUS.has_channel(&channel).await {
+            continue;
+        }
+        let categories = message
+            .categories
+            .iter()
+            .filter_map(|name| StreamingCategory::by_name(name))
+            .collect();
+
+        match StreamingEvent::from_redis(message.event, categories, db_pool).await {
+            Ok(Some(event)) => EVENT_BUS.deliver(&channel, event).await,
+            Ok(None) => {},
+            Err(err) => log::error!("Couldn't load a streaming event from Redis, {:#?}", err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Delivers the events every process publishes on Redis to the connections of this one,
+/// connecting again when the connection is lost
+pub async fn listen_redis(client: redis::Client, db_pool: Pool<AsyncPgConnection>) {
+    loop {
+        match receive_redis(&client, &db_pool).await {
+            Ok(()) => log::warn!("Redis closed the streaming subscription"),
+            Err(err) => log::error!("Lost the streaming subscription to Redis, {:#?}", err),
+        }
+        tokio::time::sleep(REDIS_RECONNECT_DELAY).await;
+    }
+}
+
 impl Default for StreamingEventBus {
     fn default() -> Self {
         Self::new()
@@ -148,16 +715,13 @@ impl Default for StreamingEventBus {
 }
 
 pub struct StreamingReceiverGuard {
-    account_id: DbId,
+    channel: StreamingChannel,
     receiver: broadcast::Receiver<StreamingEvent>,
 }
 
 impl StreamingReceiverGuard {
-    pub fn new(account_id: DbId, receiver: broadcast::Receiver<StreamingEvent>) -> Self {
-        Self {
-            account_id,
-            receiver,
-        }
+    pub fn new(channel: StreamingChannel, receiver: broadcast::Receiver<StreamingEvent>) -> Self {
+        Self { channel, receiver }
     }
 }
 
@@ -178,9 +742,51 @@ impl std::ops::DerefMut for StreamingReceiverGuard {
 
 impl std::ops::Drop for StreamingReceiverGuard {
     fn drop(&mut self) {
-        let account_id = self.account_id.clone();
+        // The receiver has to be gone before the channel is checked
+        drop(std::mem::replace(
+            &mut self.receiver,
+            broadcast::channel(1).1,
+        ));
+        let channel = self.channel.clone();
         tokio::spawn(async move {
-            EVENT_BUS.close(&account_id).await;
+            EVENT_BUS.close(&channel).await;
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{StreamingCategory, StreamingChannel, StreamingTopic, EVENT_BUS};
+
+    #[test]
+    fn channels_survive_redis() {
+        let topic = StreamingChannel::Topic(
+            StreamingTopic::new(StreamingCategory::HashtagLocal, Some("#Cats")).unwrap(),
+        );
+        assert_eq!(StreamingChannel::from_redis(topic.to_redis()), Some(topic));
+
+        let account = StreamingChannel::Account(db::types::DbId::default());
+        assert_eq!(
+            StreamingChannel::from_redis(account.to_redis()),
+            Some(account)
+        );
+    }
+
+    #[tokio::test]
+    async fn channels_close_with_the_last_subscriber() {
+        let topic = StreamingTopic::new(StreamingCategory::HashtagLocal, Some("#Closing")).unwrap();
+        let channel = StreamingChannel::Topic(topic.clone());
+        let first = EVENT_BUS.get_topic_receiver(&topic).await;
+        let second = EVENT_BUS.get_topic_receiver(&topic).await;
+
+        drop(first);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(EVENT_BUS.has_channel(&channel).await);
+
+        drop(second);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!EVENT_BUS.has_channel(&channel).await);
+    }
+}
@@ -0,0 +1,97 @@
+use activitypub_federation::kinds::{kind, object::ImageType};
+use chrono::{DateTime, Utc};
+use db::models::{CustomEmoji, UserEmoji};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+kind!(EmojiType, Emoji);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmojiIcon {
+    #[serde(rename = "type")]
+    pub kind: ImageType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    pub url: Url,
+}
+
+/// Custom emoji as used by Mastodon, `name` is the shortcode surrounded by colons
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Emoji {
+    #[serde(rename = "type")]
+    pub kind: EmojiType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Url>,
+    pub name: Option<String>,
+    pub icon: Option<EmojiIcon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+}
+
+/// Entry of `tag` of objects that only care about emojis
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum EmojiTag {
+    Emoji(Emoji),
+    Other(serde_json::Value),
+}
+
+impl EmojiTag {
+    pub fn emojis(tags: &[Self]) -> Vec<&Emoji> {
+        tags.iter()
+            .filter_map(|tag| match tag {
+                Self::Emoji(emoji) => Some(emoji),
+                Self::Other(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl Emoji {
+    /// Shortcode without the surrounding colons
+    pub fn shortcode(&self) -> Option<&str> {
+        let shortcode = self.name.as_ref()?.trim_matches(':');
+        if shortcode.is_empty() {
+            None
+        } else {
+            Some(shortcode)
+        }
+    }
+}
+
+impl TryFrom<&CustomEmoji> for Emoji {
+    type Error = url::ParseError;
+
+    fn try_from(emoji: &CustomEmoji) -> Result<Self, Self::Error> {
+        Ok(Self {
+            kind: Default::default(),
+            id: Some(Url::parse(&emoji.ap_id)?),
+            name: Some(format!(":{}:", emoji.shortcode)),
+            icon: Some(EmojiIcon {
+                kind: Default::default(),
+                media_type: emoji.media_type.clone(),
+                url: Url::parse(&emoji.image_url)?,
+            }),
+            updated: Some(emoji.updated.unwrap_or(emoji.published)),
+        })
+    }
+}
+
+impl TryFrom<&UserEmoji> for Emoji {
+    type Error = url::ParseError;
+
+    fn try_from(emoji: &UserEmoji) -> Result<Self, Self::Error> {
+        Ok(Self {
+            kind: Default::default(),
+            id: Some(Url::parse(&emoji.ap_id)?),
+            name: Some(format!(":{}:", emoji.shortcode)),
+            icon: Some(EmojiIcon {
+                kind: Default::default(),
+                media_type: emoji.media_type.clone(),
+                url: Url::parse(&emoji.url)?,
+            }),
+            updated: None,
+        })
+    }
+}
@@ -20,6 +20,7 @@ macro_rules! db_to_ap {
 }
 
 pub mod announce;
+pub mod emoji;
 pub mod note;
 pub mod ordered_collection;
 pub mod service_actor;
@@ -3,14 +3,17 @@ use std::sync::Arc;
 use activitypub_federation::{
     config::Data,
     fetch::object_id::ObjectId,
-    kinds::{kind, link::MentionType, object::NoteType},
+    kinds::{
+        kind,
+        link::{LinkType, MentionType},
+    },
     protocol::{helpers::deserialize_one_or_many, verification::verify_domains_match},
     traits::Object,
 };
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 use db::{
-    models::{Post, PostMention, User},
+    models::{Article, CustomEmoji, Post, PostMention, Tag, User},
     schema::{post_mention, posts, users},
     types::{DbId, DbVisibility},
 };
@@ -24,27 +27,33 @@ use svix_ksuid::KsuidLike;
 use url::Url;
 use web::AppState;
 
-use super::user::ApUser;
-use crate::PUBLIC;
+use super::{emoji::Emoji, user::ApUser};
+use crate::{common::emojis, PUBLIC};
 
 kind!(HashtagType, Hashtag);
-kind!(EmojiType, Emoji);
+
+/// Media type of FEP-e232 object links to ActivityPub objects
+pub const OBJECT_LINK_MEDIA_TYPE: &str =
+    "application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\"";
 
 db_to_ap!(db::models::Post, ApNote);
 
+/// Object types stored as posts. Articles keep their title and full body separately, see
+/// [`Article`]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum NoteKind {
+    #[default]
+    Note,
+    Article,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum NoteTags {
     Mention(Mention),
     Hashtag(Hashtag),
     Emoji(Emoji),
-}
-
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Emoji {
-    #[serde(rename = "type")]
-    pub kind: EmojiType,
-    pub name: Option<String>,
+    Link(ObjectLink),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -60,7 +69,46 @@ pub struct Hashtag {
     #[serde(rename = "type")]
     pub kind: HashtagType,
     pub href: Url,
-    name: Option<String>,
+    pub name: Option<String>,
+}
+
+/// FEP-e232 object link, used for quotes
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectLink {
+    #[serde(rename = "type")]
+    pub kind: LinkType,
+    pub href: Url,
+    pub media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl ObjectLink {
+    pub fn is_object(&self) -> bool {
+        matches!(
+            self.media_type.as_deref(),
+            Some(OBJECT_LINK_MEDIA_TYPE) | Some("application/activity+json")
+        )
+    }
+}
+
+/// Short version of an article shown by software that doesn't support articles: the title,
+/// the summary and a link to the full text. `summary` is HTML
+pub fn article_fallback(title: &str, summary: Option<&str>, url: &str) -> String {
+    let mut fallback = format!(
+        "<p><strong>{}</strong></p>",
+        html_escape::encode_text(title)
+    );
+    if let Some(summary) = summary {
+        fallback.push_str(summary);
+    }
+    fallback.push_str(&format!(
+        "<p><a href=\"{}\">{}</a></p>",
+        html_escape::encode_double_quoted_attribute(url),
+        html_escape::encode_text(url)
+    ));
+    fallback
 }
 
 pub fn parse_to_cc(to: &Vec<Url>, cc: &Vec<Url>, actor_followers_uri: Url) -> DbVisibility {
@@ -102,10 +150,13 @@ pub fn construct_to_cc(
 #[serde(rename_all = "camelCase")]
 pub struct Note {
     #[serde(rename = "type")]
-    pub kind: NoteType,
+    pub kind: NoteKind,
     pub id: ObjectId<ApNote>,
     pub attributed_to: ObjectId<ApUser>,
 
+    /// Title of articles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub content: String,
     pub url: Option<String>,
     #[serde_as(as = "NoneAsEmptyString")]
@@ -116,6 +167,8 @@ pub struct Note {
     pub updated: Option<DateTime<Utc>>,
     pub quote_uri: Option<ObjectId<ApNote>>,
     pub quote_url: Option<ObjectId<ApNote>>,
+    #[serde(rename = "_misskey_quote")]
+    pub misskey_quote: Option<ObjectId<ApNote>>,
     #[serde(default)]
     pub tag: Vec<NoteTags>,
 
@@ -125,6 +178,20 @@ pub struct Note {
     pub cc: Vec<Url>,
 }
 
+impl Note {
+    /// Quoted object, from any of the ways different servers specify it
+    pub fn quote(&self) -> Option<ObjectId<ApNote>> {
+        self.quote_uri
+            .clone()
+            .or(self.quote_url.clone())
+            .or(self.misskey_quote.clone())
+            .or(self.tag.iter().find_map(|tag| match tag {
+                NoteTags::Link(link) if link.is_object() => Some(ObjectId::from(link.href.clone())),
+                _ => None,
+            }))
+    }
+}
+
 impl ApNote {
     pub fn id(&self) -> Url {
         Url::parse(&self.ap_id).unwrap() // should never panic in theory
@@ -149,7 +216,7 @@ impl ApNote {
         let quote = match &self.quote {
             None => None,
             Some(quote) => match Post::by_id(quote, &data.db_pool).await? {
-                Some(quote) => Some(ObjectId::<ApNote>::from(Url::parse(&quote.ap_id)?)),
+                Some(quote) => Some(Url::parse(&quote.ap_id)?),
                 None => None,
             },
         };
@@ -181,26 +248,68 @@ impl ApNote {
             }))
         }
 
+        for tag in Tag::by_post(&self.id, &data.db_pool).await? {
+            tags.push(NoteTags::Hashtag(Hashtag {
+                kind: Default::default(),
+                href: Url::parse(&format!(
+                    "https://{}/tags/{}",
+                    data.config.web.domain, tag.name
+                ))?,
+                name: Some(format!("#{}", tag.name)),
+            }))
+        }
+
+        for emoji in CustomEmoji::by_post(&self.id, &data.db_pool).await? {
+            tags.push(NoteTags::Emoji(Emoji::try_from(&emoji)?))
+        }
+
+        if let Some(quote) = &quote {
+            tags.push(NoteTags::Link(ObjectLink {
+                kind: Default::default(),
+                href: quote.clone(),
+                media_type: Some(OBJECT_LINK_MEDIA_TYPE.to_string()),
+                name: Some(format!("RE: {}", quote)),
+            }))
+        }
+
         let (to, cc) = construct_to_cc(
             &self.visibility,
             Url::parse(&attributed_to.followers_uri)?,
             mention_ids,
         );
 
+        let (kind, name, summary, content) = match Article::by_post(&self.id, &data.db_pool).await?
+        {
+            Some(article) => (
+                NoteKind::Article,
+                Some(article.title),
+                article.summary,
+                article.content,
+            ),
+            None => (
+                NoteKind::Note,
+                None,
+                self.content_warning.clone(),
+                self.content.clone(),
+            ),
+        };
+
         Ok(Note {
-            kind: Default::default(),
+            kind,
             attributed_to: ObjectId::from(Url::parse(&attributed_to.ap_id)?),
             id: ObjectId::from(Url::parse(&self.ap_id)?),
-            summary: self.content_warning.clone().or(Some("".to_string())),
-            content: self.content.clone(),
+            name,
+            summary: summary.or(Some("".to_string())),
+            content,
             sensitive: Some(self.sensitive),
             url: Some(self.ap_id.clone()),
             in_reply_to,
             tag: tags,
             to,
             cc,
-            quote_uri: quote.clone(), // AP moment
-            quote_url: quote.clone(),
+            quote_uri: quote.clone().map(ObjectId::from), // AP moment
+            quote_url: quote.clone().map(ObjectId::from),
+            misskey_quote: quote.map(ObjectId::from),
             published: Some(published),
             updated: self.updated,
         })
@@ -252,8 +361,22 @@ impl Object for ApNote {
         if json.content.len() > 500_000 {
             return Err(anyhow!("Remote post is too big! 500k+ characters"));
         }
-        if json.summary.clone().unwrap_or("".to_string()).len() > 1000 {
-            return Err(anyhow!("Remote post CW is too big! 1k+ characters"));
+        match json.kind {
+            NoteKind::Note => {
+                if json.summary.clone().unwrap_or("".to_string()).len() > 1000 {
+                    return Err(anyhow!("Remote post CW is too big! 1k+ characters"));
+                }
+            },
+            NoteKind::Article => {
+                if json.name.clone().unwrap_or("".to_string()).chars().count() > 500 {
+                    return Err(anyhow!("Remote article title is too big! 500+ characters"));
+                }
+                if json.summary.clone().unwrap_or("".to_string()).len() > 10_000 {
+                    return Err(anyhow!(
+                        "Remote article summary is too big! 10k+ characters"
+                    ));
+                }
+            },
         }
         // TODO: Check Hashtags and Mention limits
         Ok(())
@@ -266,12 +389,29 @@ impl Object for ApNote {
             None => None,
             Some(ref reply) => Some(reply.dereference(data).await?.id.clone()),
         };
-        let quote = match json.quote_uri {
-            None => match json.quote_url {
-                Some(ref quote) => Some(quote.dereference(data).await?.id.clone()),
-                None => None,
+        // Unlike replies, a quote that can't be fetched anymore doesn't make the post invalid
+        let quote = match json.quote() {
+            Some(quote) => quote
+                .dereference(data)
+                .await
+                .ok()
+                .map(|quote| quote.id.clone()),
+            None => None,
+        };
+
+        let url = json.url.unwrap_or(json.id.inner().to_string());
+        // Articles are stored with a fallback as the content of the post, and summaries of
+        // articles are not content warnings
+        let (content, content_warning, article) = match json.kind {
+            NoteKind::Note => (json.content, json.summary, None),
+            NoteKind::Article => {
+                let title = json.name.unwrap_or_default();
+                (
+                    article_fallback(&title, json.summary.as_deref(), &url),
+                    None,
+                    Some((title, json.summary, json.content)),
+                )
             },
-            Some(ref quote) => Some(quote.dereference(data).await?.id.clone()),
         };
 
         let post = Post {
@@ -281,11 +421,11 @@ impl Object for ApNote {
                 None,
             )),
             author: actor.id.clone(),
-            content: json.content, // TODO: sanitize
-            url: json.url.unwrap_or(json.id.inner().to_string()),
+            content, // TODO: sanitize
+            url,
             local_only: false, // remote post can't be local only
             visibility: parse_to_cc(&json.to, &json.cc, Url::parse(&actor.followers_uri)?),
-            content_warning: json.summary,
+            content_warning,
             in_reply: reply,
             quote,
             sensitive: false,
@@ -302,11 +442,25 @@ impl Object for ApNote {
             .get_result::<Post>(&mut conn)
             .await?;
 
+        if let Some((title, summary, content)) = article {
+            Article::upsert(
+                Article {
+                    post_id: post_db.id.clone(),
+                    title,
+                    summary,
+                    content,
+                },
+                &data.db_pool,
+            )
+            .await?;
+        }
+
         let mut mentions: Vec<PostMention> = vec![];
+        let mut hashtags: Vec<String> = vec![];
 
         for tag in &json.tag {
-            if let NoteTags::Mention(mention) = tag {
-                mentions.push(PostMention {
+            match tag {
+                NoteTags::Mention(mention) => mentions.push(PostMention {
                     id: DbId::default(),
                     post_id: post_db.id.clone(),
                     mentioned_user_id: ObjectId::<ApUser>::from(mention.href.clone())
@@ -314,7 +468,11 @@ impl Object for ApNote {
                         .await?
                         .id
                         .clone(),
-                })
+                }),
+                NoteTags::Hashtag(Hashtag {
+                    name: Some(name), ..
+                }) => hashtags.push(name.clone()),
+                _ => {},
             }
         }
 
@@ -327,6 +485,66 @@ impl Object for ApNote {
                 .await?;
         }
 
+        Tag::attach_to_post(&post_db.id, hashtags, &data.db_pool).await?;
+
+        let emojis = emojis::save_remote(
+            json.tag
+                .iter()
+                .filter_map(|tag| match tag {
+                    NoteTags::Emoji(emoji) => Some(emoji),
+                    _ => None,
+                })
+                .collect(),
+            &actor.instance,
+            &data.db_pool,
+        )
+        .await?;
+        CustomEmoji::attach_to_post(&post_db.id, &emojis, &data.db_pool).await?;
+
         Ok(ApNote(post_db))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::objects::note::Note;
+
+    #[test]
+    fn quote_links() {
+        let note: Note = serde_json::from_value(json!({
+            "type": "Note",
+            "id": "https://example.com/notes/2",
+            "attributedTo": "https://example.com/users/alice",
+            "content": "Look at this",
+            "summary": "",
+            "to": "https://www.w3.org/ns/activitystreams#Public",
+            "tag": [
+                {
+                    "type": "Link",
+                    "mediaType": "application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\"",
+                    "href": "https://example.com/notes/1",
+                    "name": "RE: https://example.com/notes/1"
+                }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            note.quote().unwrap().inner().as_str(),
+            "https://example.com/notes/1"
+        );
+
+        let note: Note = serde_json::from_value(json!({
+            "type": "Note",
+            "id": "https://example.com/notes/3",
+            "attributedTo": "https://example.com/users/alice",
+            "content": "Not a quote",
+            "summary": "",
+            "to": "https://www.w3.org/ns/activitystreams#Public",
+            "tag": [{ "type": "Link", "mediaType": "text/html", "href": "https://example.com" }]
+        }))
+        .unwrap();
+        assert!(note.quote().is_none());
+    }
+}
@@ -3,13 +3,16 @@ use std::sync::Arc;
 use activitypub_federation::{
     config::Data,
     fetch::object_id::ObjectId,
-    protocol::{public_key::PublicKey, verification::verify_domains_match},
+    kinds::kind,
+    protocol::{
+        helpers::deserialize_one_or_many, public_key::PublicKey, verification::verify_domains_match,
+    },
     traits::{Actor, Object},
 };
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 use db::{
-    models::{User, UserInsert},
+    models::{user::UserUpdate, User, UserEmoji, UserField, UserInsert},
     schema::users,
     types::DbId,
 };
@@ -21,8 +24,17 @@ use svix_ksuid::KsuidLike;
 use url::Url;
 use web::AppState;
 
+use super::emoji::{Emoji, EmojiTag};
+use crate::common::{emojis, profile_fields};
+
+kind!(PropertyValueType, PropertyValue);
+
 db_to_ap!(db::models::User, ApUser);
 
+/// Remote servers may allow more fields than we do, but there has to be some limit
+const MAX_REMOTE_FIELDS: usize = 16;
+const MAX_REMOTE_ALIASES: usize = 16;
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum UserTypes {
     Person,
@@ -37,6 +49,21 @@ pub struct Endpoints {
     pub shared_inbox: Url,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PropertyValue {
+    #[serde(rename = "type")]
+    pub kind: PropertyValueType,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PersonAttachment {
+    PropertyValue(PropertyValue),
+    Other(serde_json::Value),
+}
+
 #[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -62,6 +89,16 @@ pub struct Person {
     pub published: Option<DateTime<Utc>>,
     pub updated: Option<DateTime<Utc>>,
 
+    #[serde(deserialize_with = "deserialize_one_or_many", default)]
+    pub attachment: Vec<PersonAttachment>,
+    #[serde(deserialize_with = "deserialize_one_or_many", default)]
+    pub tag: Vec<EmojiTag>,
+
+    #[serde(deserialize_with = "deserialize_one_or_many", default)]
+    pub also_known_as: Vec<Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<ObjectId<ApUser>>,
+
     #[serde(default)]
     pub is_cat: bool,
 }
@@ -87,7 +124,39 @@ impl Object for ApUser {
         }
     }
 
-    async fn into_json(self, _data: &Data<Self::DataType>) -> Result<Self::Kind, Self::Error> {
+    async fn into_json(self, data: &Data<Self::DataType>) -> Result<Self::Kind, Self::Error> {
+        let attachment = self
+            .fields()
+            .into_iter()
+            .map(|field| {
+                PersonAttachment::PropertyValue(PropertyValue {
+                    kind: Default::default(),
+                    value: if self.local {
+                        profile_fields::value_to_html(&field.value)
+                    } else {
+                        field.value
+                    },
+                    name: field.name,
+                })
+            })
+            .collect();
+        let tag = self
+            .emojis()
+            .iter()
+            .map(|emoji| Ok(EmojiTag::Emoji(Emoji::try_from(emoji)?)))
+            .collect::<Result<Vec<EmojiTag>, url::ParseError>>()?;
+        let also_known_as = self
+            .also_known_as()
+            .iter()
+            .map(|alias| Url::parse(alias))
+            .collect::<Result<Vec<Url>, url::ParseError>>()?;
+        let moved_to = match &self.moved_to_id {
+            Some(moved_to_id) => match User::by_id(moved_to_id, &data.db_pool).await? {
+                Some(moved_to) => Some(ObjectId::from(Url::parse(&moved_to.ap_id)?)),
+                None => None,
+            },
+            None => None,
+        };
         let ap_id = self.ap_id.clone();
         let bio = self.bio.clone();
         let updated = self.updated;
@@ -116,12 +185,21 @@ impl Object for ApUser {
                 None
             },
             manually_approves_followers: self.manually_approves_followers,
+            attachment,
+            tag,
+            also_known_as,
+            moved_to,
             is_cat: self.is_cat,
             followers: Url::parse(&(ap_id.clone() + "/ap/followers"))?, // TODO
             following: Url::parse(&(ap_id + "/ap/following"))?,         // TODO
         })
     }
 
+    /// Called when the actor is deleted, either with `Delete` or when fetching it returns 410
+    async fn delete(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        self.0.purge(&data.db_pool).await
+    }
+
     async fn verify(
         json: &Self::Kind,
         expected_domain: &Url,
@@ -132,6 +210,54 @@ impl Object for ApUser {
     }
 
     async fn from_json(json: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, Self::Error> {
+        let old_fields = match Self::read_from_id(json.id.inner().clone(), data).await? {
+            Some(user) => user.fields(),
+            None => vec![],
+        };
+        let fields = UserField::merge(
+            &old_fields,
+            json.attachment
+                .into_iter()
+                .filter_map(|attachment| match attachment {
+                    PersonAttachment::PropertyValue(property_value) => Some(UserField {
+                        name: property_value.name,
+                        value: property_value.value, // TODO: sanitize
+                        verified_at: None,
+                    }),
+                    PersonAttachment::Other(_) => None,
+                })
+                .take(MAX_REMOTE_FIELDS)
+                .collect(),
+        );
+
+        let instance = match json.id.inner().host() {
+            None => return Err(anyhow!("json id host is None")),
+            Some(id) => match id {
+                url::Host::Domain(s) => s.to_string(),
+                _ => return Err(anyhow!("json id host cannot be an IP")),
+            },
+        };
+        let emojis: Vec<UserEmoji> =
+            emojis::save_remote(EmojiTag::emojis(&json.tag), &instance, &data.db_pool)
+                .await?
+                .iter()
+                .map(UserEmoji::from)
+                .collect();
+
+        let also_known_as: Vec<String> = json
+            .also_known_as
+            .iter()
+            .take(MAX_REMOTE_ALIASES)
+            .map(Url::to_string)
+            .collect();
+        // The new account is only looked up locally, it's fetched when a `Move` is received
+        let moved_to_id = match &json.moved_to {
+            Some(moved_to) => Self::read_from_id(moved_to.inner().clone(), data)
+                .await?
+                .map(|user| user.id.clone()),
+            None => None,
+        };
+
         let mut conn = data.db_pool.get().await?;
 
         let user = UserInsert {
@@ -147,13 +273,7 @@ impl Object for ApUser {
             outbox_uri: json.outbox.to_string(),
             followers_uri: json.followers.to_string(),
             name: json.preferred_username,
-            instance: match json.id.inner().host() {
-                None => return Err(anyhow!("json id host is None")),
-                Some(id) => match id {
-                    url::Host::Domain(s) => s.to_string(),
-                    _ => return Err(anyhow!("json id host cannot be an IP")),
-                },
-            },
+            instance,
             display_name: json.name,
             bio: json.summary,
             password_encrypted: None,
@@ -165,17 +285,40 @@ impl Object for ApUser {
             manually_approves_followers: json.manually_approves_followers,
             is_cat: json.is_cat,
             bot: json.kind == UserTypes::Service || json.kind == UserTypes::Application,
+            fields: serde_json::to_value(fields)?,
+            emojis: serde_json::to_value(emojis)?,
+            also_known_as: serde_json::to_value(also_known_as)?,
+            approved: true,
+            registration_reason: None,
+            invite_id: None,
+        };
+
+        let user = insert_into(users::table)
+            .values(user.clone())
+            .on_conflict(users::ap_id)
+            .do_update()
+            .set(user)
+            .get_result::<User>(&mut conn)
+            .await?;
+        let user = if user.moved_to_id != moved_to_id {
+            user.update(
+                UserUpdate {
+                    moved_to_id: Some(moved_to_id.clone()),
+                    ..Default::default()
+                },
+                &data.db_pool,
+            )
+            .await?;
+            User {
+                moved_to_id,
+                ..user
+            }
+        } else {
+            user
         };
+        profile_fields::spawn_verification(user.clone(), old_fields, &data.db_pool);
 
-        Ok(ApUser(
-            insert_into(users::table)
-                .values(user.clone())
-                .on_conflict(users::ap_id)
-                .do_update()
-                .set(user)
-                .get_result::<User>(&mut conn)
-                .await?,
-        ))
+        Ok(ApUser(user))
     }
 }
 
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use activitypub_federation::{
+    axum::json::FederationJson, config::Data, protocol::context::WithContext,
+};
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Router};
+use db::{models::CustomEmoji, types::DbId};
+use web::{errors::AppError, AppState};
+
+use crate::objects::emoji::Emoji;
+
+pub async fn http_get_emoji(
+    Path(id): Path<String>,
+    state: Data<Arc<AppState>>,
+) -> Result<impl IntoResponse, AppError> {
+    match CustomEmoji::by_id(&DbId::from(id), &state.db_pool).await? {
+        Some(emoji) if emoji.local => {
+            Ok(FederationJson(WithContext::new_default(Emoji::try_from(&emoji)?)).into_response())
+        },
+        _ => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+pub fn emojis() -> Router {
+    Router::new().route("/emojis/:id", get(http_get_emoji))
+}
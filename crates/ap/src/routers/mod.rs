@@ -1,4 +1,5 @@
 pub mod activitypub;
+pub mod emojis;
 pub mod nodeinfo;
 pub mod posts;
 pub mod users;
@@ -11,6 +12,7 @@ use crate::objects::service_actor::ServiceActor;
 pub fn ap(service_actor: ServiceActor) -> Router {
     Router::new()
         .merge(activitypub::activitypub(service_actor))
+        .merge(emojis::emojis())
         .merge(nodeinfo::nodeinfo())
         .merge(users::users())
         .merge(posts::posts())
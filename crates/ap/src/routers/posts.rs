@@ -17,11 +17,13 @@ pub async fn http_get_post(
     //    let accept = header_map.get("accept").map(|v| v.to_str().unwrap());
     //    if accept == Some(FEDERATION_CONTENT_TYPE) {
     let post = Post::by_id(&DbId::from(id), &state.db_pool).await?;
-    if let Some(post) = post {
-        let json_post = ApNote(post).into_json(&state).await.unwrap();
-        Ok(FederationJson(WithContext::new_default(json_post)).into_response())
-    } else {
-        Ok(StatusCode::NOT_FOUND.into_response())
+    match post {
+        // Local-only posts can't be fetched by other servers or anonymous users
+        Some(post) if !post.local_only => {
+            let json_post = ApNote(post).into_json(&state).await?;
+            Ok(FederationJson(WithContext::new_default(json_post)).into_response())
+        },
+        _ => Ok(StatusCode::NOT_FOUND.into_response()),
     }
     //    } else {
     //        unreachable!()
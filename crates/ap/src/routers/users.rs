@@ -85,16 +85,27 @@ pub async fn http_get_user_outbox(
             let timeline = user
                 .posts(pagination.into(), None, false, false, &state.db_pool)
                 .await?;
-            let items = try_join_all(timeline.clone().into_iter().map(|entry| async {
-                Result::<OutboxItem, anyhow::Error>::Ok(match entry {
-                    TimelineEntry::Post(post) => {
-                        OutboxItem::Note(ApNote(post).into_json(&state).await?)
-                    },
-                    TimelineEntry::Boost(boost, _) => {
-                        OutboxItem::Announce(ApAnnounce(boost).into_json(&state).await?)
-                    },
-                })
-            }))
+            // Local-only posts and boosts of them are not federated
+            let items = try_join_all(
+                timeline
+                    .clone()
+                    .into_iter()
+                    .filter(|entry| match entry {
+                        TimelineEntry::Post(post) | TimelineEntry::Boost(_, post) => {
+                            !post.local_only
+                        },
+                    })
+                    .map(|entry| async {
+                        Result::<OutboxItem, anyhow::Error>::Ok(match entry {
+                            TimelineEntry::Post(post) => {
+                                OutboxItem::Note(ApNote(post).into_json(&state).await?)
+                            },
+                            TimelineEntry::Boost(boost, _) => {
+                                OutboxItem::Announce(ApAnnounce(boost).into_json(&state).await?)
+                            },
+                        })
+                    }),
+            )
             .await?;
             Ok(
                 FederationJson(WithContext::new_default(OrderedCollectionPage::<
@@ -170,11 +181,14 @@ pub async fn http_get_user(
     //    let accept = header_map.get("accept").map(|v| v.to_str().unwrap());
     //    if accept == Some(FEDERATION_CONTENT_TYPE) {
     let user = User::local_by_name(&name, &state.db_pool).await?;
-    if let Some(user) = user {
-        let json_user = ApUser(user).into_json(&state).await.unwrap();
-        Ok(FederationJson(WithContext::new_default(json_user)).into_response())
-    } else {
-        Ok(StatusCode::NOT_FOUND.into_response())
+    match user {
+        // Other servers remove their copy of the actor when they get 410
+        Some(user) if user.deleted_at.is_some() => Ok(StatusCode::GONE.into_response()),
+        Some(user) => {
+            let json_user = ApUser(user).into_json(&state).await.unwrap();
+            Ok(FederationJson(WithContext::new_default(json_user)).into_response())
+        },
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
     }
     //    } else {
     //        unreachable!()
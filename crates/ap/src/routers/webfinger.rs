@@ -28,6 +28,7 @@ pub async fn http_get_webfinger(
     let user: User = schema::users::table
         .filter(schema::users::local.eq(true))
         .filter(schema::users::name.eq(name))
+        .filter(schema::users::deleted_at.is_null())
         .first(&mut connection)
         .await?;
     Ok(Json(Webfinger {
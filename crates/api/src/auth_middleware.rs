@@ -1,52 +1,196 @@
-use std::sync::Arc;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 
 use axum::{
-    extract::{State, TypedHeader},
+    extract::{ConnectInfo, State, TypedHeader},
     headers::authorization::{Authorization, Bearer},
-    http::{Request, StatusCode},
+    http::{header::USER_AGENT, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
-use db::models::Session;
+use db::models::{AppToken, Session};
 use web::AppState;
 
-use crate::error::ApiError;
+use crate::{error::ApiError, scopes};
+
+/// State of the auth middlewares, `scope` is what the token has to be granted to use the route
+#[derive(Clone)]
+pub struct AuthState {
+    state: Arc<AppState>,
+    scope: Option<&'static str>,
+}
+
+/// Middleware state for routes that need the given scope
+pub fn scope(state: &Arc<AppState>, scope: &'static str) -> AuthState {
+    AuthState {
+        state: Arc::clone(state),
+        scope: Some(scope),
+    }
+}
+
+/// Middleware state for routes that any token can use
+pub fn any_scope(state: &Arc<AppState>) -> AuthState {
+    AuthState {
+        state: Arc::clone(state),
+        scope: None,
+    }
+}
+
+pub(crate) fn outside_scopes() -> ApiError {
+    ApiError::new(
+        "This action is outside the authorized scopes",
+        StatusCode::FORBIDDEN,
+    )
+}
+
+fn is_allowed(granted: &str, scope: Option<&'static str>) -> bool {
+    scope.map_or(true, |scope| scopes::allows(granted, scope))
+}
+
+/// Address of the client, behind a local reverse proxy it's the last one the proxy added to
+/// `X-Forwarded-For`
+fn client_ip<B>(request: &Request<B>) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip())?;
+    if !peer.is_loopback() {
+        return Some(peer);
+    }
+
+    request
+        .headers()
+        .get("X-Forwarded-For")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.rsplit(',').next())
+        .and_then(|address| address.trim().parse().ok())
+        .or(Some(peer))
+}
+
+/// Records where the session was last used from, which is shown in the session list
+async fn touch<B>(session: &Session, request: &Request<B>, state: &Arc<AppState>) {
+    let user_agent = request
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|header| header.to_str().ok())
+        .map(String::from);
+    let ip = client_ip(request).map(|ip| ip.to_string());
+    if let Err(error) = session.touch(ip, user_agent, &state.db_pool).await {
+        log::error!("Couldn't record the use of a session, {:#?}", error);
+    }
+}
+
+/// Finds the session by token, leaving out sessions of users who are still waiting for approval
+pub(crate) async fn approved_session(
+    token: &str,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Result<Session, ApiError>> {
+    let session = match Session::by_token(token, &state.db_pool).await? {
+        Some(session) => session,
+        None => {
+            return Ok(Err(ApiError::new(
+                "This method requires an authenticated user",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )))
+        },
+    };
+    if session.user(&state.db_pool).await?.approved {
+        Ok(Ok(session))
+    } else {
+        Ok(Err(ApiError::new(
+            "Your login is currently pending approval",
+            StatusCode::FORBIDDEN,
+        )))
+    }
+}
 
 pub async fn auth_middleware<B>(
-    State(state): State<Arc<AppState>>,
+    State(AuthState { state, scope }): State<AuthState>,
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     mut request: Request<B>,
     next: Next<B>,
 ) -> Result<Response, ApiError> {
-    let session = Session::by_token(auth.token(), &state.db_pool).await;
-    if let Ok(Some(session)) = session {
-        request.extensions_mut().insert(session);
-        Ok(next.run(request).await)
-    } else {
-        Err(ApiError::new(
+    match approved_session(auth.token(), &state).await {
+        Ok(Ok(session)) if !is_allowed(&session.scopes, scope) => Err(outside_scopes()),
+        Ok(Ok(session)) => {
+            touch(&session, &request, &state).await;
+            request.extensions_mut().insert(session);
+            Ok(next.run(request).await)
+        },
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err(ApiError::new(
             "This method requires an authenticated user",
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )),
     }
 }
 
+/// Like [`auth_middleware`], but lets requests without a valid token through. A valid token that
+/// lacks the scope is still rejected, so that clients notice they asked for too few scopes
 pub async fn optional_auth_middleware<B>(
-    State(state): State<Arc<AppState>>,
+    State(AuthState { state, scope }): State<AuthState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     mut request: Request<B>,
     next: Next<B>,
 ) -> Result<Response, ApiError> {
     let session = if let Some(TypedHeader(auth)) = auth {
-        Some(Session::by_token(auth.token(), &state.db_pool).await)
+        approved_session(auth.token(), &state)
+            .await
+            .ok()
+            .and_then(Result::ok)
     } else {
         None
     };
-
-    if let Some(Ok(session)) = session {
-        request.extensions_mut().insert(session);
-    } else {
-        request.extensions_mut().insert(None::<Session>);
+    if session
+        .as_ref()
+        .is_some_and(|session| !is_allowed(&session.scopes, scope))
+    {
+        return Err(outside_scopes());
+    }
+    if let Some(session) = &session {
+        touch(session, &request, &state).await;
     }
 
+    request.extensions_mut().insert(session);
+
     Ok(next.run(request).await)
 }
+
+/// Requires a token of an application, either one obtained with the `client_credentials` grant
+/// or a user token issued to an application
+pub async fn app_auth_middleware<B>(
+    State(AuthState { state, scope }): State<AuthState>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, ApiError> {
+    let application = match AppToken::by_token(auth.token(), &state.db_pool).await {
+        Ok(Some(app_token)) if !is_allowed(&app_token.scopes, scope) => {
+            return Err(outside_scopes())
+        },
+        Ok(Some(app_token)) => app_token.application(&state.db_pool).await.ok(),
+        Ok(None) => match Session::by_token(auth.token(), &state.db_pool).await {
+            Ok(Some(session)) if !is_allowed(&session.scopes, scope) => {
+                return Err(outside_scopes())
+            },
+            Ok(Some(session)) => {
+                touch(&session, &request, &state).await;
+                session.application(&state.db_pool).await.ok().flatten()
+            },
+            _ => None,
+        },
+        Err(_) => None,
+    };
+
+    if let Some(application) = application {
+        request.extensions_mut().insert(application);
+        Ok(next.run(request).await)
+    } else {
+        Err(ApiError::new(
+            "The access token is invalid",
+            StatusCode::UNAUTHORIZED,
+        ))
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use chrono::Utc;
+use db::{models::CustomEmoji, types::DbId};
+use lazy_static::lazy_static;
+use regex::Regex;
+use url::Url;
+use web::AppState;
+
+lazy_static! {
+    static ref SHORTCODE_RE: Regex = Regex::new(r"^[a-zA-Z0-9_]{2,100}$").unwrap();
+}
+
+fn media_type(url: &Url) -> Option<String> {
+    let extension = url.path().rsplit_once('.')?.1.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "avif" => Some("image/avif"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+    .map(str::to_string)
+}
+
+pub async fn add(
+    shortcode: String,
+    image_url: String,
+    category: Option<String>,
+    visible_in_picker: bool,
+    state: &Arc<AppState>,
+) -> anyhow::Result<CustomEmoji> {
+    if !SHORTCODE_RE.is_match(&shortcode) {
+        return Err(anyhow!(
+            "shortcode must be 2-100 characters long and contain only letters, digits and underscores"
+        ));
+    }
+
+    let url = Url::parse(&image_url)?;
+    if !matches!(url.scheme(), "http" | "https") || image_url.len() > 500 {
+        return Err(anyhow!(
+            "image URL must be an HTTP(S) URL up to 500 characters"
+        ));
+    }
+
+    if CustomEmoji::local_by_shortcode(&shortcode, &state.db_pool)
+        .await?
+        .is_some()
+    {
+        return Err(anyhow!("emoji :{}: already exists", shortcode));
+    }
+
+    let id = DbId::default();
+    CustomEmoji::create(
+        CustomEmoji {
+            ap_id: format!("https://{}/emojis/{}", state.config.web.domain, id),
+            id,
+            shortcode,
+            instance: state.config.web.domain.clone(),
+            local: true,
+            media_type: media_type(&url),
+            image_url,
+            category: category.filter(|category| !category.trim().is_empty()),
+            visible_in_picker,
+            published: Utc::now(),
+            updated: None,
+        },
+        &state.db_pool,
+    )
+    .await
+}
+
+/// Returns `false` if there is no such emoji
+pub async fn remove(shortcode: &str, state: &Arc<AppState>) -> anyhow::Result<bool> {
+    match CustomEmoji::local_by_shortcode(shortcode, &state.db_pool).await? {
+        Some(emoji) => emoji.delete(&state.db_pool).await,
+        None => Ok(false),
+    }
+}
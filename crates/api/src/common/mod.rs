@@ -1,3 +1,8 @@
-pub mod follows;
+pub mod emojis;
+pub mod pkce;
 pub mod posts;
+pub mod push;
+pub mod sessions;
+pub mod tokens;
+pub mod two_factor;
 pub mod users;
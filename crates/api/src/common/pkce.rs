@@ -0,0 +1,64 @@
+//! Proof Key for Code Exchange, [RFC 7636](https://datatracker.ietf.org/doc/html/rfc7636)
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+
+/// Supported values of `code_challenge_method`, the first one is preferred
+pub const CHALLENGE_METHODS: [&str; 2] = ["S256", "plain"];
+
+/// Challenges and verifiers are 43 to 128 unreserved characters
+fn is_valid(value: &str) -> bool {
+    (43..=128).contains(&value.len())
+        && value
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || "-._~".contains(char))
+}
+
+/// Checks the `code_challenge` and `code_challenge_method` of an authorization request and
+/// returns the method, which is `plain` when it's left out. `None` if the request is invalid
+pub fn challenge_method(challenge: &str, method: Option<&str>) -> Option<&'static str> {
+    let method = method.unwrap_or("plain");
+    if !is_valid(challenge) {
+        return None;
+    }
+    CHALLENGE_METHODS
+        .into_iter()
+        .find(|supported| *supported == method)
+}
+
+/// Whether the `code_verifier` of the token request matches the challenge
+pub fn verify(verifier: &str, challenge: &str, method: &str) -> bool {
+    if !is_valid(verifier) {
+        return false;
+    }
+    match method {
+        "S256" => URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) == challenge,
+        "plain" => verifier == challenge,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From appendix B of RFC 7636
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn verifies_s256_and_plain() {
+        assert!(verify(VERIFIER, CHALLENGE, "S256"));
+        assert!(verify(VERIFIER, VERIFIER, "plain"));
+        assert!(!verify(VERIFIER, CHALLENGE, "plain"));
+        assert!(!verify("too-short", "too-short", "plain"));
+    }
+
+    #[test]
+    fn checks_challenge_method() {
+        assert_eq!(challenge_method(CHALLENGE, Some("S256")), Some("S256"));
+        assert_eq!(challenge_method(CHALLENGE, None), Some("plain"));
+        assert_eq!(challenge_method(CHALLENGE, Some("S512")), None);
+        assert_eq!(challenge_method("short", Some("S256")), None);
+    }
+}
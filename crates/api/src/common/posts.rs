@@ -4,17 +4,25 @@ use activitypub_federation::{
     config::Data, fetch::webfinger::webfinger_resolve_actor, traits::Actor,
 };
 use ap::{
-    activities::{create::note::CreateNote, like::Like, undo::like::UndoLike},
-    common::notifications,
+    activities::{
+        create::note::CreateNote,
+        emoji_react::EmojiReact,
+        like::Like,
+        undo::{emoji_react::UndoEmojiReact, like::UndoLike},
+    },
+    common::{emojis, notifications, reactions, streaming},
     objects::{
         announce::{Announce, ApAnnounce},
-        note::ApNote,
+        note::{self, ApNote},
         user::ApUser,
     },
 };
 use chrono::Utc;
 use db::{
-    models::{Post, PostBoost, PostLike, PostMention, User},
+    models::{
+        Article, CustomEmoji, Post, PostBoost, PostLike, PostMention, PostReaction,
+        ScheduledStatusParams, Tag, User,
+    },
     types::{DbId, DbVisibility},
 };
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
@@ -23,11 +31,18 @@ use web::AppState;
 
 use super::users::MENTION_RE;
 
+pub const HASHTAG_RE: &str = r"(?P<prefix>^|[^\w&/])#(?P<name>\w+)";
+
 pub async fn accessible_for(
     post: &Post,
     user: Option<&User>,
     db_pool: &Pool<AsyncPgConnection>,
 ) -> anyhow::Result<bool> {
+    // Local-only posts are only for users of this instance
+    if post.local_only && !user.map(|user| user.local).unwrap_or(false) {
+        return Ok(false);
+    }
+
     if let Some(user) = user {
         match post.visibility {
             DbVisibility::Public | DbVisibility::Unlisted => Ok(true),
@@ -87,6 +102,83 @@ pub struct NewPost {
     pub local_only: bool,
     pub sensitive: bool,
     pub content_warning: Option<String>,
+    /// Makes the post an article, `content` is ignored then
+    pub article: Option<NewArticle>,
+}
+
+/// Title, summary and body of an article in plain text
+pub struct NewArticle {
+    pub title: String,
+    pub summary: Option<String>,
+    pub content: String,
+}
+
+impl NewPost {
+    pub fn to_scheduled_params(&self) -> ScheduledStatusParams {
+        ScheduledStatusParams {
+            text: self.content.clone(),
+            visibility: self.visibility.clone(),
+            in_reply_to_id: self.in_reply.as_ref().map(|post| post.id.to_string()),
+            quote_id: self.quote.as_ref().map(|post| post.id.to_string()),
+            local_only: self.local_only,
+            sensitive: self.sensitive,
+            spoiler_text: self.content_warning.clone(),
+        }
+    }
+
+    /// Replied and quoted posts that were deleted while the post was waiting to
+    /// be published are dropped
+    pub async fn from_scheduled_params(
+        params: ScheduledStatusParams,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            visibility: params.visibility,
+            content: params.text,
+            in_reply: match params.in_reply_to_id {
+                Some(id) => Post::by_id(&DbId::from(id), db_pool).await?,
+                None => None,
+            },
+            quote: match params.quote_id {
+                Some(id) => Post::by_id(&DbId::from(id), db_pool).await?,
+                None => None,
+            },
+            local_only: params.local_only,
+            sensitive: params.sensitive,
+            content_warning: params.spoiler_text,
+            article: None,
+        })
+    }
+}
+
+/// Turns plain text into HTML paragraphs, separated by empty lines
+fn text_to_html(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| {
+            format!(
+                "<p>{}</p>",
+                html_escape::encode_text(paragraph).replace('\n', "<br>")
+            )
+        })
+        .collect()
+}
+
+fn match_hashtags(content: &str) -> Vec<String> {
+    let mut hashtags: Vec<String> = vec![];
+    for captures in regex::Regex::new(HASHTAG_RE)
+        .unwrap()
+        .captures_iter(content)
+    {
+        let hashtag = Tag::normalize_name(&captures["name"]);
+        if !hashtags.contains(&hashtag) {
+            hashtags.push(hashtag);
+        }
+    }
+
+    hashtags
 }
 
 fn match_mentions(content: String) -> Vec<String> {
@@ -112,7 +204,17 @@ pub async fn post(
         id.clone().to_string()
     );
 
-    let mut content = options.content;
+    let article = options.article.map(|article| Article {
+        post_id: id.clone(),
+        title: article.title,
+        summary: article.summary.as_deref().map(text_to_html),
+        content: text_to_html(&article.content),
+    });
+    // Articles are shown as a fallback by software that doesn't support them
+    let mut content = match &article {
+        Some(article) => note::article_fallback(&article.title, article.summary.as_deref(), &ap_id),
+        None => options.content,
+    };
     let mut mentions: Vec<ApUser> = vec![];
 
     for mention in match_mentions(content.clone()) {
@@ -135,6 +237,20 @@ pub async fn post(
         mentions.push(user);
     }
 
+    let hashtags = match_hashtags(&content);
+    let content = regex::Regex::new(HASHTAG_RE)
+        .unwrap()
+        .replace_all(&content, |captures: &regex::Captures| {
+            format!(
+                "{}<a href=\"https://{}/tags/{}\" class=\"mention hashtag\" rel=\"tag\">#<span>{}</span></a>",
+                &captures["prefix"],
+                data.config.web.domain,
+                Tag::normalize_name(&captures["name"]),
+                &captures["name"],
+            )
+        })
+        .to_string();
+
     let mentions_data: Vec<PostMention> = mentions
         .iter()
         .map(|mention| PostMention {
@@ -144,6 +260,11 @@ pub async fn post(
         })
         .collect();
 
+    let quote_author = match &options.quote {
+        Some(quote) => Some(quote.author(&data.db_pool).await?),
+        None => None,
+    };
+
     let post = Post {
         id,
         url: ap_id.clone(),
@@ -160,6 +281,23 @@ pub async fn post(
         content_warning: options.content_warning,
     };
 
+    // The post has to be saved before federating so that its hashtags can be looked up
+    let post = Post::create(post, mentions_data, &data.db_pool).await?;
+    if let Some(article) = article {
+        Article::upsert(article, &data.db_pool).await?;
+    }
+    Tag::attach_to_post(&post.id, hashtags, &data.db_pool).await?;
+    let post_emojis = emojis::local(
+        &[
+            post.content.as_str(),
+            post.content_warning.as_deref().unwrap_or_default(),
+        ],
+        &data.config.web.domain,
+        &data.db_pool,
+    )
+    .await?;
+    CustomEmoji::attach_to_post(&post.id, &post_emojis, &data.db_pool).await?;
+
     if !options.local_only {
         let inboxes = if post.visibility == DbVisibility::Direct {
             mentions
@@ -174,6 +312,9 @@ pub async fn post(
                     .iter()
                     .map(|mention| mention.shared_inbox_or_inbox().to_string()),
             );
+            if let Some(quote_author) = quote_author.filter(|author| !author.local) {
+                inboxes.push(ApUser(quote_author).shared_inbox_or_inbox().to_string());
+            }
             inboxes
         };
 
@@ -190,8 +331,8 @@ pub async fn post(
         .await?;
     }
 
-    let post = Post::create(post, mentions_data, &data.db_pool).await?;
     notifications::process_post(&post, &data.db_pool).await?;
+    streaming::process_post(&post, &data.db_pool).await?;
     Ok(post)
 }
 
@@ -237,6 +378,7 @@ pub async fn boost(
     }
 
     let boost = PostBoost::create(boost, &data.db_pool).await?;
+    streaming::process_boost(&boost, post, &data.db_pool).await?;
     notifications::process_boost(post, user, &author, false, &data.db_pool).await?;
     Ok(boost)
 }
@@ -289,9 +431,120 @@ pub async fn unlike(user: &User, post: &Post, data: &Data<Arc<AppState>>) -> any
     Ok(())
 }
 
+/// Resolves a reaction given by a client: an Unicode emoji, a shortcode of a local custom emoji
+/// or `shortcode@instance` of a remote one, with or without colons
+pub async fn resolve_reaction(
+    name: &str,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Option<(String, Option<CustomEmoji>)>> {
+    let name = name.trim();
+    if reactions::is_unicode_emoji(name) {
+        return Ok(Some((name.to_string(), None)));
+    }
+
+    let name = name.trim_matches(':');
+    let emoji = match name.split_once('@') {
+        Some((shortcode, instance)) => {
+            CustomEmoji::by_shortcodes(&[shortcode.to_string()], instance, db_pool)
+                .await?
+                .into_iter()
+                .next()
+        },
+        None => CustomEmoji::local_by_shortcode(name, db_pool).await?,
+    };
+
+    Ok(emoji.map(|emoji| (reactions::custom_emoji_name(&emoji), Some(emoji))))
+}
+
+pub async fn react(
+    user: &User,
+    post: &Post,
+    name: String,
+    emoji: Option<CustomEmoji>,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    if PostReaction::by_post_actor_and_name(post, user, &name, &data.db_pool)
+        .await?
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    let author = post.author(&data.db_pool).await?;
+    let id = if author.local {
+        None
+    } else {
+        Some(
+            EmojiReact::send(
+                &ApUser(user.clone()),
+                &ApUser(author.clone()),
+                &ApNote(post.clone()),
+                &name,
+                emoji.as_ref(),
+                data,
+            )
+            .await?
+            .to_string(),
+        )
+    };
+
+    PostReaction::create(
+        PostReaction {
+            id: DbId::default(),
+            ap_id: id,
+            post_id: post.id.clone(),
+            actor_id: user.id.clone(),
+            name: name.clone(),
+            emoji_id: emoji.map(|emoji| emoji.id),
+            published: Utc::now(),
+        },
+        &data.db_pool,
+    )
+    .await?;
+    notifications::process_reaction(post, user, &author, &name, false, &data.db_pool).await?;
+
+    Ok(())
+}
+
+pub async fn unreact(
+    user: &User,
+    post: &Post,
+    name: &str,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    let reaction =
+        match PostReaction::by_post_actor_and_name(post, user, name, &data.db_pool).await? {
+            Some(reaction) => reaction,
+            None => return Ok(()),
+        };
+
+    let author = post.author(&data.db_pool).await?;
+    if let (false, Some(ap_id)) = (author.local, &reaction.ap_id) {
+        let emoji = match &reaction.emoji_id {
+            Some(emoji_id) => CustomEmoji::by_id(emoji_id, &data.db_pool).await?,
+            None => None,
+        };
+        UndoEmojiReact::send(
+            Url::parse(ap_id)?,
+            &ApUser(user.clone()),
+            &ApUser(author.clone()),
+            &ApNote(post.clone()),
+            &reaction.name,
+            emoji.as_ref(),
+            data,
+        )
+        .await?;
+    }
+
+    reaction.delete(&data.db_pool).await?;
+    notifications::process_reaction(post, user, &author, name, true, &data.db_pool).await?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::common::posts::match_mentions;
+    use crate::common::posts::{match_hashtags, match_mentions, text_to_html};
 
     #[test]
     fn mentions() {
@@ -307,4 +560,24 @@ mod tests {
         let result = match_mentions("@cryap&@vector1dev".to_string());
         assert_eq!(result, vec!["cryap", "vector1dev"]);
     }
+
+    #[test]
+    fn hashtags() {
+        let result = match_hashtags("Hi!");
+        assert!(result.len() == 0);
+
+        let result = match_hashtags("#Rust is #rust, #fediverse too");
+        assert_eq!(result, vec!["rust", "fediverse"]);
+
+        let result = match_hashtags("issue#1 &#39; https://example.com/#anchor");
+        assert!(result.len() == 0);
+    }
+
+    #[test]
+    fn paragraphs() {
+        assert_eq!(
+            text_to_html("First <b>line</b>\nsecond line\r\n\r\n\n\nNext paragraph\n"),
+            "<p>First &lt;b&gt;line&lt;/b&gt;<br>second line</p><p>Next paragraph</p>"
+        );
+    }
 }
@@ -0,0 +1,381 @@
+//! Web Push: messages are encrypted as in RFC 8291 and the push service is told who sends them
+//! with VAPID (RFC 8292)
+
+use std::time::Duration;
+
+use aes_gcm::{aead::Aead, Aes128Gcm, KeyInit, Nonce};
+use anyhow::anyhow;
+use ap::common::profile_fields;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use db::models::PushSubscription;
+use hkdf::Hkdf;
+use p256::{
+    ecdh::EphemeralSecret,
+    ecdsa::{signature::Signer, Signature, SigningKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+    PublicKey, SecretKey,
+};
+use rand::{rngs::OsRng, RngCore};
+use reqwest::{redirect::Policy, StatusCode};
+use serde_json::json;
+use sha2::Sha256;
+use url::Url;
+
+/// Notification types that can be pushed, as in the `alerts` of a subscription
+pub const ALERTS: [&str; 10] = [
+    "mention",
+    "status",
+    "reblog",
+    "follow",
+    "follow_request",
+    "favourite",
+    "poll",
+    "update",
+    "quote",
+    "pleroma:emoji_reaction",
+];
+pub const POLICIES: [&str; 4] = ["all", "followed", "follower", "none"];
+
+/// Size of the single record the payload is encrypted into
+const RECORD_SIZE: u32 = 4096;
+/// How long the push service keeps a message while the device is offline
+const TTL: u32 = 48 * 60 * 60;
+const VAPID_EXPIRY: i64 = 12 * 60 * 60;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushOutcome {
+    Delivered,
+    /// The subscription has expired or was unsubscribed, it should be removed
+    Gone,
+    /// The push service is unavailable or rate limited, the message can be sent again later
+    Retry,
+    /// The push service rejected the message
+    Failed,
+}
+
+/// New base64url-encoded VAPID private key
+pub fn generate_vapid_key() -> String {
+    URL_SAFE_NO_PAD.encode(SecretKey::random(&mut OsRng).to_bytes())
+}
+
+/// Reads the VAPID private key from the file, generating it on the first start
+pub fn load_vapid_key(path: &str) -> anyhow::Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(key) => {
+            let key = key.trim().to_string();
+            secret_key(&key)?;
+            Ok(key)
+        },
+        Err(_) => {
+            let key = generate_vapid_key();
+            std::fs::write(path, &key)?;
+            Ok(key)
+        },
+    }
+}
+
+fn secret_key(vapid_key: &str) -> anyhow::Result<SecretKey> {
+    Ok(SecretKey::from_slice(&URL_SAFE_NO_PAD.decode(vapid_key)?)?)
+}
+
+/// Base64url-encoded uncompressed public key, what clients pass as `applicationServerKey`
+pub fn vapid_public_key(vapid_key: &str) -> anyhow::Result<String> {
+    Ok(URL_SAFE_NO_PAD.encode(
+        secret_key(vapid_key)?
+            .public_key()
+            .to_encoded_point(false)
+            .as_bytes(),
+    ))
+}
+
+/// Origin of the push service, the audience of the VAPID token
+fn origin(endpoint: &Url) -> String {
+    match endpoint.port() {
+        Some(port) => format!(
+            "{}://{}:{}",
+            endpoint.scheme(),
+            endpoint.host_str().unwrap_or_default(),
+            port
+        ),
+        None => format!(
+            "{}://{}",
+            endpoint.scheme(),
+            endpoint.host_str().unwrap_or_default()
+        ),
+    }
+}
+
+/// `Authorization` header with an ES256 JWT signed by the VAPID key
+fn vapid_authorization(endpoint: &Url, vapid_key: &str, domain: &str) -> anyhow::Result<String> {
+    let secret_key = secret_key(vapid_key)?;
+    let header = URL_SAFE_NO_PAD.encode(json!({ "typ": "JWT", "alg": "ES256" }).to_string());
+    let claims = URL_SAFE_NO_PAD.encode(
+        json!({
+            "aud": origin(endpoint),
+            "exp": Utc::now().timestamp() + VAPID_EXPIRY,
+            "sub": format!("https://{}", domain),
+        })
+        .to_string(),
+    );
+    let signing_input = format!("{}.{}", header, claims);
+    let signature: Signature = SigningKey::from(&secret_key).sign(signing_input.as_bytes());
+
+    Ok(format!(
+        "vapid t={}.{}, k={}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+        vapid_public_key(vapid_key)?
+    ))
+}
+
+fn hkdf_expand<const N: usize>(salt: &[u8], ikm: &[u8], info: &[u8]) -> anyhow::Result<[u8; N]> {
+    let mut okm = [0u8; N];
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(info, &mut okm)
+        .map_err(|_| anyhow!("Invalid HKDF output length"))?;
+    Ok(okm)
+}
+
+/// Content encryption key and nonce of a message, see section 3.4 of RFC 8291
+fn derive_key_and_nonce(
+    shared_secret: &[u8],
+    auth: &[u8],
+    ua_public: &[u8],
+    as_public: &[u8],
+    salt: &[u8],
+) -> anyhow::Result<([u8; 16], [u8; 12])> {
+    let key_info = [b"WebPush: info\0".as_slice(), ua_public, as_public].concat();
+    let ikm: [u8; 32] = hkdf_expand(auth, shared_secret, &key_info)?;
+    Ok((
+        hkdf_expand(salt, &ikm, b"Content-Encoding: aes128gcm\0")?,
+        hkdf_expand(salt, &ikm, b"Content-Encoding: nonce\0")?,
+    ))
+}
+
+/// Encrypts the payload for the subscriber with the `aes128gcm` content coding
+pub fn encrypt(payload: &[u8], p256dh: &str, auth: &str) -> anyhow::Result<Vec<u8>> {
+    let ua_public = URL_SAFE_NO_PAD.decode(p256dh.trim_end_matches('='))?;
+    let auth = URL_SAFE_NO_PAD.decode(auth.trim_end_matches('='))?;
+    let ua_key = PublicKey::from_sec1_bytes(&ua_public)?;
+
+    let ephemeral = EphemeralSecret::random(&mut OsRng);
+    let as_public = ephemeral.public_key().to_encoded_point(false);
+    let shared_secret = ephemeral.diffie_hellman(&ua_key);
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+
+    let (key, nonce) = derive_key_and_nonce(
+        shared_secret.raw_secret_bytes(),
+        &auth,
+        &ua_public,
+        as_public.as_bytes(),
+        &salt,
+    )?;
+    // A single record, the padding delimiter marks it as the last one
+    let plaintext = [payload, &[2u8]].concat();
+    if plaintext.len() + 16 > RECORD_SIZE as usize {
+        return Err(anyhow!("Push payload is too large"));
+    }
+    let ciphertext = Aes128Gcm::new_from_slice(&key)
+        .map_err(|_| anyhow!("Invalid content encryption key"))?
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| anyhow!("Couldn't encrypt the push payload"))?;
+
+    Ok([
+        salt.as_slice(),
+        &RECORD_SIZE.to_be_bytes(),
+        &[as_public.len() as u8],
+        as_public.as_bytes(),
+        &ciphertext,
+    ]
+    .concat())
+}
+
+/// Client for the endpoint of a subscription. It only connects to the public address the host
+/// resolved to, so that subscriptions can't make the server send requests into its own network
+pub async fn client(
+    endpoint: &Url,
+    allow_private_addresses: bool,
+) -> anyhow::Result<reqwest::Client> {
+    let address = profile_fields::resolve(endpoint, allow_private_addresses).await?;
+    let mut client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .redirect(Policy::none());
+    if let Some(domain) = endpoint.domain() {
+        client = client.resolve(domain, address);
+    }
+    Ok(client.build()?)
+}
+
+/// Sends an encrypted message to the push service of the subscription
+pub async fn send(
+    subscription: &PushSubscription,
+    payload: &[u8],
+    vapid_key: &str,
+    domain: &str,
+    client: &reqwest::Client,
+) -> anyhow::Result<PushOutcome> {
+    let endpoint = Url::parse(&subscription.endpoint)?;
+    let body = encrypt(payload, &subscription.p256dh, &subscription.auth)?;
+
+    let response = client
+        .post(endpoint.clone())
+        .header(
+            "Authorization",
+            vapid_authorization(&endpoint, vapid_key, domain)?,
+        )
+        .header("Content-Encoding", "aes128gcm")
+        .header("Content-Type", "application/octet-stream")
+        .header("TTL", TTL.to_string())
+        .header("Urgency", "normal")
+        .body(body)
+        .send()
+        .await;
+
+    Ok(match response {
+        Ok(response) => match response.status() {
+            status if status.is_success() => PushOutcome::Delivered,
+            StatusCode::NOT_FOUND | StatusCode::GONE => PushOutcome::Gone,
+            status if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() => {
+                PushOutcome::Retry
+            },
+            _ => PushOutcome::Failed,
+        },
+        Err(_) => PushOutcome::Retry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{
+        body::Bytes,
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use db::types::DbId;
+    use p256::{
+        ecdh::diffie_hellman,
+        ecdsa::{signature::Verifier, VerifyingKey},
+    };
+
+    use super::*;
+
+    type Received = Arc<Mutex<Option<(HeaderMap, Bytes)>>>;
+
+    /// What a user agent does with a message it receives from the push service
+    fn decrypt(body: &[u8], ua_secret: &SecretKey, auth: &[u8]) -> Vec<u8> {
+        let salt = &body[..16];
+        let record_size = u32::from_be_bytes(body[16..20].try_into().unwrap());
+        assert_eq!(record_size, RECORD_SIZE);
+        let key_length = body[20] as usize;
+        let as_public = &body[21..21 + key_length];
+        let ciphertext = &body[21 + key_length..];
+
+        let shared_secret = diffie_hellman(
+            ua_secret.to_nonzero_scalar(),
+            PublicKey::from_sec1_bytes(as_public).unwrap().as_affine(),
+        );
+        let ua_public = ua_secret.public_key().to_encoded_point(false);
+        let (key, nonce) = derive_key_and_nonce(
+            shared_secret.raw_secret_bytes(),
+            auth,
+            ua_public.as_bytes(),
+            as_public,
+            salt,
+        )
+        .unwrap();
+        Aes128Gcm::new_from_slice(&key)
+            .unwrap()
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .unwrap()
+    }
+
+    fn subscription(endpoint: String, ua_secret: &SecretKey, auth: &[u8]) -> PushSubscription {
+        PushSubscription {
+            id: DbId::default(),
+            session_id: DbId::default(),
+            user_id: DbId::default(),
+            endpoint,
+            p256dh: URL_SAFE_NO_PAD.encode(ua_secret.public_key().to_encoded_point(false)),
+            auth: URL_SAFE_NO_PAD.encode(auth),
+            alerts: String::from("mention"),
+            policy: String::from("all"),
+            published: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn delivery() {
+        let received: Received = Arc::new(Mutex::new(None));
+        let app = Router::new()
+            .route(
+                "/push/active",
+                post(
+                    |State(received): State<Received>, headers: HeaderMap, body: Bytes| async move {
+                        *received.lock().unwrap() = Some((headers, body));
+                        StatusCode::CREATED
+                    },
+                ),
+            )
+            .route("/push/expired", post(|| async { StatusCode::GONE }))
+            .with_state(Arc::clone(&received));
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let address = server.local_addr();
+        tokio::spawn(server);
+
+        let vapid_key = generate_vapid_key();
+        let ua_secret = SecretKey::random(&mut OsRng);
+        let auth = [7u8; 16];
+        let payload = br#"{"notification_type":"mention","title":"Hi"}"#;
+        let active = subscription(format!("http://{address}/push/active"), &ua_secret, &auth);
+        let endpoint = Url::parse(&active.endpoint).unwrap();
+        // The test server is on the loopback address, which is refused unless explicitly allowed
+        assert!(client(&endpoint, false).await.is_err());
+        let client = client(&endpoint, true).await.unwrap();
+        assert_eq!(
+            send(&active, payload, &vapid_key, "cryap.example", &client)
+                .await
+                .unwrap(),
+            PushOutcome::Delivered
+        );
+        let (headers, body) = received.lock().unwrap().take().unwrap();
+        assert_eq!(headers["content-encoding"], "aes128gcm");
+        assert_eq!(
+            decrypt(&body, &ua_secret, &auth),
+            [payload.as_slice(), &[2u8]].concat()
+        );
+
+        let authorization = headers["authorization"].to_str().unwrap();
+        let (token, public_key) = authorization
+            .strip_prefix("vapid t=")
+            .unwrap()
+            .split_once(", k=")
+            .unwrap();
+        assert_eq!(public_key, vapid_public_key(&vapid_key).unwrap());
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let verifying_key =
+            VerifyingKey::from_sec1_bytes(&URL_SAFE_NO_PAD.decode(public_key).unwrap()).unwrap();
+        let signature = Signature::from_slice(&URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+        assert!(verifying_key
+            .verify(signing_input.as_bytes(), &signature)
+            .is_ok());
+
+        let expired = subscription(format!("http://{address}/push/expired"), &ua_secret, &auth);
+        assert_eq!(
+            send(&expired, payload, &vapid_key, "cryap.example", &client)
+                .await
+                .unwrap(),
+            PushOutcome::Gone
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ap::common::streaming::{StreamingEvent, EVENT_BUS};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use db::{
+    models::{Application, Session},
+    types::DbId,
+};
+use sha2::{Digest, Sha256};
+use web::AppState;
+
+use crate::entities::Session as ApiSession;
+
+/// Sessions of the user with their applications, `current` is marked in the list
+pub async fn list(
+    user_id: &DbId,
+    current: &DbId,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Vec<ApiSession>> {
+    let applications: HashMap<DbId, Application> =
+        Application::authorized_by(user_id, &state.db_pool)
+            .await?
+            .into_iter()
+            .map(|application| (application.id.clone(), application))
+            .collect();
+
+    Ok(Session::get_for_user(user_id, &state.db_pool)
+        .await?
+        .into_iter()
+        .map(|session| {
+            let application = session
+                .application_id
+                .as_ref()
+                .and_then(|application_id| applications.get(application_id))
+                .cloned();
+            let is_current = &session.id == current;
+            ApiSession::new(session, application, is_current)
+        })
+        .collect())
+}
+
+/// Token that forms of the settings pages are submitted with, so that other sites can't submit
+/// them with the sign-in cookie. It's derived from the session token, which they can't read
+pub fn csrf_token(session: &Session) -> String {
+    URL_SAFE_NO_PAD.encode(
+        Sha256::new()
+            .chain_update(b"csrf:")
+            .chain_update(session.token.as_bytes())
+            .finalize(),
+    )
+}
+
+/// Closes the streaming connections of the sessions, in every process. Called whenever sessions
+/// are removed or their tokens replaced
+pub async fn disconnect(user_id: &DbId, session_ids: Vec<DbId>) {
+    if session_ids.is_empty() {
+        return;
+    }
+
+    EVENT_BUS
+        .send(user_id, StreamingEvent::sessions_revoked(session_ids))
+        .await;
+}
+
+/// Signs the session out, including its streaming connections
+pub async fn revoke(session: &Session, state: &Arc<AppState>) -> anyhow::Result<()> {
+    session.delete(&state.db_pool).await?;
+    disconnect(&session.user_id, vec![session.id.clone()]).await;
+    Ok(())
+}
+
+/// Revokes every token the user has given to the application. Returns whether there were any
+pub async fn revoke_application(
+    user_id: &DbId,
+    application_id: &DbId,
+    state: &Arc<AppState>,
+) -> anyhow::Result<bool> {
+    let session_ids =
+        Session::delete_for_application(user_id, application_id, &state.db_pool).await?;
+    if session_ids.is_empty() {
+        return Ok(false);
+    }
+
+    disconnect(user_id, session_ids).await;
+    Ok(true)
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use web::AppState;
+
+/// When a token issued to an application now expires, following the `access_token_lifetime`
+/// setting
+pub fn expires_at(state: &Arc<AppState>) -> Option<DateTime<Utc>> {
+    state
+        .config
+        .instance
+        .access_token_lifetime
+        .map(|lifetime| Utc::now() + Duration::seconds(lifetime))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use db::{
+        models::{Session, User, UserInsert},
+        schema::users,
+        types::DbId,
+    };
+    use diesel::insert_into;
+    use diesel_async::{
+        pooled_connection::{deadpool::Pool, AsyncDieselConnectionManager},
+        AsyncPgConnection, RunQueryDsl,
+    };
+
+    /// Migrated database from `DATABASE_URL`
+    async fn db_pool() -> Pool<AsyncPgConnection> {
+        let uri = std::env::var("DATABASE_URL").expect("DATABASE_URL should be set");
+        let db_pool = Pool::builder(AsyncDieselConnectionManager::<AsyncPgConnection>::new(uri))
+            .build()
+            .unwrap();
+        db::migrations::run_migrations(&mut db_pool.get().await.unwrap())
+            .await
+            .unwrap();
+        db_pool
+    }
+
+    /// Unapproved user, so that `delete_pending` cleans it up with its sessions
+    async fn create_user(db_pool: &Pool<AsyncPgConnection>) -> User {
+        let id = DbId::default();
+        let name = id.to_string().to_lowercase();
+        insert_into(users::table)
+            .values(UserInsert {
+                id,
+                ap_id: format!("https://cryap.example/u/{name}"),
+                local: true,
+                inbox_uri: format!("https://cryap.example/u/{name}/ap/inbox"),
+                shared_inbox_uri: None,
+                outbox_uri: format!("https://cryap.example/u/{name}/ap/outbox"),
+                followers_uri: format!("https://cryap.example/u/{name}/ap/followers"),
+                name,
+                instance: String::from("cryap.example"),
+                display_name: None,
+                bio: None,
+                password_encrypted: None,
+                admin: false,
+                public_key: String::new(),
+                private_key: None,
+                published: Utc::now(),
+                updated: None,
+                manually_approves_followers: false,
+                is_cat: false,
+                bot: false,
+                fields: serde_json::json!([]),
+                emojis: serde_json::json!([]),
+                also_known_as: serde_json::json!([]),
+                approved: false,
+                registration_reason: None,
+                invite_id: None,
+            })
+            .get_result::<User>(&mut db_pool.get().await.unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL database in DATABASE_URL"]
+    async fn refresh_tokens_are_single_use() {
+        let db_pool = db_pool().await;
+        let user = create_user(&db_pool).await;
+        let expires_at = Some(Utc::now() + Duration::hours(1));
+        let session = Session::create(
+            user.id.clone(),
+            None,
+            String::from("read"),
+            expires_at,
+            false,
+            &db_pool,
+        )
+        .await
+        .unwrap();
+
+        let rotated = session
+            .rotate(String::from("read"), expires_at, &db_pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(rotated.token, session.token);
+        assert_ne!(rotated.refresh_token, session.refresh_token);
+        assert!(Session::by_token(&session.token, &db_pool)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(Session::by_token(&rotated.token, &db_pool)
+            .await
+            .unwrap()
+            .is_some());
+
+        // A second refresh with the same token, e.g. a concurrent one, gets nothing
+        assert!(session
+            .rotate(String::from("read"), expires_at, &db_pool)
+            .await
+            .unwrap()
+            .is_none());
+
+        user.delete_pending(&db_pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL database in DATABASE_URL"]
+    async fn expired_tokens_can_only_be_refreshed() {
+        let db_pool = db_pool().await;
+        let user = create_user(&db_pool).await;
+        let session = Session::create(
+            user.id.clone(),
+            None,
+            String::from("read"),
+            Some(Utc::now() - Duration::minutes(1)),
+            false,
+            &db_pool,
+        )
+        .await
+        .unwrap();
+
+        assert!(Session::by_token(&session.token, &db_pool)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            Session::by_refresh_token(session.refresh_token.as_deref().unwrap(), &db_pool)
+                .await
+                .unwrap(),
+            Some(session)
+        );
+
+        user.delete_pending(&db_pool).await.unwrap();
+    }
+}
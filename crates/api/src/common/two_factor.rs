@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use chrono::Utc;
+use db::{
+    models::{user::UserUpdate, RecoveryCode, User},
+    utils::random_string,
+};
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+use web::AppState;
+
+pub const RECOVERY_CODES_COUNT: usize = 10;
+
+/// What an authenticator app needs to be set up, `qr_code` is a PNG data URI of
+/// `provisioning_uri`
+pub struct Provisioning {
+    pub key: String,
+    pub provisioning_uri: String,
+    pub qr_code: String,
+}
+
+/// New base32 TOTP secret
+pub fn generate_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn totp(secret: &str, user: &User, state: &Arc<AppState>) -> anyhow::Result<TOTP> {
+    Ok(TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        Secret::Encoded(secret.to_string()).to_bytes()?,
+        // The issuer and the account name are separated by a colon in the URI
+        Some(state.config.instance.title.replace(':', "")),
+        format!("{}@{}", user.name, state.config.web.domain),
+    )?)
+}
+
+pub fn provisioning(
+    secret: &str,
+    user: &User,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Provisioning> {
+    let totp = totp(secret, user, state)?;
+    Ok(Provisioning {
+        key: secret.to_string(),
+        provisioning_uri: totp.get_url(),
+        qr_code: format!(
+            "data:image/png;base64,{}",
+            totp.get_qr_base64().map_err(|err| anyhow!(err))?
+        ),
+    })
+}
+
+/// Time step the code was generated for, allowing one step of clock drift either way
+fn matching_timestep(totp: &TOTP, code: &str, time: u64) -> Option<u64> {
+    let current = time / totp.step;
+    (current.saturating_sub(totp.skew as u64)..=current + totp.skew as u64)
+        .find(|timestep| totp.generate(timestep * totp.step) == code)
+}
+
+/// Checks a code from an authenticator app against the secret. Each code is accepted only once,
+/// like in Mastodon, so that an observed code can't be used for another sign-in
+pub async fn check_totp(
+    secret: &str,
+    code: &str,
+    user: &User,
+    state: &Arc<AppState>,
+) -> anyhow::Result<bool> {
+    let totp = totp(secret, user, state)?;
+    match matching_timestep(&totp, code.trim(), Utc::now().timestamp() as u64) {
+        Some(timestep) => {
+            user.consume_otp_timestep(timestep as i64, &state.db_pool)
+                .await
+        },
+        None => Ok(false),
+    }
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(code.trim().to_lowercase().as_bytes())
+    )
+}
+
+/// Replaces the recovery codes of the user, the returned codes can't be shown again
+pub async fn generate_recovery_codes(
+    user: &User,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Vec<String>> {
+    let codes: Vec<String> = (0..RECOVERY_CODES_COUNT)
+        .map(|_| random_string(10).to_lowercase())
+        .collect();
+    RecoveryCode::replace_for_user(
+        &user.id,
+        codes.iter().map(|code| hash_recovery_code(code)).collect(),
+        &state.db_pool,
+    )
+    .await?;
+    Ok(codes)
+}
+
+/// Checks the second step of a sign-in. Recovery codes are removed once used
+pub async fn verify(user: &User, code: &str, state: &Arc<AppState>) -> anyhow::Result<bool> {
+    let secret = match &user.otp_secret {
+        Some(secret) if user.otp_enabled => secret,
+        _ => return Ok(false),
+    };
+    let code = code.trim();
+    if code.len() == 6 && code.chars().all(|char| char.is_ascii_digit()) {
+        check_totp(secret, code, user, state).await
+    } else {
+        RecoveryCode::redeem(&user.id, &hash_recovery_code(code), &state.db_pool).await
+    }
+}
+
+/// Turns 2FA on with a secret the user has confirmed and returns new recovery codes
+pub async fn enable(
+    user: &User,
+    secret: String,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Vec<String>> {
+    let mut updated_user = UserUpdate::new();
+    updated_user.otp_secret = Some(Some(secret));
+    updated_user.otp_enabled = Some(true);
+    user.update(updated_user, &state.db_pool).await?;
+    generate_recovery_codes(user, state).await
+}
+
+pub async fn disable(user: &User, state: &Arc<AppState>) -> anyhow::Result<()> {
+    let mut updated_user = UserUpdate::new();
+    updated_user.otp_secret = Some(None);
+    updated_user.otp_enabled = Some(false);
+    user.update(updated_user, &state.db_pool).await?;
+    RecoveryCode::delete_for_user(&user.id, &state.db_pool).await
+}
+
+/// Whether the user has to set up 2FA before they can sign in
+pub fn is_required(user: &User, state: &Arc<AppState>) -> bool {
+    user.admin && state.config.instance.require_two_factor_for_admins && !user.otp_enabled
+}
+
+#[cfg(test)]
+mod tests {
+    use totp_rs::{Algorithm, Secret, TOTP};
+
+    use super::{hash_recovery_code, matching_timestep};
+
+    #[test]
+    fn recovery_codes_are_hashed_case_insensitively() {
+        assert_eq!(
+            hash_recovery_code(" AbCdE12345 "),
+            hash_recovery_code("abcde12345")
+        );
+        assert_eq!(hash_recovery_code("abcde12345").len(), 64);
+    }
+
+    #[test]
+    fn codes_match_their_time_step() {
+        let totp = TOTP::new(
+            Algorithm::SHA1,
+            6,
+            1,
+            30,
+            Secret::Raw(b"12345678901234567890".to_vec())
+                .to_bytes()
+                .unwrap(),
+            None,
+            String::from("vector1dev@cryap.example"),
+        )
+        .unwrap();
+        let code = totp.generate(59);
+
+        assert_eq!(matching_timestep(&totp, &code, 59), Some(1));
+        // One step of drift either way
+        assert_eq!(matching_timestep(&totp, &code, 30 * 2), Some(1));
+        assert_eq!(matching_timestep(&totp, &code, 0), Some(1));
+        assert_eq!(matching_timestep(&totp, &code, 30 * 3), None);
+    }
+}
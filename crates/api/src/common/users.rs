@@ -1,24 +1,32 @@
 use std::sync::Arc;
 
 use activitypub_federation::{
-    activity_queue::queue_activity, config::Data, http_signatures::generate_actor_keypair,
+    config::Data, fetch::webfinger::webfinger_resolve_actor,
+    http_signatures::generate_actor_keypair,
 };
 use anyhow::anyhow;
-use ap::{activities::update::Update, objects::user::ApUser};
+use ap::{
+    activities::{delete::DeleteUser, update::Update},
+    common::{delivery::queue_activity, emojis, follows},
+    objects::user::ApUser,
+};
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2,
+    Argon2, PasswordHash, PasswordVerifier,
 };
 use chrono::Utc;
 use db::{
-    models::{User, UserInsert},
+    models::{user::UserUpdate, Invite, Session, User, UserInsert},
     schema::users,
     types::DbId,
 };
 use diesel::{insert_into, ExpressionMethods, QueryDsl};
 use diesel_async::RunQueryDsl;
+use regex::Regex;
 use url::Url;
-use web::AppState;
+use web::{config::RegistrationMode, AppState};
+
+use crate::common::sessions;
 
 pub const USERNAME_RE: &str = r"[a-z0-9_]+([a-z0-9_.-]+[a-z0-9_]+)?";
 pub const MENTION_RE: &str = r"@(?P<name>[\w.]+)(@(?P<domain>[a-zA-Z0-9._:-]+))?";
@@ -28,6 +36,8 @@ pub async fn register(
     password: String,
     bio: Option<String>,
     display_name: Option<String>,
+    approved: bool,
+    registration_reason: Option<String>,
     state: &Arc<AppState>,
 ) -> anyhow::Result<ApUser> {
     let mut conn = state.db_pool.get().await?;
@@ -51,6 +61,16 @@ pub async fn register(
         Err(_) => return Err(anyhow!("password hashing failed")),
     };
 
+    let emojis = emojis::local_for_user(
+        &[
+            display_name.as_deref().unwrap_or_default(),
+            bio.as_deref().unwrap_or_default(),
+        ],
+        &state.config.web.domain,
+        &state.db_pool,
+    )
+    .await?;
+
     let user = UserInsert {
         id: DbId::default(),
         ap_id: ap_id.clone(),
@@ -72,6 +92,12 @@ pub async fn register(
         manually_approves_followers: false,
         is_cat: false,
         bot: false,
+        fields: serde_json::Value::Array(vec![]),
+        emojis,
+        also_known_as: serde_json::Value::Array(vec![]),
+        approved,
+        registration_reason,
+        invite_id: None,
     };
 
     Ok(ApUser(
@@ -108,3 +134,145 @@ pub async fn distribute_update(user: &User, data: &Data<Arc<AppState>>) -> anyho
     .await?;
     Ok(())
 }
+
+/// Checks the password of a local user
+pub fn verify_password(user: &User, password: &str) -> bool {
+    let hash = match &user.password_encrypted {
+        Some(hash) => hash,
+        None => return false,
+    };
+    match PasswordHash::new(hash) {
+        Ok(parsed_hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Finds a user by `name` or `name@instance`, fetching remote users through WebFinger if needed
+pub async fn resolve_acct(acct: &str, data: &Data<Arc<AppState>>) -> anyhow::Result<Option<User>> {
+    let acct = acct.trim_start_matches('@');
+    if !acct.contains('@') {
+        return User::local_by_name(acct, &data.db_pool).await;
+    }
+
+    match User::by_acct(acct.to_string(), &data.db_pool).await? {
+        Some(user) => Ok(Some(user)),
+        None => {
+            let user: Result<ApUser, _> = webfinger_resolve_actor(acct, data).await;
+            Ok(user.ok().map(|user| user.0))
+        },
+    }
+}
+
+/// Tells other servers that the user is gone and purges everything of theirs, except for the
+/// tombstone that keeps the name taken
+pub async fn delete(user: &User, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+    DeleteUser::send(&ApUser(user.clone()), data).await?;
+    let session_ids = Session::get_for_user(&user.id, &data.db_pool)
+        .await?
+        .into_iter()
+        .map(|session| session.id)
+        .collect();
+    user.purge(&data.db_pool).await?;
+    sessions::disconnect(&user.id, session_ids).await;
+    Ok(())
+}
+
+pub const USERNAME_MAX_CHARACTERS: usize = 30;
+pub const PASSWORD_MIN_CHARACTERS: usize = 8;
+/// Same as in Mastodon
+pub const REGISTRATION_REASON_MAX_CHARACTERS: usize = 420;
+
+/// Checks a sign-up request from the API or the sign-up page, returning the message to show if
+/// it's invalid
+pub async fn validate_registration(
+    name: &str,
+    password: &str,
+    agreement: bool,
+    reason: Option<&str>,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Option<String>> {
+    if name.chars().count() > USERNAME_MAX_CHARACTERS
+        || !Regex::new(&format!("^{USERNAME_RE}$"))?.is_match(name)
+    {
+        return Ok(Some(format!("Username must contain only lowercase letters, numbers, underscores, dots and dashes, and be at most {USERNAME_MAX_CHARACTERS} characters long")));
+    }
+    if User::local_by_name(name, &state.db_pool).await?.is_some() {
+        return Ok(Some(String::from("Username is already taken")));
+    }
+    if password.chars().count() < PASSWORD_MIN_CHARACTERS {
+        return Ok(Some(format!(
+            "Password is too short (minimum is {PASSWORD_MIN_CHARACTERS} characters)"
+        )));
+    }
+    if !agreement {
+        return Ok(Some(String::from("Agreement must be accepted")));
+    }
+    if reason.is_some_and(|reason| reason.chars().count() > REGISTRATION_REASON_MAX_CHARACTERS) {
+        return Ok(Some(format!(
+            "Reason is too long (maximum is {REGISTRATION_REASON_MAX_CHARACTERS} characters)"
+        )));
+    }
+    Ok(None)
+}
+
+/// Invites can't be set to expire more than a year ahead, in seconds
+pub const INVITE_MAX_EXPIRES_IN: i64 = 365 * 24 * 60 * 60;
+
+/// Whether someone can sign up under the `registrations` setting of the instance
+pub enum SignUpAccess {
+    /// `approved` is `false` if the account has to wait for an admin. Invites skip the approval
+    Allowed {
+        approved: bool,
+        invite: Option<Invite>,
+    },
+    Closed,
+    InvalidInvite,
+}
+
+pub async fn sign_up_access(
+    invite_code: Option<&str>,
+    state: &Arc<AppState>,
+) -> anyhow::Result<SignUpAccess> {
+    if let Some(code) = invite_code.filter(|code| !code.is_empty()) {
+        return Ok(match Invite::by_code(code, &state.db_pool).await? {
+            Some(invite) if invite.is_valid() => SignUpAccess::Allowed {
+                approved: true,
+                invite: Some(invite),
+            },
+            _ => SignUpAccess::InvalidInvite,
+        });
+    }
+
+    Ok(match state.config.instance.registrations {
+        RegistrationMode::Open => SignUpAccess::Allowed {
+            approved: true,
+            invite: None,
+        },
+        RegistrationMode::ApprovalRequired => SignUpAccess::Allowed {
+            approved: false,
+            invite: None,
+        },
+        RegistrationMode::Closed => SignUpAccess::Closed,
+    })
+}
+
+/// Records the invite a new user signed up with and makes them follow its creator if the invite
+/// asks for it. The invite has to be claimed with [`Invite::claim`] before registering
+pub async fn accept_invite(
+    user: &User,
+    invite: &Invite,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    let mut updated_user = UserUpdate::new();
+    updated_user.invite_id = Some(Some(invite.id.clone()));
+    user.update(updated_user, &data.db_pool).await?;
+
+    if let Some(inviter_id) = invite.user_id.as_ref().filter(|_| invite.autofollow) {
+        if let Some(inviter) = User::by_id(inviter_id, &data.db_pool).await? {
+            follows::want_to_follow(user, &inviter, data).await?;
+        }
+    }
+    Ok(())
+}
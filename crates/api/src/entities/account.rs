@@ -1,11 +1,36 @@
+use ap::common::profile_fields;
 use chrono::{DateTime, NaiveDate, Utc};
-use db::{models::User, types::DbVisibility};
+use db::{
+    models::{User, UserField},
+    types::DbVisibility,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
 use serde::Serialize;
 
+use super::CustomEmoji;
+
+// https://docs.joinmastodon.org/entities/Account/#Field
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct AccountField {
+    pub name: String,
+    pub value: String,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl From<UserField> for AccountField {
+    fn from(field: UserField) -> Self {
+        Self {
+            name: field.name,
+            value: field.value,
+            verified_at: field.verified_at,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Debug, PartialEq)]
 pub struct AccountSource {
     note: String,
-    fields: Vec<()>,
+    fields: Vec<AccountField>,
     privacy: DbVisibility,
     sensitive: bool,
     language: String,
@@ -29,6 +54,8 @@ pub struct Account {
     pub following_count: u32,
     pub statuses_count: u32,
     pub last_status_at: Option<NaiveDate>,
+    pub fields: Vec<AccountField>,
+    pub emojis: Vec<CustomEmoji>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<AccountSource>,
@@ -38,11 +65,36 @@ pub struct Account {
     pub header_static: String,
     pub avatar_static: String,
 
+    /// Account the user has moved to, only set by [`Account::build`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved: Option<Box<Account>>,
+
     pub is_cat: bool,
 }
 
 impl Account {
+    /// Same as [`Account::new`], but also looks up the account the user has moved to
+    pub async fn build(
+        user: User,
+        with_source: bool,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let moved = match &user.moved_to_id {
+            Some(moved_to_id) => User::by_id(moved_to_id, db_pool)
+                .await?
+                .map(|moved| Box::new(Self::new(moved, false))),
+            None => None,
+        };
+
+        Ok(Self {
+            moved,
+            ..Self::new(user, with_source)
+        })
+    }
+
     pub fn new(user: User, with_source: bool) -> Self {
+        let fields = user.fields();
+        let emojis = user.emojis().into_iter().map(CustomEmoji::from).collect();
         Self {
             id: user.id.to_string(),
             url: user.ap_id.clone(), // TODO: Discuss
@@ -64,12 +116,23 @@ impl Account {
             last_status_at: user
                 .last_post_published
                 .map(|date_time| date_time.date_naive()),
+            fields: fields
+                .iter()
+                .cloned()
+                .map(|mut field| {
+                    if user.local {
+                        field.value = profile_fields::value_to_html(&field.value);
+                    }
+                    AccountField::from(field)
+                })
+                .collect(),
+            emojis,
 
             source: if with_source {
                 Some(AccountSource {
                     sensitive: false,
                     note: user.bio.unwrap_or_default(),
-                    fields: vec![],
+                    fields: fields.into_iter().map(AccountField::from).collect(),
                     privacy: DbVisibility::Public,
                     language: "en".to_string(),
                     follow_requests_count: user.follow_requests_count.try_into().unwrap(),
@@ -83,6 +146,7 @@ impl Account {
             avatar: "https://http.cat/images/404.jpg".to_string(),
             avatar_static: "https://http.cat/images/404.jpg".to_string(),
 
+            moved: None,
             is_cat: user.is_cat,
         }
     }
@@ -11,6 +11,7 @@ pub struct Application {
     #[serialize_always]
     pub website: Option<String>,
     pub redirect_uri: String,
+    pub scopes: Vec<String>,
     pub client_id: Option<String>,
     pub client_secret: Option<String>,
 }
@@ -23,6 +24,11 @@ impl Application {
                 name: application.name,
                 website: application.website,
                 redirect_uri: application.redirect_url,
+                scopes: application
+                    .scopes
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect(),
                 client_id: Some(application.client_id),
                 client_secret: Some(application.client_secret),
             }
@@ -32,6 +38,11 @@ impl Application {
                 name: application.name,
                 website: application.website,
                 redirect_uri: application.redirect_url,
+                scopes: application
+                    .scopes
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect(),
                 client_id: None,
                 client_secret: None,
             }
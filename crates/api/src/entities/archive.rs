@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use db::models::Archive as DbArchive;
+use serde::Serialize;
+
+/// Cryap extension: account archive, `url` is set once it's ready to download and `failed_at`
+/// if it couldn't be generated
+#[derive(Serialize, Debug)]
+pub struct Archive {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub url: Option<String>,
+}
+
+impl Archive {
+    pub fn new(archive: DbArchive, domain: &str) -> Self {
+        Self {
+            url: archive.finished_at.map(|_| {
+                format!(
+                    "https://{}/api/v1/cryap/archives/{}/download",
+                    domain, archive.id
+                )
+            }),
+            id: archive.id.to_string(),
+            created_at: archive.published,
+            finished_at: archive.finished_at,
+            failed_at: archive.failed_at,
+        }
+    }
+}
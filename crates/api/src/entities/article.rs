@@ -0,0 +1,26 @@
+use db::models::Article as DbArticle;
+use serde::Serialize;
+
+use super::Status;
+
+/// Cryap extension: full text of an article, the status itself only contains a fallback
+#[derive(Clone, Serialize, Debug)]
+pub struct Article {
+    pub id: String,
+    pub title: String,
+    pub summary: Option<String>,
+    pub content: String,
+    pub status: Status,
+}
+
+impl Article {
+    pub fn new(article: DbArticle, status: Status) -> Self {
+        Self {
+            id: article.post_id.to_string(),
+            title: article.title,
+            summary: article.summary,
+            content: article.content,
+            status,
+        }
+    }
+}
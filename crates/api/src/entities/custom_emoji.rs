@@ -0,0 +1,38 @@
+use db::models::{CustomEmoji as DbCustomEmoji, UserEmoji};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+// https://docs.joinmastodon.org/entities/CustomEmoji/
+#[skip_serializing_none]
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct CustomEmoji {
+    pub shortcode: String,
+    pub url: String,
+    pub static_url: String,
+    pub visible_in_picker: bool,
+    pub category: Option<String>,
+}
+
+impl From<DbCustomEmoji> for CustomEmoji {
+    fn from(emoji: DbCustomEmoji) -> Self {
+        Self {
+            shortcode: emoji.shortcode,
+            static_url: emoji.image_url.clone(), // TODO: Media
+            url: emoji.image_url,
+            visible_in_picker: emoji.visible_in_picker,
+            category: emoji.category,
+        }
+    }
+}
+
+impl From<UserEmoji> for CustomEmoji {
+    fn from(emoji: UserEmoji) -> Self {
+        Self {
+            shortcode: emoji.shortcode,
+            static_url: emoji.url.clone(),
+            url: emoji.url,
+            visible_in_picker: true,
+            category: None,
+        }
+    }
+}
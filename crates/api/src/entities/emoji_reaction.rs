@@ -0,0 +1,33 @@
+use db::models::ReactionSummary;
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use super::Account;
+
+// https://docs.pleroma.social/backend/development/API/differences_in_mastoapi_responses/#statuses
+#[skip_serializing_none]
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct EmojiReaction {
+    pub name: String,
+    pub count: u32,
+    pub me: bool,
+    pub url: Option<String>,
+    pub static_url: Option<String>,
+    pub accounts: Option<Vec<Account>>,
+}
+
+impl From<ReactionSummary> for EmojiReaction {
+    fn from(reaction: ReactionSummary) -> Self {
+        Self {
+            name: reaction.name,
+            count: reaction
+                .count
+                .try_into()
+                .expect("Nice, my post has 4294967296 reactions!"),
+            me: reaction.me,
+            static_url: reaction.image_url.clone(),
+            url: reaction.image_url,
+            accounts: None,
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use db::{
+    models::Import as DbImport,
+    types::{DbImportMode, DbImportState, DbImportType},
+};
+use serde::Serialize;
+
+/// Cryap extension: progress of a CSV import
+#[derive(Serialize, Debug)]
+pub struct Import {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub import_type: DbImportType,
+    pub mode: DbImportMode,
+    pub state: DbImportState,
+    pub total_items: i32,
+    pub processed_items: i32,
+    pub failed_items: i32,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<DbImport> for Import {
+    fn from(import: DbImport) -> Self {
+        Self {
+            id: import.id.to_string(),
+            import_type: import.import_type,
+            mode: import.mode,
+            state: import.state,
+            total_items: import.total_items,
+            processed_items: import.processed_items,
+            failed_items: import.failed_items,
+            created_at: import.published,
+            finished_at: import.finished_at,
+        }
+    }
+}
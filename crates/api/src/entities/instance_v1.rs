@@ -1,5 +1,5 @@
 use serde::Serialize;
-use web::config::Config;
+use web::config::{Config, RegistrationMode};
 
 use crate::entities::Rule;
 
@@ -51,9 +51,9 @@ impl Instance {
             cryap_version: String::from(env!("CARGO_PKG_VERSION")),
             thumbnail: String::new(),
             languages: config.instance.languages.clone(),
-            registrations: false,
-            approval_required: true,
-            invites_enabled: false,
+            registrations: config.instance.registrations != RegistrationMode::Closed,
+            approval_required: config.instance.registrations == RegistrationMode::ApprovalRequired,
+            invites_enabled: config.instance.users_can_invite,
             urls: Urls {
                 streaming_api: format!("wss://{}", &config.web.domain),
             },
@@ -1,5 +1,5 @@
 use serde::Serialize;
-use web::config::Config;
+use web::config::{Config, RegistrationMode};
 
 use crate::entities::Rule;
 
@@ -15,10 +15,16 @@ pub struct ConfigurationStatuses {
     pub max_characters: i32,
 }
 
+#[derive(Serialize, Debug)]
+pub struct ConfigurationVapid {
+    pub public_key: String,
+}
+
 #[derive(Serialize, Debug)]
 pub struct Configuration {
     pub urls: ConfigurationUrls,
     pub statuses: ConfigurationStatuses,
+    pub vapid: ConfigurationVapid,
 }
 
 #[derive(Serialize, Debug)]
@@ -44,7 +50,7 @@ pub struct Instance {
 }
 
 impl Instance {
-    pub fn new(config: &Config) -> Self {
+    pub fn new(config: &Config, vapid_public_key: String) -> Self {
         Self {
             domain: config.web.domain.clone(),
             title: config.instance.title.clone(),
@@ -61,10 +67,14 @@ impl Instance {
                 statuses: ConfigurationStatuses {
                     max_characters: config.instance.max_characters,
                 },
+                vapid: ConfigurationVapid {
+                    public_key: vapid_public_key,
+                },
             },
             registrations: Registrations {
-                enabled: false,
-                approval_required: true,
+                enabled: config.instance.registrations != RegistrationMode::Closed,
+                approval_required: config.instance.registrations
+                    == RegistrationMode::ApprovalRequired,
                 message: None,
             },
             rules: config
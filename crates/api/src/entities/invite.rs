@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use db::models::Invite as DbInvite;
+use serde::Serialize;
+
+/// Cryap extension: invite, `url` leads to the sign-up page with the code filled in
+#[derive(Serialize, Debug)]
+pub struct Invite {
+    pub id: String,
+    pub code: String,
+    pub url: String,
+    pub uses: i32,
+    pub max_uses: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub autofollow: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Invite {
+    pub fn new(invite: DbInvite, domain: &str) -> Self {
+        Self {
+            url: format!(
+                "https://{}/auth/sign_up?invite_code={}",
+                domain, invite.code
+            ),
+            id: invite.id.to_string(),
+            code: invite.code,
+            uses: invite.uses,
+            max_uses: invite.max_uses,
+            expires_at: invite.expires_at,
+            autofollow: invite.autofollow,
+            created_at: invite.published,
+        }
+    }
+}
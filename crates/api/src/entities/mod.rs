@@ -1,17 +1,37 @@
 pub mod account;
 pub mod application;
+pub mod archive;
+pub mod article;
+pub mod custom_emoji;
+pub mod emoji_reaction;
+pub mod import;
 pub mod instance_v1;
 pub mod instance_v2;
+pub mod invite;
 pub mod notification;
 pub mod relationship;
 pub mod rule;
+pub mod scheduled_status;
+pub mod session;
 pub mod status;
+pub mod tag;
 pub mod token;
+pub mod web_push_subscription;
 
 pub use account::Account;
 pub use application::Application;
+pub use archive::Archive;
+pub use article::Article;
+pub use custom_emoji::CustomEmoji;
+pub use emoji_reaction::EmojiReaction;
+pub use import::Import;
+pub use invite::Invite;
 pub use notification::Notification;
 pub use relationship::Relationship;
 pub use rule::Rule;
+pub use scheduled_status::ScheduledStatus;
+pub use session::Session;
 pub use status::Status;
+pub use tag::Tag;
 pub use token::Token;
+pub use web_push_subscription::WebPushSubscription;
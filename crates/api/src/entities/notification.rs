@@ -20,6 +20,9 @@ pub struct Notification {
     #[serde(rename = "type")]
     pub notification_type: DbNotificationType,
     pub created_at: DateTime<Utc>,
+    /// Pleroma extension for `pleroma:emoji_reaction` notifications
+    pub emoji: Option<String>,
+    pub emoji_url: Option<String>,
 }
 
 impl Notification {
@@ -94,12 +97,26 @@ impl Notification {
     }
 
     fn raw_build(notification: DbNotification, account: Account, status: Option<Status>) -> Self {
+        let emoji_url = match (&notification.reaction, &status) {
+            (Some(reaction), Some(status)) => status
+                .reactions
+                .iter()
+                .find(|status_reaction| status_reaction.name == *reaction)
+                .and_then(|status_reaction| status_reaction.url.clone()),
+            _ => None,
+        };
+
         Self {
             id: notification.id.to_string(),
             account,
             status,
             notification_type: notification.notification_type,
             created_at: notification.published,
+            emoji: notification.reaction.map(|reaction| match emoji_url {
+                Some(_) => format!(":{}:", reaction),
+                None => reaction,
+            }),
+            emoji_url,
         }
     }
 }
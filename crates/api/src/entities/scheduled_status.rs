@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use db::{models::ScheduledStatus as DbScheduledStatus, types::DbVisibility};
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct ScheduledStatusParams {
+    pub text: String,
+    pub poll: Option<()>,
+    pub media_ids: Option<Vec<String>>,
+    pub sensitive: bool,
+    pub spoiler_text: Option<String>,
+    pub visibility: DbVisibility,
+    pub in_reply_to_id: Option<String>,
+    pub quote_id: Option<String>,
+    pub language: Option<String>,
+    pub application_id: Option<String>,
+    pub scheduled_at: Option<DateTime<Utc>>,
+    pub idempotency: Option<String>,
+    pub with_rate_limit: bool,
+}
+
+// https://docs.joinmastodon.org/entities/ScheduledStatus/
+#[derive(Serialize, Debug)]
+pub struct ScheduledStatus {
+    pub id: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub params: ScheduledStatusParams,
+    pub media_attachments: Vec<()>,
+}
+
+impl ScheduledStatus {
+    pub fn new(scheduled_status: DbScheduledStatus) -> anyhow::Result<Self> {
+        let params = scheduled_status.params()?;
+        Ok(Self {
+            id: scheduled_status.id.to_string(),
+            scheduled_at: scheduled_status.scheduled_at,
+            params: ScheduledStatusParams {
+                text: params.text,
+                poll: None,
+                media_ids: None,
+                sensitive: params.sensitive,
+                spoiler_text: params.spoiler_text,
+                visibility: params.visibility,
+                in_reply_to_id: params.in_reply_to_id,
+                quote_id: params.quote_id,
+                language: None,
+                application_id: None,
+                scheduled_at: None,
+                idempotency: None,
+                with_rate_limit: false,
+            },
+            media_attachments: vec![],
+        })
+    }
+
+    pub fn new_from_vec(scheduled_statuses: Vec<DbScheduledStatus>) -> anyhow::Result<Vec<Self>> {
+        scheduled_statuses.into_iter().map(Self::new).collect()
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use db::models::{Application as DbApplication, Session as DbSession};
+use serde::Serialize;
+
+use crate::entities::Application;
+
+/// Cryap extension: place where the user is signed in, `current` is the session the request was
+/// made with. Sessions of the sign-in page have no `application`
+#[derive(Serialize, Debug)]
+pub struct Session {
+    pub id: String,
+    pub application: Option<Application>,
+    pub scopes: Vec<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub current: bool,
+}
+
+impl Session {
+    pub fn new(session: DbSession, application: Option<DbApplication>, current: bool) -> Self {
+        Self {
+            id: session.id.to_string(),
+            application: application.map(|application| Application::new(application, false)),
+            scopes: session
+                .scopes
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            ip: session.ip,
+            user_agent: session.user_agent,
+            created_at: session.published,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+            current,
+        }
+    }
+}
@@ -2,14 +2,18 @@ use std::sync::Arc;
 
 use db::{
     common::timelines::TimelineEntry,
-    models::{post::PostRelationship, Post, PostBoost, User},
+    models::{
+        post::PostRelationship, CustomEmoji as DbCustomEmoji, Post, PostBoost, PostReaction,
+        ReactionSummary, User,
+    },
     types::{DbId, DbVisibility},
 };
 use futures::future::join_all;
 use serde::Serialize;
 use web::AppState;
 
-use super::Account;
+use super::{Account, CustomEmoji, EmojiReaction};
+use crate::common::posts::accessible_for;
 
 #[derive(Clone, Serialize, Debug)]
 pub struct StatusMention {
@@ -47,6 +51,19 @@ impl From<&PostRelationship> for StatusRelationship {
     }
 }
 
+/// Everything needed to build a status besides the post itself
+struct StatusParts {
+    account: Account,
+    reblogs_count: i64,
+    favourites_count: i64,
+    mentions: Vec<User>,
+    emojis: Vec<DbCustomEmoji>,
+    reactions: Vec<ReactionSummary>,
+    in_reply: Option<Post>,
+    quote: Option<Status>,
+    relationship: Option<StatusRelationship>,
+}
+
 // TODO: Fully implement https://docs.joinmastodon.org/entities/Status/
 #[derive(Clone, Serialize, Debug)]
 pub struct Status {
@@ -60,7 +77,8 @@ pub struct Status {
     pub spoiler_text: String,
     pub mentions: Vec<StatusMention>,
     // tags
-    // emojis
+    pub emojis: Vec<CustomEmoji>,
+    pub reactions: Vec<EmojiReaction>,
     pub reblogs_count: u32,
     pub favourites_count: u32,
     pub replies_count: u32,
@@ -68,11 +86,14 @@ pub struct Status {
     pub in_reply_to_id: Option<String>,
     pub in_reply_to_account_id: Option<String>,
     pub reblog: Option<Box<Status>>,
+    pub quote: Option<Box<Status>>,
     pub pool: Option<()>,
     pub card: Option<()>,
     pub language: Option<String>,
     pub text: String,
     pub edited_at: Option<String>,
+    /// Glitch and Hometown extension
+    pub local_only: bool,
 
     #[serde(flatten)]
     pub relationship: Option<StatusRelationship>,
@@ -91,7 +112,13 @@ impl Status {
         };
 
         let mentions = post.mentioned_users(&state.db_pool).await?;
+        let emojis = DbCustomEmoji::by_post(&post.id, &state.db_pool).await?;
+        let reactions = PostReaction::summaries(vec![&post.id], user_id, &state.db_pool).await?;
         let account = Account::new(post.author(&state.db_pool).await?, false);
+        let quote = Self::quotes(vec![&post], user_id, state)
+            .await?
+            .pop()
+            .flatten();
         let relationship = if let Some(user_id) = user_id {
             Some(post.relationship(&user_id, &state.db_pool).await?.into())
         } else {
@@ -100,12 +127,17 @@ impl Status {
 
         Ok(Self::raw_build(
             post,
-            account,
-            stats.boosts_count,
-            stats.likes_count,
-            mentions,
-            in_reply,
-            relationship,
+            StatusParts {
+                account,
+                reblogs_count: stats.boosts_count,
+                favourites_count: stats.likes_count,
+                mentions,
+                emojis,
+                reactions,
+                in_reply,
+                quote,
+                relationship,
+            },
         ))
     }
 
@@ -134,6 +166,64 @@ impl Status {
         posts: Vec<Post>,
         user_id: Option<&DbId>,
         state: &Arc<AppState>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let quotes = Self::quotes(posts.iter().collect(), user_id, state).await?;
+        Self::build_from_vec_with_quotes(posts, quotes, user_id, state).await
+    }
+
+    /// Quoted posts of the posts, in the same order. Quotes the user can't see are left out and
+    /// quotes are built without their own quotes, so they don't nest endlessly
+    async fn quotes(
+        posts: Vec<&Post>,
+        user_id: Option<&DbId>,
+        state: &Arc<AppState>,
+    ) -> anyhow::Result<Vec<Option<Self>>> {
+        let quote_ids: Vec<&DbId> = posts
+            .iter()
+            .filter_map(|post| post.quote.as_ref())
+            .collect();
+        if quote_ids.is_empty() {
+            return Ok(posts.iter().map(|_| None).collect());
+        }
+
+        let user = match user_id {
+            Some(user_id) => User::by_id(user_id, &state.db_pool).await?,
+            None => None,
+        };
+        let mut quoted: Vec<Post> = vec![];
+        for post in Post::by_ids(quote_ids, &state.db_pool)
+            .await?
+            .into_iter()
+            .flatten()
+        {
+            if !quoted.iter().any(|quote| quote.id == post.id)
+                && accessible_for(&post, user.as_ref(), &state.db_pool).await?
+            {
+                quoted.push(post);
+            }
+        }
+
+        let no_quotes = quoted.iter().map(|_| None).collect();
+        let quoted = Self::build_from_vec_with_quotes(quoted, no_quotes, user_id, state).await?;
+
+        Ok(posts
+            .iter()
+            .map(|post| {
+                post.quote.as_ref().and_then(|quote_id| {
+                    quoted
+                        .iter()
+                        .find(|quote| quote.id == quote_id.to_string())
+                        .cloned()
+                })
+            })
+            .collect())
+    }
+
+    async fn build_from_vec_with_quotes(
+        posts: Vec<Post>,
+        quotes: Vec<Option<Self>>,
+        user_id: Option<&DbId>,
+        state: &Arc<AppState>,
     ) -> anyhow::Result<Vec<Self>> {
         let accounts =
             User::by_ids(
@@ -177,6 +267,16 @@ impl Status {
         .into_iter()
         .collect::<anyhow::Result<Vec<_>>>()?;
 
+        let emojis =
+            DbCustomEmoji::by_posts(posts.iter().map(|post| &post.id).collect(), &state.db_pool)
+                .await?;
+        let reactions = PostReaction::summaries(
+            posts.iter().map(|post| &post.id).collect(),
+            user_id,
+            &state.db_pool,
+        )
+        .await?;
+
         let relationships = if let Some(user_id) = user_id {
             Some(
                 Post::relationships(
@@ -195,7 +295,8 @@ impl Status {
             .zip(accounts)
             .zip(in_replies)
             .zip(mentions)
-            .map(|(((post, account), in_reply), mentions)| {
+            .zip(quotes)
+            .map(|((((post, account), in_reply), mentions), quote)| {
                 let stats = stats
                     .iter()
                     .find(|stats| stats.post_id == post.id)
@@ -207,14 +308,29 @@ impl Status {
                         .expect("each post must be in the result of the request")
                         .into()
                 });
+                let emojis = emojis
+                    .iter()
+                    .filter(|(post_id, _)| *post_id == post.id)
+                    .map(|(_, emoji)| emoji.clone())
+                    .collect();
+                let reactions = reactions
+                    .iter()
+                    .filter(|reaction| reaction.post_id == post.id)
+                    .cloned()
+                    .collect();
                 Self::raw_build(
                     post,
-                    account,
-                    stats.boosts_count,
-                    stats.likes_count,
-                    mentions,
-                    in_reply,
-                    relationship,
+                    StatusParts {
+                        account,
+                        reblogs_count: stats.boosts_count,
+                        favourites_count: stats.likes_count,
+                        mentions,
+                        emojis,
+                        reactions,
+                        in_reply,
+                        quote,
+                        relationship,
+                    },
                 )
             })
             .collect())
@@ -311,6 +427,26 @@ impl Status {
         .into_iter()
         .collect::<anyhow::Result<Vec<_>>>()?;
 
+        let post_ids: Vec<&DbId> = entries
+            .iter()
+            .map(|entry| match entry {
+                TimelineEntry::Post(post) | TimelineEntry::Boost(_, post) => &post.id,
+            })
+            .collect();
+        let emojis = DbCustomEmoji::by_posts(post_ids.clone(), &state.db_pool).await?;
+        let reactions = PostReaction::summaries(post_ids, user_id, &state.db_pool).await?;
+        let quotes = Self::quotes(
+            entries
+                .iter()
+                .map(|entry| match entry {
+                    TimelineEntry::Post(post) | TimelineEntry::Boost(_, post) => post,
+                })
+                .collect(),
+            user_id,
+            state,
+        )
+        .await?;
+
         let relationships = if let Some(user_id) = user_id {
             Some(
                 Post::relationships(
@@ -335,8 +471,9 @@ impl Status {
             .zip(boost_accounts)
             .zip(in_replies)
             .zip(mentions)
+            .zip(quotes)
             .map(
-                |((((entry, post_account), boost_account), in_reply), mentions)| {
+                |(((((entry, post_account), boost_account), in_reply), mentions), quote)| {
                     let stats = stats
                         .iter()
                         .find(|stats| match &entry {
@@ -356,27 +493,40 @@ impl Status {
                             .expect("each post must be in the result of the request")
                             .into()
                     });
+                    let emojis = emojis
+                        .iter()
+                        .filter(|(post_id, _)| match &entry {
+                            TimelineEntry::Post(post) | TimelineEntry::Boost(_, post) => {
+                                *post_id == post.id
+                            },
+                        })
+                        .map(|(_, emoji)| emoji.clone())
+                        .collect::<Vec<DbCustomEmoji>>();
+                    let reactions = reactions
+                        .iter()
+                        .filter(|reaction| match &entry {
+                            TimelineEntry::Post(post) | TimelineEntry::Boost(_, post) => {
+                                reaction.post_id == post.id
+                            },
+                        })
+                        .cloned()
+                        .collect::<Vec<ReactionSummary>>();
+                    let parts = StatusParts {
+                        account: post_account,
+                        reblogs_count: stats.boosts_count,
+                        favourites_count: stats.likes_count,
+                        mentions,
+                        emojis,
+                        reactions,
+                        in_reply,
+                        quote,
+                        relationship,
+                    };
                     match entry {
-                        TimelineEntry::Post(post) => Self::raw_build(
-                            post,
-                            post_account,
-                            stats.boosts_count,
-                            stats.likes_count,
-                            mentions,
-                            in_reply,
-                            relationship,
-                        ),
+                        TimelineEntry::Post(post) => Self::raw_build(post, parts),
                         TimelineEntry::Boost(boost, post) => Self::raw_boost_build(
                             boost,
-                            Self::raw_build(
-                                post,
-                                post_account,
-                                stats.boosts_count,
-                                stats.likes_count,
-                                mentions,
-                                in_reply,
-                                relationship,
-                            ),
+                            Self::raw_build(post, parts),
                             boost_account.expect("must be here"),
                         ),
                     }
@@ -398,15 +548,19 @@ impl Status {
         }
     }
 
-    fn raw_build(
-        post: Post,
-        author: Account,
-        reblogs_count: i64,
-        favourites_count: i64,
-        mentions: Vec<User>,
-        in_reply: Option<Post>,
-        relationship: Option<StatusRelationship>,
-    ) -> Self {
+    fn raw_build(post: Post, parts: StatusParts) -> Self {
+        let StatusParts {
+            account: author,
+            reblogs_count,
+            favourites_count,
+            mentions,
+            emojis,
+            reactions,
+            in_reply,
+            quote,
+            relationship,
+        } = parts;
+
         Self {
             id: post.id.to_string(),
             uri: post.ap_id.to_string(),
@@ -414,6 +568,7 @@ impl Status {
             account: author,
             content: post.content.clone(),
             visibility: post.visibility,
+            local_only: post.local_only,
             sensitive: post.sensitive,
             spoiler_text: post.content_warning.unwrap_or("".to_string()),
             mentions: mentions
@@ -429,6 +584,8 @@ impl Status {
                     username: user.name,
                 })
                 .collect(),
+            emojis: emojis.into_iter().map(CustomEmoji::from).collect(),
+            reactions: reactions.into_iter().map(EmojiReaction::from).collect(),
             reblogs_count: reblogs_count
                 .try_into()
                 .expect("Nice, my post has 4294967296 boosts!"),
@@ -440,6 +597,7 @@ impl Status {
             in_reply_to_id: post.in_reply.map(|id| id.to_string()),
             in_reply_to_account_id: in_reply.map(|post| post.author.to_string()),
             reblog: None,
+            quote: quote.map(Box::new),
             pool: None,
             card: None,
             language: None,
@@ -0,0 +1,24 @@
+use db::models::Tag as DbTag;
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+// TODO: Fully implement https://docs.joinmastodon.org/entities/Tag/
+#[skip_serializing_none]
+#[derive(Clone, Serialize, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub url: String,
+    pub history: Vec<()>,
+    pub following: Option<bool>,
+}
+
+impl Tag {
+    pub fn new(tag: DbTag, following: Option<bool>, domain: &str) -> Self {
+        Self {
+            url: format!("https://{}/tags/{}", domain, tag.name),
+            name: tag.name,
+            history: vec![],
+            following,
+        }
+    }
+}
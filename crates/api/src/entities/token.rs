@@ -1,12 +1,23 @@
-use db::models::Session;
+use chrono::{DateTime, Utc};
+use db::models::{AppToken, Session};
 use serde::Serialize;
+use serde_with::skip_serializing_none;
 
 // TODO: Fully implement https://docs.joinmastodon.org/entities/Token/
+#[skip_serializing_none]
 #[derive(Serialize, Debug)]
 pub struct Token {
     pub access_token: String,
     pub token_type: String,
+    pub scope: String,
     pub created_at: i64,
+    /// Seconds until the token expires, see RFC 6749
+    pub expires_in: Option<i64>,
+    pub refresh_token: Option<String>,
+}
+
+fn expires_in(expires_at: Option<DateTime<Utc>>) -> Option<i64> {
+    expires_at.map(|expires_at| (expires_at - Utc::now()).num_seconds().max(0))
 }
 
 impl Token {
@@ -14,7 +25,21 @@ impl Token {
         Self {
             access_token: session.token,
             token_type: String::from("Bearer"),
+            scope: session.scopes,
             created_at: session.published.timestamp(),
+            expires_in: expires_in(session.expires_at),
+            refresh_token: session.refresh_token,
+        }
+    }
+
+    pub fn from_app_token(app_token: AppToken) -> Self {
+        Self {
+            access_token: app_token.token,
+            token_type: String::from("Bearer"),
+            scope: app_token.scopes,
+            created_at: app_token.published.timestamp(),
+            expires_in: expires_in(app_token.expires_at),
+            refresh_token: None,
         }
     }
 }
@@ -0,0 +1,31 @@
+use std::collections::BTreeMap;
+
+use db::models::PushSubscription;
+use serde::Serialize;
+
+use crate::common::push::ALERTS;
+
+// https://docs.joinmastodon.org/entities/WebPushSubscription/
+#[derive(Serialize, Debug)]
+pub struct WebPushSubscription {
+    pub id: String,
+    pub endpoint: String,
+    pub alerts: BTreeMap<String, bool>,
+    pub policy: String,
+    pub server_key: String,
+}
+
+impl WebPushSubscription {
+    pub fn new(subscription: PushSubscription, server_key: String) -> Self {
+        Self {
+            id: subscription.id.to_string(),
+            alerts: ALERTS
+                .iter()
+                .map(|alert| (alert.to_string(), subscription.has_alert(alert)))
+                .collect(),
+            endpoint: subscription.endpoint,
+            policy: subscription.policy,
+            server_key,
+        }
+    }
+}
@@ -5,6 +5,8 @@ pub mod common;
 pub mod entities;
 pub mod error;
 pub mod routers;
+pub mod scopes;
+pub mod workers;
 
 use axum::{
     response::{IntoResponse, Response},
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use axum::{
+    handler::Handler, http::StatusCode, middleware::from_fn_with_state, response::IntoResponse,
+    routing::post, Extension, Form, Json, Router,
+};
+use db::models::Session;
+use serde::Deserialize;
+use serde_json::json;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    common::users,
+    error::ApiError,
+};
+
+#[derive(Deserialize)]
+pub struct DeleteAccountBody {
+    password: String,
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#post-apipleromadelete_account
+pub async fn http_post_delete_account(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Form(body): Form<DeleteAccountBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    if !users::verify_password(&user, &body.password) {
+        return Ok(ApiError::new("Invalid password", StatusCode::FORBIDDEN).into_response());
+    }
+
+    users::delete(&user, &state).await?;
+
+    Ok(Json(json!({ "status": "success" })).into_response())
+}
+
+pub fn account_deletion(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/pleroma/delete_account",
+        post(http_post_delete_account.layer(from_fn_with_state(
+            scope(state, "write:accounts"),
+            auth_middleware,
+        ))),
+    )
+}
@@ -12,7 +12,10 @@ use axum::{
 use db::{models::Session, pagination::PaginationQuery};
 use web::{errors::AppError, AppState};
 
-use crate::{entities::Status, routers::accounts::auth_middleware};
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    entities::Status,
+};
 
 // https://docs.joinmastodon.org/methods/bookmarks/#get
 pub async fn http_get_bookmarks(
@@ -48,6 +51,9 @@ pub async fn http_get_bookmarks(
 pub fn bookmarks(state: &Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new().route(
         "/api/v1/bookmarks",
-        get(http_get_bookmarks.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+        get(http_get_bookmarks.layer(from_fn_with_state(
+            scope(state, "read:bookmarks"),
+            auth_middleware,
+        ))),
     )
 }
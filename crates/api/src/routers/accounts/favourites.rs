@@ -12,7 +12,10 @@ use axum::{
 use db::{models::Session, pagination::PaginationQuery};
 use web::{errors::AppError, AppState};
 
-use crate::{entities::Status, routers::accounts::auth_middleware};
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    entities::Status,
+};
 
 // https://docs.joinmastodon.org/methods/favourites/#get
 pub async fn http_get_favourites(
@@ -47,6 +50,9 @@ pub async fn http_get_favourites(
 pub fn favourites(state: &Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new().route(
         "/api/v1/favourites",
-        get(http_get_favourites.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+        get(http_get_favourites.layer(from_fn_with_state(
+            scope(state, "read:favourites"),
+            auth_middleware,
+        ))),
     )
 }
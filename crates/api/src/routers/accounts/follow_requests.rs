@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use activitypub_federation::config::Data;
+use ap::common::follows;
 use axum::{
     extract::{Path, Query, State},
     handler::Handler,
@@ -18,10 +19,9 @@ use db::{
 use web::{errors::AppError, AppState};
 
 use crate::{
-    common::follows,
+    auth_middleware::{auth_middleware, scope},
     entities::{Account, Relationship},
     error::ApiError,
-    routers::accounts::auth_middleware,
 };
 
 // https://docs.joinmastodon.org/methods/follow_requests/#get
@@ -127,15 +127,23 @@ pub fn follow_requests(state: &Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route(
             "/api/v1/follow_requests",
-            get(http_get_follow_requests
-                .layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            get(http_get_follow_requests.layer(from_fn_with_state(
+                scope(state, "read:follows"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/follow_requests/:id/authorize",
-            post(http_post_authorize.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_authorize.layer(from_fn_with_state(
+                scope(state, "write:follows"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/follow_requests/:id/reject",
-            post(http_post_reject.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_reject.layer(from_fn_with_state(
+                scope(state, "write:follows"),
+                auth_middleware,
+            ))),
         )
 }
@@ -2,9 +2,10 @@ mod bookmarks;
 mod favourites;
 mod follow_requests;
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use activitypub_federation::config::Data;
+use ap::common::{emojis, follows, profile_fields};
 use axum::{
     extract::{Extension, Path, Query, State},
     handler::Handler,
@@ -16,7 +17,7 @@ use axum::{
 };
 use axum_extra::extract::Query as QueryExtra;
 use db::{
-    models::{user::UserUpdate, PrivateNote, Session, User},
+    models::{user::UserUpdate, Application, PrivateNote, Session, User, UserField},
     pagination::PaginationQuery,
     types::DbId,
 };
@@ -25,18 +26,113 @@ use serde::Deserialize;
 use web::{errors::AppError, AppState};
 
 use crate::{
-    auth_middleware::{auth_middleware, optional_auth_middleware},
-    common::{follows, users},
-    entities::{Account, Relationship, Status},
+    auth_middleware::{app_auth_middleware, auth_middleware, optional_auth_middleware, scope},
+    common::{
+        tokens,
+        users::{self, SignUpAccess},
+    },
+    entities::{Account, Relationship, Status, Token},
     error::ApiError,
 };
 
+#[derive(Deserialize)]
+pub struct RegisterBody {
+    username: String,
+    password: String,
+    #[serde(default)]
+    agreement: bool,
+    reason: Option<String>,
+    invite_code: Option<String>,
+}
+
+// https://docs.joinmastodon.org/methods/accounts/#create
+pub async fn http_post_register(
+    state: Data<Arc<AppState>>,
+    Extension(application): Extension<Application>,
+    Json(body): Json<RegisterBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let (approved, invite) = match users::sign_up_access(body.invite_code.as_deref(), &state)
+        .await?
+    {
+        SignUpAccess::Allowed { approved, invite } => (approved, invite),
+        SignUpAccess::Closed => {
+            return Ok(
+                ApiError::new("Registrations are closed", StatusCode::FORBIDDEN).into_response(),
+            );
+        },
+        SignUpAccess::InvalidInvite => {
+            return Ok(ApiError::new(
+                "Validation failed: Invite is invalid or has expired",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        },
+    };
+
+    let reason = body
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+    if let Some(error) = users::validate_registration(
+        &body.username,
+        &body.password,
+        body.agreement,
+        reason.as_deref(),
+        &state,
+    )
+    .await?
+    {
+        return Ok(ApiError::new_from_string(
+            format!("Validation failed: {error}"),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    if let Some(invite) = &invite {
+        if !invite.claim(&state.db_pool).await? {
+            return Ok(ApiError::new(
+                "Validation failed: Invite is invalid or has expired",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        }
+    }
+
+    let user = users::register(
+        body.username,
+        body.password,
+        None,
+        None,
+        approved,
+        reason,
+        &state,
+    )
+    .await?
+    .0;
+    if let Some(invite) = &invite {
+        users::accept_invite(&user, invite, &state).await?;
+    }
+
+    let session = Session::create(
+        user.id,
+        Some(application.id),
+        application.scopes.clone(),
+        tokens::expires_at(&state),
+        false,
+        &state.db_pool,
+    )
+    .await?;
+    Ok(Json(Token::new(session)).into_response())
+}
+
 // https://docs.joinmastodon.org/methods/accounts/#verify_credentials
 pub async fn http_get_verify_credentials(
     state: State<Arc<AppState>>,
     Extension(session): Extension<Session>,
 ) -> Result<impl IntoResponse, AppError> {
-    Ok(Json(Account::new(session.user(&state.db_pool).await?, true)).into_response())
+    let user = session.user(&state.db_pool).await?;
+    Ok(Json(Account::build(user, true, &state.db_pool).await?).into_response())
 }
 
 #[derive(Deserialize)]
@@ -48,8 +144,41 @@ pub struct UpdateCredentialsBody {
     manually_approves_followers: Option<bool>,
     bot: Option<bool>,
     is_cat: Option<bool>,
+    fields_attributes: Option<FieldsAttributes>,
+}
+
+#[derive(Deserialize)]
+pub struct FieldAttributes {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    value: String,
+}
+
+/// Clients send fields either as an array or as an object with indexes as keys
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum FieldsAttributes {
+    List(Vec<FieldAttributes>),
+    Map(HashMap<String, FieldAttributes>),
+}
+
+impl FieldsAttributes {
+    fn into_vec(self) -> Vec<FieldAttributes> {
+        match self {
+            FieldsAttributes::List(fields) => fields,
+            FieldsAttributes::Map(fields) => {
+                let mut fields: Vec<(String, FieldAttributes)> = fields.into_iter().collect();
+                fields.sort_by_key(|(index, _)| index.parse::<usize>().unwrap_or(usize::MAX));
+                fields.into_iter().map(|(_, field)| field).collect()
+            },
+        }
+    }
 }
 
+/// Same as in Mastodon
+const PROFILE_FIELD_MAX_CHARACTERS: usize = 255;
+
 // TODO: Fully implement https://docs.joinmastodon.org/methods/accounts/#update_credentials
 pub async fn http_patch_update_credentials(
     state: Data<Arc<AppState>>,
@@ -57,6 +186,7 @@ pub async fn http_patch_update_credentials(
     Json(body): Json<UpdateCredentialsBody>,
 ) -> Result<impl IntoResponse, AppError> {
     let mut user = session.user(&state.db_pool).await?;
+    let previous_fields = user.fields();
     let mut updated_user = UserUpdate::new();
     let mut there_are_changes = false;
 
@@ -124,12 +254,69 @@ pub async fn http_patch_update_credentials(
         updated_user.is_cat = Some(is_cat);
     }
 
+    if let Some(fields_attributes) = body.fields_attributes {
+        let fields: Vec<UserField> = fields_attributes
+            .into_vec()
+            .into_iter()
+            .filter(|field| !field.name.trim().is_empty() || !field.value.trim().is_empty())
+            .map(|field| UserField {
+                name: field.name.trim().to_string(),
+                value: field.value.trim().to_string(),
+                verified_at: None,
+            })
+            .collect();
+
+        let max_fields = state.config.instance.max_profile_fields;
+        if fields.len() > max_fields.try_into().unwrap() {
+            return Ok(ApiError::new_from_string(
+                format!(
+                    "Validation failed: Fields is too long (maximum is {} fields)",
+                    max_fields
+                ),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        }
+
+        if fields.iter().any(|field| {
+            field.name.chars().count() > PROFILE_FIELD_MAX_CHARACTERS
+                || field.value.chars().count() > PROFILE_FIELD_MAX_CHARACTERS
+        }) {
+            return Ok(ApiError::new_from_string(
+                format!(
+                    "Validation failed: Fields name and value are too long (maximum is {} characters)",
+                    PROFILE_FIELD_MAX_CHARACTERS
+                ),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        }
+
+        there_are_changes = true;
+        let fields = serde_json::to_value(UserField::merge(&user.fields(), fields))?;
+        user.fields = fields.clone();
+        updated_user.fields = Some(fields);
+    }
+
     if there_are_changes {
+        let emojis = emojis::local_for_user(
+            &[
+                user.display_name.as_deref().unwrap_or_default(),
+                user.bio.as_deref().unwrap_or_default(),
+            ],
+            &state.config.web.domain,
+            &state.db_pool,
+        )
+        .await?;
+        user.emojis = emojis.clone();
+        updated_user.emojis = Some(emojis);
+
         user.update(updated_user, &state.db_pool).await?;
         users::distribute_update(&user, &state).await?;
+        profile_fields::spawn_verification(user.clone(), previous_fields, &state.db_pool);
     }
 
-    Ok(Json(Account::new(user, true)).into_response())
+    Ok(Json(Account::build(user, true, &state.db_pool).await?).into_response())
 }
 
 #[derive(Deserialize)]
@@ -150,8 +337,10 @@ pub async fn http_get_lookup(
     };
 
     match user {
-        Some(user) => Ok(Json(Account::new(user, false)).into_response()),
-        None => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+        Some(user) if user.deleted_at.is_none() => {
+            Ok(Json(Account::build(user, false, &state.db_pool).await?).into_response())
+        },
+        _ => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
     }
 }
 
@@ -163,8 +352,10 @@ pub async fn http_get_get(
     let id = DbId::from(id);
     let user = User::by_id(&id, &state.db_pool).await?;
     match user {
-        Some(user) => Ok(Json(Account::new(user, false)).into_response()),
-        None => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+        Some(user) if user.deleted_at.is_none() => {
+            Ok(Json(Account::build(user, false, &state.db_pool).await?).into_response())
+        },
+        _ => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
     }
 }
 
@@ -499,24 +690,33 @@ pub fn accounts(state: &Arc<AppState>) -> Router<Arc<AppState>> {
         .merge(bookmarks::bookmarks(state))
         .merge(favourites::favourites(state))
         .merge(follow_requests::follow_requests(state))
+        .route(
+            "/api/v1/accounts",
+            post(http_post_register.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                app_auth_middleware,
+            ))),
+        )
         .route(
             "/api/v1/accounts/verify_credentials",
-            get(http_get_verify_credentials
-                .layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            get(http_get_verify_credentials.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/accounts/update_credentials",
-            patch(
-                http_patch_update_credentials
-                    .layer(from_fn_with_state(Arc::clone(state), auth_middleware)),
-            ),
+            patch(http_patch_update_credentials.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
         )
         .route("/api/v1/accounts/lookup", get(http_get_lookup))
         .route("/api/v1/accounts/:id", get(http_get_get))
         .route(
             "/api/v1/accounts/:id/statuses",
             get(http_get_statuses.layer(from_fn_with_state(
-                Arc::clone(state),
+                scope(state, "read:statuses"),
                 optional_auth_middleware,
             ))),
         )
@@ -524,26 +724,37 @@ pub fn accounts(state: &Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/api/v1/accounts/:id/following", get(http_get_following))
         .route(
             "/api/v1/accounts/:id/follow",
-            post(http_post_follow.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_follow.layer(from_fn_with_state(
+                scope(state, "write:follows"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/accounts/:id/unfollow",
-            post(http_post_unfollow.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_unfollow.layer(from_fn_with_state(
+                scope(state, "write:follows"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/accounts/:id/remove_from_followers",
-            post(
-                http_post_remove_from_followers
-                    .layer(from_fn_with_state(Arc::clone(state), auth_middleware)),
-            ),
+            post(http_post_remove_from_followers.layer(from_fn_with_state(
+                scope(state, "write:follows"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/accounts/:id/note",
-            post(http_post_note.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_note.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/accounts/relationships",
-            get(http_get_relationships
-                .layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            get(http_get_relationships.layer(from_fn_with_state(
+                scope(state, "read:follows"),
+                auth_middleware,
+            ))),
         )
 }
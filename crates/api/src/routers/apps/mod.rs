@@ -11,13 +11,16 @@ use axum::{
     routing::{get, post},
     Extension, Json, Router,
 };
-use db::models::{Application, Session};
+use db::models::Application;
 use serde::Deserialize;
 use url::Url;
 use web::{errors::AppError, AppState};
 
 use crate::{
-    auth_middleware::auth_middleware, entities::Application as ApiApplication, error::ApiError,
+    auth_middleware::{any_scope, app_auth_middleware},
+    entities::Application as ApiApplication,
+    error::ApiError,
+    scopes,
 };
 
 #[derive(Deserialize)]
@@ -27,6 +30,7 @@ pub struct CreateBody {
     #[serde(rename = "redirect_uris")]
     redirect_uri: String,
     website: Option<String>,
+    scopes: Option<String>,
 }
 
 // https://docs.joinmastodon.org/methods/apps/#create
@@ -42,20 +46,33 @@ pub async fn http_post_create(
         .into_response());
     }
 
-    let application =
-        Application::create(body.name, body.website, body.redirect_uri, &state.db_pool).await?;
+    let scopes = match scopes::parse(body.scopes.as_deref().unwrap_or(scopes::DEFAULT_SCOPES)) {
+        Some(scopes) if !scopes.is_empty() => scopes.join(" "),
+        _ => {
+            return Ok(ApiError::new(
+                "Validation failed: Scopes must be known OAuth scopes",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        },
+    };
+
+    let application = Application::create(
+        body.name,
+        body.website,
+        body.redirect_uri,
+        scopes,
+        &state.db_pool,
+    )
+    .await?;
     Ok(Json(ApiApplication::new(application, true)).into_response())
 }
 
-// TODO: Fully implement https://docs.joinmastodon.org/methods/apps/#verify_credentials
+// https://docs.joinmastodon.org/methods/apps/#verify_credentials
 pub async fn http_get_verify_credentials(
-    state: State<Arc<AppState>>,
-    Extension(session): Extension<Session>,
+    Extension(application): Extension<Application>,
 ) -> Result<impl IntoResponse, AppError> {
-    match session.application(&state.db_pool).await? {
-        Some(application) => Ok(Json(ApiApplication::new(application, false)).into_response()),
-        None => Ok(Json(()).into_response()), // FIXME: I don't know what Mastodon does in this case
-    }
+    Ok(Json(ApiApplication::new(application, false)).into_response())
 }
 
 pub fn apps(state: &Arc<AppState>) -> Router<Arc<AppState>> {
@@ -65,6 +82,6 @@ pub fn apps(state: &Arc<AppState>) -> Router<Arc<AppState>> {
         .route(
             "/api/v1/apps/verify_credentials",
             get(http_get_verify_credentials
-                .layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+                .layer(from_fn_with_state(any_scope(state), app_auth_middleware))),
         )
 }
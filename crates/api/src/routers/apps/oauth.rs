@@ -9,13 +9,68 @@ use axum::{
     Form, Json, Router,
 };
 use axum_extra::extract::cookie::CookieJar;
-use db::models::{Application, RedirectCode, Session};
+use db::models::{AppToken, Application, RedirectCode, Session};
 use serde::Deserialize;
+use serde_json::json;
 use tera::Context;
 use url::Url;
 use web::{errors::AppError, AppState};
 
-use crate::{entities::Token, error::ApiError, EmptyJsonObject, TEMPLATES};
+use crate::{
+    common::{pkce, sessions, tokens},
+    entities::Token,
+    error::ApiError,
+    scopes, EmptyJsonObject, TEMPLATES,
+};
+
+/// Normalized requested scopes, `None` if they're unknown or not registered for the application
+fn requested_scopes(requested: Option<&str>, application: &Application) -> Option<String> {
+    let requested = scopes::parse(requested.unwrap_or(scopes::DEFAULT_SCOPES))?.join(" ");
+    if requested.is_empty() || !scopes::allows_all(&application.scopes, &requested) {
+        None
+    } else {
+        Some(requested)
+    }
+}
+
+/// Method of the PKCE challenge, `Ok(None)` if the client doesn't use PKCE
+fn code_challenge_method(
+    challenge: Option<&str>,
+    method: Option<&str>,
+) -> Result<Option<&'static str>, ApiError> {
+    match challenge {
+        Some(challenge) => match pkce::challenge_method(challenge, method) {
+            Some(method) => Ok(Some(method)),
+            None => Err(invalid_request()),
+        },
+        None if method.is_some() => Err(invalid_request()),
+        None => Ok(None),
+    }
+}
+
+fn invalid_request() -> ApiError {
+    ApiError::new_with_description(
+        "invalid_request",
+        "The request is missing a required parameter, includes an unsupported parameter value, or is otherwise malformed.",
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+fn invalid_grant() -> ApiError {
+    ApiError::new_with_description("invalid_grant", "The provided authorization grant is invalid, expired, revoked, does not match the redirection URI used in the authorization request, or was issued to another client.", StatusCode::UNAUTHORIZED)
+}
+
+fn invalid_client() -> ApiError {
+    ApiError::new_with_description("invalid_client", "Client authentication failed due to unknown client, no client authentication included, or unsupported authentication method.", StatusCode::UNAUTHORIZED)
+}
+
+fn invalid_scope() -> ApiError {
+    ApiError::new_with_description(
+        "invalid_scope",
+        "The requested scope is invalid, unknown, or malformed.",
+        StatusCode::BAD_REQUEST,
+    )
+}
 
 #[derive(Deserialize)]
 pub struct AuthorizeQuery {
@@ -23,6 +78,9 @@ pub struct AuthorizeQuery {
     client_id: String,
     #[serde(rename = "redirect_uri")]
     redirect_url: String,
+    scope: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
 }
 
 // TODO: Fully implement https://docs.joinmastodon.org/methods/oauth/#authorize
@@ -63,6 +121,18 @@ pub async fn http_get_oauth_authorize(
             return Ok(StatusCode::BAD_REQUEST.into_response());
         },
     };
+    let scope = match requested_scopes(query.scope.as_deref(), &application) {
+        Some(scope) => scope,
+        None => return Ok(invalid_scope().into_response()),
+    };
+    let scope_descriptions: Vec<String> = scope.split(' ').map(scopes::describe).collect();
+    let code_challenge_method = match code_challenge_method(
+        query.code_challenge.as_deref(),
+        query.code_challenge_method.as_deref(),
+    ) {
+        Ok(method) => method,
+        Err(err) => return Ok(err.into_response()),
+    };
 
     let user = session.user(&state.db_pool).await?;
     let mut context = Context::new();
@@ -71,6 +141,12 @@ pub async fn http_get_oauth_authorize(
     context.insert("application_name", &application.name);
     context.insert("client_id", &query.client_id);
     context.insert("redirect_url", &query.redirect_url);
+    context.insert("scope", &scope);
+    context.insert("scope_descriptions", &scope_descriptions);
+    if let (Some(challenge), Some(method)) = (&query.code_challenge, code_challenge_method) {
+        context.insert("code_challenge", challenge);
+        context.insert("code_challenge_method", method);
+    }
     Ok(Html(TEMPLATES.render("authorize.html", &context)?).into_response())
 }
 
@@ -78,6 +154,9 @@ pub async fn http_get_oauth_authorize(
 pub struct AuthorizeBody {
     client_id: String,
     redirect_url: String,
+    scope: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
 }
 
 pub async fn http_post_oauth_authorize(
@@ -103,10 +182,24 @@ pub async fn http_post_oauth_authorize(
             return Ok(StatusCode::BAD_REQUEST.into_response());
         },
     };
+    let scope = match requested_scopes(body.scope.as_deref(), &application) {
+        Some(scope) => scope,
+        None => return Ok(invalid_scope().into_response()),
+    };
+    let code_challenge_method = match code_challenge_method(
+        body.code_challenge.as_deref(),
+        body.code_challenge_method.as_deref(),
+    ) {
+        Ok(method) => method.map(String::from),
+        Err(err) => return Ok(err.into_response()),
+    };
 
     let redirect_code = RedirectCode::create(
         application.client_id,
         session.user(&state.db_pool).await?.id,
+        scope,
+        body.code_challenge,
+        code_challenge_method,
         &mut state.redis.clone(),
     )
     .await?;
@@ -127,11 +220,16 @@ pub async fn http_post_oauth_authorize(
 
 #[derive(Deserialize)]
 pub struct TokenBody {
-    code: String,
+    grant_type: Option<String>,
+    code: Option<String>,
     client_id: String,
-    client_secret: String,
+    /// Left out by public clients, which have to use PKCE instead
+    client_secret: Option<String>,
     #[serde(rename = "redirect_uri")]
-    redirect_url: String,
+    redirect_url: Option<String>,
+    scope: Option<String>,
+    code_verifier: Option<String>,
+    refresh_token: Option<String>,
 }
 
 // TODO: Fully implement https://docs.joinmastodon.org/methods/oauth/#token
@@ -140,23 +238,121 @@ pub async fn http_post_oauth_token(
     Form(body): Form<TokenBody>,
 ) -> Result<impl IntoResponse, AppError> {
     let application = match Application::by_client_id(&body.client_id, &state.db_pool).await? {
-        Some(application) if application.client_secret == body.client_secret => application,
-        _ => {
-            return Ok(ApiError::new_with_description("invalid_client", "Client authentication failed due to unknown client, no client authentication included, or unsupported authentication method.", StatusCode::UNAUTHORIZED).into_response());
+        Some(application)
+            if body.client_secret.as_ref().map_or(true, |client_secret| {
+                &application.client_secret == client_secret
+            }) =>
+        {
+            application
         },
+        _ => return Ok(invalid_client().into_response()),
     };
 
-    let mut redis = state.redis.clone();
-    let redirect_code = match RedirectCode::by_code(&body.code, &mut redis).await? {
-        Some(redirect_code) if application.redirect_url == body.redirect_url => redirect_code,
+    match body.grant_type.as_deref().unwrap_or("authorization_code") {
+        "authorization_code" => {},
+        "client_credentials" if body.client_secret.is_none() => {
+            return Ok(invalid_client().into_response());
+        },
+        "client_credentials" => {
+            let scope = match requested_scopes(body.scope.as_deref(), &application) {
+                Some(scope) => scope,
+                None => return Ok(invalid_scope().into_response()),
+            };
+            let app_token = AppToken::create(
+                &application,
+                scope,
+                tokens::expires_at(&state),
+                &state.db_pool,
+            )
+            .await?;
+            return Ok(Json(Token::from_app_token(app_token)).into_response());
+        },
+        "refresh_token" => {
+            let session = match Session::by_refresh_token(
+                body.refresh_token.as_deref().unwrap_or(""),
+                &state.db_pool,
+            )
+            .await?
+            {
+                Some(session) if session.application_id.as_ref() == Some(&application.id) => {
+                    session
+                },
+                _ => return Ok(invalid_grant().into_response()),
+            };
+            // Only tokens of clients that used PKCE can be refreshed without the secret, like
+            // they exchanged the code
+            if !session.pkce && body.client_secret.is_none() {
+                return Ok(invalid_client().into_response());
+            }
+            // The refreshed token can be narrowed down, but not get more scopes
+            let scope = match &body.scope {
+                Some(scope) => match scopes::parse(scope).map(|scopes| scopes.join(" ")) {
+                    Some(scope)
+                        if !scope.is_empty() && scopes::allows_all(&session.scopes, &scope) =>
+                    {
+                        scope
+                    },
+                    _ => return Ok(invalid_scope().into_response()),
+                },
+                None => session.scopes.clone(),
+            };
+
+            let session = match session
+                .rotate(scope, tokens::expires_at(&state), &state.db_pool)
+                .await?
+            {
+                Some(session) => session,
+                None => return Ok(invalid_grant().into_response()),
+            };
+            // Connections opened with the old token are closed, like when it's revoked
+            sessions::disconnect(&session.user_id, vec![session.id.clone()]).await;
+            return Ok(Json(Token::new(session)).into_response());
+        },
         _ => {
-            return Ok(ApiError::new_with_description("invalid_grant", "The provided authorization grant is invalid, expired, revoked, does not match the redirection URI used in the authorization request, or was issued to another client.", StatusCode::UNAUTHORIZED).into_response());
+            return Ok(ApiError::new_with_description(
+                "unsupported_grant_type",
+                "The authorization grant type is not supported by the authorization server.",
+                StatusCode::BAD_REQUEST,
+            )
+            .into_response());
         },
-    };
+    }
+
+    let mut redis = state.redis.clone();
+    let redirect_code =
+        match RedirectCode::by_code(body.code.as_deref().unwrap_or(""), &mut redis).await? {
+            Some(redirect_code)
+                if redirect_code.client_id == application.client_id
+                    && body.redirect_url.as_ref() == Some(&application.redirect_url) =>
+            {
+                redirect_code
+            },
+            _ => return Ok(invalid_grant().into_response()),
+        };
+    match (
+        &redirect_code.code_challenge,
+        &redirect_code.code_challenge_method,
+    ) {
+        (Some(challenge), Some(method)) => {
+            if !body
+                .code_verifier
+                .as_ref()
+                .is_some_and(|verifier| pkce::verify(verifier, challenge, method))
+            {
+                return Ok(invalid_grant().into_response());
+            }
+        },
+        // Only clients that use PKCE can leave out the secret
+        _ if body.client_secret.is_none() => return Ok(invalid_client().into_response()),
+        _ => {},
+    }
 
     let session = Session::create(
         redirect_code.user(&state.db_pool).await?.id,
         Some(application.id),
+        redirect_code.scopes.clone(),
+        tokens::expires_at(&state),
+        redirect_code.code_challenge.is_some(),
         &state.db_pool,
     )
     .await?;
@@ -190,7 +386,7 @@ pub async fn http_post_oauth_revoke(
                     |session_application| session_application.id == application.id,
                 ) =>
         {
-            session.delete(&state.db_pool).await?;
+            sessions::revoke(&session, &state).await?;
             Ok(EmptyJsonObject::response())
         },
         _ => Ok(ApiError::new_with_description(
@@ -202,10 +398,37 @@ pub async fn http_post_oauth_revoke(
     }
 }
 
+// https://datatracker.ietf.org/doc/html/rfc8414
+pub async fn http_get_authorization_server_metadata(
+    state: State<Arc<AppState>>,
+) -> Result<impl IntoResponse, AppError> {
+    let domain = &state.config.web.domain;
+    Ok(Json(json!({
+        "issuer": format!("https://{}/", domain),
+        "authorization_endpoint": format!("https://{}/oauth/authorize", domain),
+        "token_endpoint": format!("https://{}/oauth/token", domain),
+        "revocation_endpoint": format!("https://{}/oauth/revoke", domain),
+        "app_registration_endpoint": format!("https://{}/api/v1/apps", domain),
+        "scopes_supported": scopes::TOP_LEVEL_SCOPES
+            .into_iter()
+            .chain(scopes::GRANULAR_SCOPES)
+            .collect::<Vec<&str>>(),
+        "response_types_supported": ["code"],
+        "response_modes_supported": ["query"],
+        "grant_types_supported": ["authorization_code", "client_credentials", "refresh_token"],
+        "token_endpoint_auth_methods_supported": ["client_secret_post", "none"],
+        "code_challenge_methods_supported": pkce::CHALLENGE_METHODS,
+    })))
+}
+
 pub fn oauth() -> Router<Arc<AppState>> {
     Router::new()
         .route("/oauth/authorize", get(http_get_oauth_authorize))
         .route("/oauth/authorize", post(http_post_oauth_authorize))
         .route("/oauth/token", post(http_post_oauth_token))
         .route("/oauth/revoke", post(http_post_oauth_revoke))
+        .route(
+            "/.well-known/oauth-authorization-server",
+            get(http_get_authorization_server_metadata),
+        )
 }
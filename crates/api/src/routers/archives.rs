@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    handler::Handler,
+    http::{header, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{Duration, Utc};
+use db::{
+    models::{Archive, Session},
+    types::DbId,
+};
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    entities::Archive as ApiArchive,
+    error::ApiError,
+};
+
+/// Archives are expensive to generate, so users can only request one per this many days
+const ARCHIVE_INTERVAL_DAYS: i64 = 7;
+
+// Cryap extension: requests an archive of the account, which is generated in the background
+pub async fn http_post_create(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let archives = Archive::get_for_user(&user.id, &state.db_pool).await?;
+    // Archives that failed don't count, the user can ask for a new one right away
+    if let Some(latest) = archives.iter().find(|archive| archive.failed_at.is_none()) {
+        if latest.published > Utc::now() - Duration::days(ARCHIVE_INTERVAL_DAYS) {
+            return Ok(ApiError::new_from_string(
+                format!(
+                    "You can request an archive once every {} days",
+                    ARCHIVE_INTERVAL_DAYS
+                ),
+                StatusCode::TOO_MANY_REQUESTS,
+            )
+            .into_response());
+        }
+    }
+
+    let archive = Archive::create(&user, &state.db_pool).await?;
+
+    Ok(Json(ApiArchive::new(archive, &state.config.web.domain)).into_response())
+}
+
+// Cryap extension: archives of the account, newest first
+pub async fn http_get_archives(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let archives = Archive::get_for_user(&session.user_id, &state.db_pool)
+        .await?
+        .into_iter()
+        .map(|archive| ApiArchive::new(archive, &state.config.web.domain))
+        .collect::<Vec<ApiArchive>>();
+
+    Ok(Json(archives).into_response())
+}
+
+// Cryap extension: ZIP file of a finished archive
+pub async fn http_get_download(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let archive = match Archive::by_id(&DbId::from(id), &state.db_pool).await? {
+        Some(archive) if archive.user_id == session.user_id => archive,
+        _ => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    let data = match archive.data(&state.db_pool).await? {
+        Some(data) => data,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"archive-{}-{}.zip\"",
+                    archive.published.format("%Y%m%d%H%M%S"),
+                    archive.id
+                ),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+pub fn archives(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/cryap/archives",
+            get(http_get_archives.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            )))
+            .post(http_post_create.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/cryap/archives/:id/download",
+            get(http_get_download.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            ))),
+        )
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use axum::{
+    extract::Path,
+    handler::Handler,
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use db::{
+    models::{Article, Post, Session},
+    types::{DbId, DbVisibility},
+};
+use serde::Deserialize;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, optional_auth_middleware, scope},
+    common::posts,
+    entities::{Article as ApiArticle, Status},
+    error::ApiError,
+};
+
+const MAX_TITLE_LENGTH: usize = 500;
+
+#[derive(Deserialize)]
+pub struct CreateArticleBody {
+    title: String,
+    summary: Option<String>,
+    content: String,
+    in_reply_to_id: Option<String>,
+    sensitive: Option<bool>,
+    visibility: Option<DbVisibility>,
+    local_only: Option<bool>,
+}
+
+// Cryap extension, same as https://docs.joinmastodon.org/methods/statuses/#create but for articles
+pub async fn http_post_create(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Json(body): Json<CreateArticleBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let title = body.title.trim().to_string();
+    if title.is_empty() || title.chars().count() > MAX_TITLE_LENGTH {
+        return Ok(ApiError::new_from_string(
+            format!(
+                "Validation failed: Title must be between 1 and {} characters",
+                MAX_TITLE_LENGTH
+            ),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+    if body.content.trim().is_empty() {
+        return Ok(ApiError::new(
+            "Validation failed: Content can't be blank",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    let options = posts::NewPost {
+        visibility: body.visibility.unwrap_or(DbVisibility::Public),
+        content: String::new(),
+        in_reply: match body.in_reply_to_id {
+            Some(id) => Post::by_id(&DbId::from(id), &state.db_pool).await?,
+            None => None,
+        },
+        quote: None,
+        local_only: body.local_only.unwrap_or(false),
+        sensitive: body.sensitive.unwrap_or(false),
+        content_warning: None,
+        article: Some(posts::NewArticle {
+            title,
+            summary: body.summary.filter(|summary| !summary.trim().is_empty()),
+            content: body.content,
+        }),
+    };
+    let post = posts::post(&user, options, &state).await?;
+    let article = Article::by_post(&post.id, &state.db_pool)
+        .await?
+        .expect("article is created together with the post");
+
+    Ok(Json(ApiArticle::new(
+        article,
+        Status::build(post, Some(&user.id), &state).await?,
+    ))
+    .into_response())
+}
+
+// Cryap extension: full text of an article
+pub async fn http_get_get(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Option<Session>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = match session {
+        Some(session) => Some(session.user(&state.db_pool).await?),
+        None => None,
+    };
+
+    let post = match Post::by_id(&DbId::from(id), &state.db_pool).await? {
+        Some(post) if posts::accessible_for(&post, user.as_ref(), &state.db_pool).await? => post,
+        _ => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    let article = match Article::by_post(&post.id, &state.db_pool).await? {
+        Some(article) => article,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+
+    Ok(Json(ApiArticle::new(
+        article,
+        Status::build(post, user.as_ref().map(|user| &user.id), &state).await?,
+    ))
+    .into_response())
+}
+
+pub fn articles(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/cryap/articles",
+            post(http_post_create.layer(from_fn_with_state(
+                scope(state, "write:statuses"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/cryap/articles/:id",
+            get(http_get_get.layer(from_fn_with_state(
+                scope(state, "read:statuses"),
+                optional_auth_middleware,
+            ))),
+        )
+}
@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use axum::{extract::State, response::IntoResponse, routing::get, Json, Router};
+use db::models::CustomEmoji;
+use web::{errors::AppError, AppState};
+
+use crate::entities::CustomEmoji as ApiCustomEmoji;
+
+// https://docs.joinmastodon.org/methods/custom_emojis/#get
+pub async fn http_get_get(state: State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
+    let emojis: Vec<ApiCustomEmoji> = CustomEmoji::local(&state.db_pool)
+        .await?
+        .into_iter()
+        .map(ApiCustomEmoji::from)
+        .collect();
+
+    Ok(Json(emojis).into_response())
+}
+
+pub fn custom_emojis() -> Router<Arc<AppState>> {
+    Router::new().route("/api/v1/custom_emojis", get(http_get_get))
+}
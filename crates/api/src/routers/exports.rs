@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    handler::Handler,
+    http::{header, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Extension, Router,
+};
+use db::models::{Session, User};
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    error::ApiError,
+};
+
+fn acct(user: &User) -> String {
+    format!("{}@{}", user.name, user.instance)
+}
+
+// Cryap extension: CSV exports in the same format as Mastodon's. Blocks, mutes, domain blocks
+// and lists aren't exported, as Cryap doesn't have them
+pub async fn http_get_export(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(file): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let mut writer = csv::Writer::from_writer(vec![]);
+    match file.as_str() {
+        "following_accounts.csv" => {
+            writer.write_record([
+                "Account address",
+                "Show boosts",
+                "Notify on new posts",
+                "Languages",
+            ])?;
+            for followed in user.all_following(&state.db_pool).await? {
+                writer.write_record([acct(&followed).as_str(), "true", "false", ""])?;
+            }
+        },
+        "followers.csv" => {
+            writer.write_record(["Account address"])?;
+            for follower in user.all_followers(&state.db_pool).await? {
+                writer.write_record([acct(&follower)])?;
+            }
+        },
+        "bookmarks.csv" => {
+            for post in user.all_bookmarked_posts(&state.db_pool).await? {
+                writer.write_record([post.ap_id])?;
+            }
+        },
+        _ => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file),
+            ),
+        ],
+        writer.into_inner().map_err(|err| err.into_error())?,
+    )
+        .into_response())
+}
+
+pub fn exports(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/v1/cryap/exports/:file",
+        get(http_get_export.layer(from_fn_with_state(
+            scope(state, "read:accounts"),
+            auth_middleware,
+        ))),
+    )
+}
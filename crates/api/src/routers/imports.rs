@@ -0,0 +1,218 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    handler::Handler,
+    http::{header, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use db::{
+    models::{Import, Session},
+    pagination::PaginationQuery,
+    types::{DbId, DbImportMode, DbImportType},
+};
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    entities::Import as ApiImport,
+    error::ApiError,
+};
+
+const MAX_ROWS: usize = 20_000;
+
+/// First column of every row, without duplicates and the header Mastodon adds to some exports
+fn parse_rows(data: &[u8]) -> anyhow::Result<Vec<String>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data);
+
+    let mut seen = HashSet::new();
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record?;
+        let value = match record.get(0) {
+            Some(value) => value.trim(),
+            None => continue,
+        };
+        if value.is_empty() || value == "Account address" || !seen.insert(value.to_string()) {
+            continue;
+        }
+        rows.push(value.to_string());
+    }
+
+    Ok(rows)
+}
+
+// Cryap extension: schedules a CSV import of the given `type` with `mode` and the file in `data`
+pub async fn http_post_create(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let mut import_type = None;
+    let mut mode = DbImportMode::Merge;
+    let mut data = None;
+    while let Some(field) = multipart.next_field().await? {
+        match field.name() {
+            Some("type") => import_type = DbImportType::from_string(&field.text().await?),
+            Some("mode") => match DbImportMode::from_string(&field.text().await?) {
+                Some(field_mode) => mode = field_mode,
+                None => {
+                    return Ok(ApiError::new(
+                        "Validation failed: Mode is not included in the list",
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                    )
+                    .into_response())
+                },
+            },
+            Some("data") => data = Some(field.bytes().await?),
+            _ => {},
+        }
+    }
+
+    let import_type = match import_type {
+        Some(import_type) => import_type,
+        None => {
+            return Ok(ApiError::new(
+                "Validation failed: Type is not included in the list",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response())
+        },
+    };
+    let rows = match data.map(|data| parse_rows(&data)) {
+        Some(Ok(rows)) if !rows.is_empty() => rows,
+        Some(Ok(_)) | None => {
+            return Ok(ApiError::new(
+                "Validation failed: Data can't be blank",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response())
+        },
+        Some(Err(_)) => {
+            return Ok(ApiError::new(
+                "Validation failed: Data is not a valid CSV file",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response())
+        },
+    };
+    if rows.len() > MAX_ROWS {
+        return Ok(ApiError::new_from_string(
+            format!(
+                "Validation failed: Data can't have more than {} rows",
+                MAX_ROWS
+            ),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+    if Import::has_unfinished(&user.id, &state.db_pool).await? {
+        return Ok(ApiError::new(
+            "Validation failed: Another import is still in progress",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    let import = Import::create(&user, import_type, mode, &rows, &state.db_pool).await?;
+
+    Ok(Json(ApiImport::from(import)).into_response())
+}
+
+// Cryap extension: imports of the user, newest first
+pub async fn http_get_imports(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let imports = Import::get_for_user(&session.user_id, pagination.into(), &state.db_pool)
+        .await?
+        .into_iter()
+        .map(ApiImport::from)
+        .collect::<Vec<ApiImport>>();
+
+    if imports.is_empty() {
+        Ok(Json(imports).into_response())
+    } else {
+        Ok((
+            [(
+                header::LINK, format!(
+                    "<https://{}/api/v1/cryap/imports?max_id={}>; rel=\"next\", <https://{}/api/v1/cryap/imports?min_id={}>; rel\"prev\"",
+                    state.config.web.domain, imports.last().unwrap().id.clone(),
+                    state.config.web.domain, imports.first().unwrap().id.clone()
+                )
+            )],
+            Json(imports),
+        ).into_response())
+    }
+}
+
+// Cryap extension: progress of a single import
+pub async fn http_get_import(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    match Import::by_id(&DbId::from(id), &state.db_pool).await? {
+        Some(import) if import.user_id == session.user_id => {
+            Ok(Json(ApiImport::from(import)).into_response())
+        },
+        _ => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+pub fn imports(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/cryap/imports",
+            get(http_get_imports.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            )))
+            .post(http_post_create.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/cryap/imports/:id",
+            get(http_get_import.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            ))),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_rows;
+
+    #[test]
+    fn mastodon_following_export() {
+        let data = b"Account address,Show boosts,Notify on new posts,Languages\n\
+            alice@example.com,true,false,\n\
+            bob@example.org,false,false,en\n\
+            alice@example.com,true,false,\n";
+        assert_eq!(
+            parse_rows(data).unwrap(),
+            vec!["alice@example.com", "bob@example.org"]
+        );
+    }
+
+    #[test]
+    fn headerless_bookmarks() {
+        let data = b"https://example.com/notes/1\n\nhttps://example.com/notes/2\n";
+        assert_eq!(
+            parse_rows(data).unwrap(),
+            vec!["https://example.com/notes/1", "https://example.com/notes/2"]
+        );
+    }
+}
@@ -4,7 +4,7 @@ use axum::{extract::State, response::IntoResponse, routing::get, Json, Router};
 use web::{errors::AppError, AppState};
 
 use crate::{
-    common::users,
+    common::{push, users},
     entities::{instance_v1, instance_v2, Rule},
 };
 
@@ -12,7 +12,11 @@ use crate::{
 pub async fn http_get_instance_v2(
     state: State<Arc<AppState>>,
 ) -> Result<impl IntoResponse, AppError> {
-    Ok(Json(instance_v2::Instance::new(&state.config)).into_response())
+    Ok(Json(instance_v2::Instance::new(
+        &state.config,
+        push::vapid_public_key(&state.vapid_key)?,
+    ))
+    .into_response())
 }
 
 // https://docs.joinmastodon.org/methods/instance/#v1
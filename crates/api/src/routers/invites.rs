@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    handler::Handler,
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{delete, get},
+    Extension, Json, Router,
+};
+use chrono::{Duration, Utc};
+use db::{
+    models::{Invite, Session},
+    types::DbId,
+};
+use serde::Deserialize;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    common::users::INVITE_MAX_EXPIRES_IN,
+    entities::Invite as ApiInvite,
+    error::ApiError,
+};
+
+#[derive(Deserialize)]
+pub struct CreateInviteBody {
+    max_uses: Option<i32>,
+    /// Seconds until the invite expires
+    expires_in: Option<i64>,
+    #[serde(default)]
+    autofollow: bool,
+}
+
+// Cryap extension: creates an invite that lets someone sign up even when registrations are closed
+pub async fn http_post_create(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Json(body): Json<CreateInviteBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+    if !state.config.instance.users_can_invite && !user.admin {
+        return Ok(ApiError::new(
+            "Invites are disabled on this instance",
+            StatusCode::FORBIDDEN,
+        )
+        .into_response());
+    }
+    if body.max_uses.is_some_and(|max_uses| max_uses < 1) {
+        return Ok(ApiError::new(
+            "Validation failed: Max uses must be greater than 0",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+    let expires_at = match body.expires_in {
+        Some(expires_in) if !(1..=INVITE_MAX_EXPIRES_IN).contains(&expires_in) => {
+            return Ok(ApiError::new_from_string(
+                format!(
+                    "Validation failed: Expiration must be between 1 and {} seconds",
+                    INVITE_MAX_EXPIRES_IN
+                ),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        },
+        Some(expires_in) => Some(Utc::now() + Duration::seconds(expires_in)),
+        None => None,
+    };
+
+    let invite = Invite::create(
+        Some(user.id),
+        body.max_uses,
+        expires_at,
+        body.autofollow,
+        &state.db_pool,
+    )
+    .await?;
+
+    Ok(Json(ApiInvite::new(invite, &state.config.web.domain)).into_response())
+}
+
+// Cryap extension: invites created by the user, newest first
+pub async fn http_get_invites(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let invites = Invite::get_for_user(&session.user_id, &state.db_pool)
+        .await?
+        .into_iter()
+        .map(|invite| ApiInvite::new(invite, &state.config.web.domain))
+        .collect::<Vec<ApiInvite>>();
+
+    Ok(Json(invites).into_response())
+}
+
+// Cryap extension: expires an invite. It isn't deleted, so that it's still known who the users
+// that signed up with it were invited by
+pub async fn http_delete_invite(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let invite = match Invite::by_id(&DbId::from(id), &state.db_pool).await? {
+        Some(invite) if invite.user_id.as_ref() == Some(&session.user_id) => invite,
+        _ => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+
+    invite.expire(&state.db_pool).await?;
+    let invite = Invite::by_id(&invite.id, &state.db_pool)
+        .await?
+        .unwrap_or(invite);
+
+    Ok(Json(ApiInvite::new(invite, &state.config.web.domain)).into_response())
+}
+
+pub fn invites(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/cryap/invites",
+            get(http_get_invites.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            )))
+            .post(http_post_create.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/cryap/invites/:id",
+            delete(http_delete_invite.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+}
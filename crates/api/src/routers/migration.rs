@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId, traits::Object};
+use ap::{activities::move_account::Move, common::follows, objects::user::ApUser};
+use axum::{
+    handler::Handler,
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Form, Json, Router,
+};
+use db::models::{user::UserUpdate, Session, User};
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    common::users,
+    error::ApiError,
+};
+
+#[derive(Deserialize)]
+pub struct AliasBody {
+    alias: String,
+}
+
+#[derive(Deserialize)]
+pub struct MoveAccountBody {
+    password: String,
+    target_account: String,
+}
+
+async fn set_aliases(
+    mut user: User,
+    aliases: Vec<String>,
+    state: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    let aliases = serde_json::to_value(aliases)?;
+    user.also_known_as = aliases.clone();
+    user.update(
+        UserUpdate {
+            also_known_as: Some(aliases),
+            ..Default::default()
+        },
+        &state.db_pool,
+    )
+    .await?;
+    users::distribute_update(&user, state).await
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#get-apipleromaaliases
+pub async fn http_get_aliases(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let mut aliases = vec![];
+    for alias in user.also_known_as() {
+        let user = match Url::parse(&alias) {
+            Ok(url) => ApUser::read_from_id(url, &state).await?,
+            Err(_) => None,
+        };
+        aliases.push(match user {
+            Some(user) => format!("{}@{}", user.name, user.instance),
+            None => alias,
+        });
+    }
+
+    Ok(Json(json!({ "aliases": aliases })).into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#put-apipleromaaliases
+pub async fn http_put_alias(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Json(body): Json<AliasBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let alias = match users::resolve_acct(&body.alias, &state).await? {
+        Some(alias) => alias,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    if alias.id == user.id {
+        return Ok(ApiError::new(
+            "Validation failed: Account can't be an alias of itself",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    let mut aliases = user.also_known_as();
+    if !aliases.contains(&alias.ap_id) {
+        aliases.push(alias.ap_id);
+        set_aliases(user, aliases, &state).await?;
+    }
+
+    Ok(Json(json!({ "status": "success" })).into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#delete-apipleromaaliases
+pub async fn http_delete_alias(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Json(body): Json<AliasBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let ap_id = match users::resolve_acct(&body.alias, &state).await? {
+        Some(alias) => alias.ap_id,
+        None => body.alias,
+    };
+    let mut aliases = user.also_known_as();
+    if !aliases.contains(&ap_id) {
+        return Ok(
+            ApiError::new("Account has no such alias", StatusCode::NOT_FOUND).into_response(),
+        );
+    }
+
+    aliases.retain(|alias| *alias != ap_id);
+    set_aliases(user, aliases, &state).await?;
+
+    Ok(Json(json!({ "status": "success" })).into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#post-apipleromamove_account
+pub async fn http_post_move_account(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Form(body): Form<MoveAccountBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    if !users::verify_password(&user, &body.password) {
+        return Ok(ApiError::new("Invalid password", StatusCode::FORBIDDEN).into_response());
+    }
+
+    let target = match users::resolve_acct(&body.target_account, &state).await? {
+        Some(target) => target,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    if target.id == user.id {
+        return Ok(ApiError::new(
+            "Validation failed: Account can't be moved to itself",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    // Remote targets are fetched again, as the alias might have been added just now
+    let target = if target.local {
+        target
+    } else {
+        ObjectId::<ApUser>::parse(&target.ap_id)?
+            .dereference_forced(&state)
+            .await?
+            .0
+    };
+    if !target.also_known_as().contains(&user.ap_id) {
+        return Ok(ApiError::new(
+            "Target account must have the origin in `alsoKnownAs`",
+            StatusCode::BAD_REQUEST,
+        )
+        .into_response());
+    }
+
+    Move::send(&ApUser(user.clone()), &ApUser(target.clone()), &state).await?;
+    follows::move_followers(&user, &target, &state).await?;
+
+    Ok(Json(json!({ "status": "success" })).into_response())
+}
+
+pub fn migration(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/pleroma/aliases",
+            get(http_get_aliases.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            )))
+            .put(http_put_alias.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            )))
+            .delete(http_delete_alias.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/pleroma/move_account",
+            post(http_post_move_account.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+}
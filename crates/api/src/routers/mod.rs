@@ -1,9 +1,23 @@
+pub mod account_deletion;
 pub mod accounts;
 pub mod apps;
+pub mod archives;
+pub mod articles;
+pub mod custom_emojis;
+pub mod exports;
+pub mod imports;
 pub mod instance;
+pub mod invites;
+pub mod migration;
 pub mod notifications;
+pub mod push;
+pub mod reactions;
+pub mod scheduled_statuses;
+pub mod sessions;
 pub mod statuses;
+pub mod tags;
 pub mod timelines;
+pub mod two_factor;
 pub mod ui;
 
 use std::sync::Arc;
@@ -13,11 +27,25 @@ use web::AppState;
 
 pub fn api(state: Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
+        .merge(account_deletion::account_deletion(&state))
         .merge(accounts::accounts(&state))
         .merge(apps::apps(&state))
+        .merge(archives::archives(&state))
+        .merge(articles::articles(&state))
+        .merge(custom_emojis::custom_emojis())
+        .merge(exports::exports(&state))
+        .merge(imports::imports(&state))
         .merge(instance::instance())
+        .merge(invites::invites(&state))
+        .merge(migration::migration(&state))
         .merge(notifications::notifications(&state))
+        .merge(push::push(&state))
+        .merge(reactions::reactions(&state))
+        .merge(scheduled_statuses::scheduled_statuses(&state))
+        .merge(sessions::sessions(&state))
         .merge(statuses::statuses(&state))
+        .merge(tags::tags(&state))
         .merge(timelines::timelines(&state))
+        .merge(two_factor::two_factor(&state))
         .merge(ui::ui())
 }
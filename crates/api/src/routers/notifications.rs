@@ -19,7 +19,9 @@ use serde::Deserialize;
 use web::{errors::AppError, AppState};
 
 use crate::{
-    auth_middleware::auth_middleware, entities::Notification as ApiNotification, error::ApiError,
+    auth_middleware::{auth_middleware, scope},
+    entities::Notification as ApiNotification,
+    error::ApiError,
     EmptyJsonObject,
 };
 
@@ -136,18 +138,30 @@ pub fn notifications(state: &Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route(
             "/api/v1/notifications",
-            get(http_get_get.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            get(http_get_get.layer(from_fn_with_state(
+                scope(state, "read:notifications"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/notifications/:id",
-            get(http_get_get_one.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            get(http_get_get_one.layer(from_fn_with_state(
+                scope(state, "read:notifications"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/notifications/clear",
-            post(http_post_clear.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_clear.layer(from_fn_with_state(
+                scope(state, "write:notifications"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/notifications/:id/dismiss",
-            post(http_post_dismiss.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_dismiss.layer(from_fn_with_state(
+                scope(state, "write:notifications"),
+                auth_middleware,
+            ))),
         )
 }
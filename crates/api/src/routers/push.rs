@@ -0,0 +1,296 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ap::common::profile_fields;
+use axum::{
+    body::Bytes,
+    extract::State,
+    handler::Handler,
+    http::{header, HeaderMap, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use db::models::{PushSubscription, Session};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Map, Value};
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    common::push::{self, ALERTS, POLICIES},
+    entities::WebPushSubscription,
+    error::ApiError,
+};
+
+#[derive(Deserialize)]
+pub struct SubscriptionKeys {
+    p256dh: String,
+    auth: String,
+}
+
+#[derive(Deserialize)]
+pub struct SubscriptionParams {
+    endpoint: String,
+    keys: SubscriptionKeys,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SubscriptionData {
+    /// Booleans in JSON bodies, strings in form bodies
+    alerts: Option<HashMap<String, Value>>,
+    policy: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSubscriptionBody {
+    subscription: SubscriptionParams,
+    #[serde(default)]
+    data: SubscriptionData,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateSubscriptionBody {
+    #[serde(default)]
+    data: SubscriptionData,
+    /// Mastodon also accepts the policy outside of `data`
+    policy: Option<String>,
+}
+
+/// Turns `subscription[keys][auth]=...` form fields into nested objects
+fn nest_form(body: &[u8]) -> Value {
+    let mut root = Map::new();
+    for (key, value) in url::form_urlencoded::parse(body) {
+        let path = key
+            .split('[')
+            .map(|part| part.trim_end_matches(']'))
+            .collect::<Vec<&str>>();
+        let mut object = &mut root;
+        for part in &path[..path.len() - 1] {
+            let entry = object
+                .entry(part.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            object = entry.as_object_mut().unwrap();
+        }
+        object.insert(
+            path[path.len() - 1].to_string(),
+            Value::String(value.into_owned()),
+        );
+    }
+    Value::Object(root)
+}
+
+/// Clients send either JSON or form bodies
+fn parse_body<T: DeserializeOwned>(headers: &HeaderMap, body: &[u8]) -> Option<T> {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    if is_json {
+        serde_json::from_slice(body).ok()
+    } else {
+        serde_json::from_value(nest_form(body)).ok()
+    }
+}
+
+/// Space-separated alerts that are turned on, unknown ones are left out
+fn enabled_alerts(alerts: &HashMap<String, Value>) -> String {
+    ALERTS
+        .iter()
+        .filter(|alert| match alerts.get(**alert) {
+            Some(Value::Bool(enabled)) => *enabled,
+            Some(Value::String(enabled)) => enabled == "true" || enabled == "1",
+            _ => false,
+        })
+        .copied()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn invalid_policy() -> ApiError {
+    ApiError::new(
+        "Validation failed: Policy is not included in the list",
+        StatusCode::UNPROCESSABLE_ENTITY,
+    )
+}
+
+// https://docs.joinmastodon.org/methods/push/#create
+pub async fn http_post_subscription(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let body = match parse_body::<CreateSubscriptionBody>(&headers, &body) {
+        Some(body) => body,
+        None => {
+            return Ok(ApiError::new(
+                "Validation failed: Endpoint and keys are required",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response())
+        },
+    };
+    let policy = body.data.policy.unwrap_or(String::from("all"));
+    if !POLICIES.contains(&policy.as_str()) {
+        return Ok(invalid_policy().into_response());
+    }
+    let endpoint_allowed = match url::Url::parse(&body.subscription.endpoint) {
+        Ok(endpoint) if endpoint.scheme() == "https" => {
+            profile_fields::resolve(&endpoint, false).await.is_ok()
+        },
+        _ => false,
+    };
+    if !endpoint_allowed {
+        return Ok(ApiError::new(
+            "Validation failed: Endpoint is invalid",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+    if push::encrypt(
+        b"",
+        &body.subscription.keys.p256dh,
+        &body.subscription.keys.auth,
+    )
+    .is_err()
+    {
+        return Ok(ApiError::new(
+            "Validation failed: Keys are invalid",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    let subscription = PushSubscription::create(
+        &session,
+        body.subscription.endpoint,
+        body.subscription.keys.p256dh,
+        body.subscription.keys.auth,
+        enabled_alerts(&body.data.alerts.unwrap_or_default()),
+        policy,
+        &state.db_pool,
+    )
+    .await?;
+
+    Ok(Json(WebPushSubscription::new(
+        subscription,
+        push::vapid_public_key(&state.vapid_key)?,
+    ))
+    .into_response())
+}
+
+// https://docs.joinmastodon.org/methods/push/#get
+pub async fn http_get_subscription(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let subscription = match PushSubscription::by_session(&session.id, &state.db_pool).await? {
+        Some(subscription) => subscription,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+
+    Ok(Json(WebPushSubscription::new(
+        subscription,
+        push::vapid_public_key(&state.vapid_key)?,
+    ))
+    .into_response())
+}
+
+// https://docs.joinmastodon.org/methods/push/#update
+pub async fn http_put_subscription(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let subscription = match PushSubscription::by_session(&session.id, &state.db_pool).await? {
+        Some(subscription) => subscription,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    let body =
+        parse_body::<UpdateSubscriptionBody>(&headers, &body).unwrap_or(UpdateSubscriptionBody {
+            data: SubscriptionData::default(),
+            policy: None,
+        });
+    let policy = body
+        .data
+        .policy
+        .or(body.policy)
+        .unwrap_or(subscription.policy.clone());
+    if !POLICIES.contains(&policy.as_str()) {
+        return Ok(invalid_policy().into_response());
+    }
+    let alerts = match &body.data.alerts {
+        Some(alerts) => enabled_alerts(alerts),
+        None => subscription.alerts.clone(),
+    };
+
+    let subscription = subscription.update(alerts, policy, &state.db_pool).await?;
+
+    Ok(Json(WebPushSubscription::new(
+        subscription,
+        push::vapid_public_key(&state.vapid_key)?,
+    ))
+    .into_response())
+}
+
+// https://docs.joinmastodon.org/methods/push/#delete
+pub async fn http_delete_subscription(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(subscription) = PushSubscription::by_session(&session.id, &state.db_pool).await? {
+        subscription.delete(&state.db_pool).await?;
+    }
+
+    Ok(Json(json!({})).into_response())
+}
+
+pub fn push(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/v1/push/subscription",
+        get(http_get_subscription.layer(from_fn_with_state(scope(state, "push"), auth_middleware)))
+            .post(
+                http_post_subscription
+                    .layer(from_fn_with_state(scope(state, "push"), auth_middleware)),
+            )
+            .put(
+                http_put_subscription
+                    .layer(from_fn_with_state(scope(state, "push"), auth_middleware)),
+            )
+            .delete(
+                http_delete_subscription
+                    .layer(from_fn_with_state(scope(state, "push"), auth_middleware)),
+            ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::nest_form;
+
+    #[test]
+    fn form_fields_are_nested() {
+        assert_eq!(
+            nest_form(
+                b"subscription[endpoint]=https%3A%2F%2Fpush.example%2F1&subscription[keys][auth]=abc&data[alerts][mention]=true&data[policy]=followed"
+            ),
+            json!({
+                "subscription": {
+                    "endpoint": "https://push.example/1",
+                    "keys": { "auth": "abc" },
+                },
+                "data": {
+                    "alerts": { "mention": "true" },
+                    "policy": "followed",
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use axum::{
+    extract::Path, handler::Handler, http::StatusCode, middleware::from_fn_with_state,
+    response::IntoResponse, routing::get, Extension, Json, Router,
+};
+use db::{
+    models::{Post, PostReaction, Session, User},
+    types::DbId,
+};
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, optional_auth_middleware, scope},
+    common::posts,
+    entities::{Account, EmojiReaction, Status},
+    error::ApiError,
+};
+
+/// Post with the ID if the user can see it, reactions to boosts go to the boosted post
+async fn accessible_post(
+    id: String,
+    user: Option<&User>,
+    state: &Data<Arc<AppState>>,
+) -> anyhow::Result<Option<Post>> {
+    let (post, boost) = posts::post_or_boost_by_id(&DbId::from(id), &state.db_pool).await?;
+    let post = match post {
+        Some(post) => post,
+        None => return Ok(None),
+    };
+
+    let accessible = match boost {
+        Some(boost) => posts::boost_accessible_for(&boost, user, &state.db_pool).await?,
+        None => posts::accessible_for(&post, user, &state.db_pool).await?,
+    };
+    Ok(accessible.then_some(post))
+}
+
+async fn reactions_with_accounts(
+    post: &Post,
+    name: Option<&str>,
+    user: Option<&User>,
+    state: &Data<Arc<AppState>>,
+) -> anyhow::Result<Vec<EmojiReaction>> {
+    let mut reactors = PostReaction::reactors(post, name, &state.db_pool).await?;
+    let reactions =
+        PostReaction::summaries(vec![&post.id], user.map(|user| &user.id), &state.db_pool)
+            .await?
+            .into_iter()
+            .filter(|summary| name.map(|name| summary.name == name).unwrap_or(true))
+            .map(|summary| {
+                let mut reaction = EmojiReaction::from(summary);
+                let (accounts, rest) =
+                    reactors
+                        .drain(..)
+                        .partition::<Vec<(String, User)>, _>(|(reactor_name, _)| {
+                            *reactor_name == reaction.name
+                        });
+                reactors = rest;
+                reaction.accounts = Some(
+                    accounts
+                        .into_iter()
+                        .map(|(_, user)| Account::new(user, false))
+                        .collect(),
+                );
+                reaction
+            })
+            .collect();
+
+    Ok(reactions)
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#get-apiv1pleromastatusesidreactions
+pub async fn http_get_reactions(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Option<Session>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = match session {
+        Some(session) => Some(session.user(&state.db_pool).await?),
+        None => None,
+    };
+
+    match accessible_post(id, user.as_ref(), &state).await? {
+        Some(post) => Ok(
+            Json(reactions_with_accounts(&post, None, user.as_ref(), &state).await?)
+                .into_response(),
+        ),
+        None => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#get-apiv1pleromastatusesidreactionsemoji
+pub async fn http_get_reaction(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Option<Session>>,
+    Path((id, emoji)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = match session {
+        Some(session) => Some(session.user(&state.db_pool).await?),
+        None => None,
+    };
+
+    let post = match accessible_post(id, user.as_ref(), &state).await? {
+        Some(post) => post,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    let name = match posts::resolve_reaction(&emoji, &state.db_pool).await? {
+        Some((name, _)) => name,
+        None => return Ok(Json(Vec::<EmojiReaction>::new()).into_response()),
+    };
+
+    Ok(
+        Json(reactions_with_accounts(&post, Some(&name), user.as_ref(), &state).await?)
+            .into_response(),
+    )
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#put-apiv1pleromastatusesidreactionsemoji
+pub async fn http_put_reaction(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path((id, emoji)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let post = match accessible_post(id, Some(&user), &state).await? {
+        Some(post) => post,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    let (name, emoji) = match posts::resolve_reaction(&emoji, &state.db_pool).await? {
+        Some(reaction) => reaction,
+        None => {
+            return Ok(ApiError::new(
+                "Validation failed: Emoji is not a valid emoji",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response())
+        },
+    };
+
+    posts::react(&user, &post, name, emoji, &state).await?;
+
+    Ok(Json(Status::build(post, Some(&user.id), &state).await?).into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#delete-apiv1pleromastatusesidreactionsemoji
+pub async fn http_delete_reaction(
+    state: Data<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path((id, emoji)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    let post = match accessible_post(id, Some(&user), &state).await? {
+        Some(post) => post,
+        None => return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    };
+    if let Some((name, _)) = posts::resolve_reaction(&emoji, &state.db_pool).await? {
+        posts::unreact(&user, &post, &name, &state).await?;
+    }
+
+    Ok(Json(Status::build(post, Some(&user.id), &state).await?).into_response())
+}
+
+pub fn reactions(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/pleroma/statuses/:id/reactions",
+            get(http_get_reactions.layer(from_fn_with_state(
+                scope(state, "read:statuses"),
+                optional_auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/pleroma/statuses/:id/reactions/:emoji",
+            get(http_get_reaction.layer(from_fn_with_state(
+                scope(state, "read:statuses"),
+                optional_auth_middleware,
+            )))
+            .put(http_put_reaction.layer(from_fn_with_state(
+                scope(state, "write:statuses"),
+                auth_middleware,
+            )))
+            .delete(http_delete_reaction.layer(from_fn_with_state(
+                scope(state, "write:statuses"),
+                auth_middleware,
+            ))),
+        )
+}
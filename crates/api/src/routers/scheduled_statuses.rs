@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    handler::Handler,
+    http::{header, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use db::{
+    models::{ScheduledStatus, Session},
+    pagination::PaginationQuery,
+    types::DbId,
+};
+use serde::Deserialize;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    entities::ScheduledStatus as ApiScheduledStatus,
+    error::ApiError,
+    routers::statuses::MIN_SCHEDULED_STATUS_DELAY,
+    EmptyJsonObject,
+};
+
+// https://docs.joinmastodon.org/methods/scheduled_statuses/#get
+pub async fn http_get_get(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let scheduled_statuses = ApiScheduledStatus::new_from_vec(
+        ScheduledStatus::get_for_user(&session.user_id, pagination.into(), &state.db_pool).await?,
+    )?;
+
+    if scheduled_statuses.is_empty() {
+        Ok(Json(scheduled_statuses).into_response())
+    } else {
+        Ok((
+            [(
+                header::LINK, format!(
+                    "<https://{}/api/v1/scheduled_statuses?max_id={}>; rel=\"next\", <https://{}/api/v1/scheduled_statuses?min_id={}>; rel\"prev\"",
+                    state.config.web.domain, scheduled_statuses.last().unwrap().id.clone(),
+                    state.config.web.domain, scheduled_statuses.first().unwrap().id.clone()
+                )
+            )],
+            Json(scheduled_statuses),
+        ).into_response())
+    }
+}
+
+// https://docs.joinmastodon.org/methods/scheduled_statuses/#get-one
+pub async fn http_get_get_one(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let id = DbId::from(id);
+    let scheduled_status = ScheduledStatus::by_id(&id, &state.db_pool).await?;
+    match scheduled_status {
+        Some(scheduled_status) if scheduled_status.user_id == session.user_id => {
+            Ok(Json(ApiScheduledStatus::new(scheduled_status)?).into_response())
+        },
+        _ => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateBody {
+    scheduled_at: DateTime<Utc>,
+}
+
+// https://docs.joinmastodon.org/methods/scheduled_statuses/#update
+pub async fn http_put_update(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let id = DbId::from(id);
+    let scheduled_status = ScheduledStatus::by_id(&id, &state.db_pool).await?;
+    match scheduled_status {
+        Some(scheduled_status) if scheduled_status.user_id == session.user_id => {
+            if body.scheduled_at < Utc::now() + Duration::minutes(MIN_SCHEDULED_STATUS_DELAY) {
+                return Ok(ApiError::new(
+                    "Validation failed: Scheduled at The scheduled date must be in the future",
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                )
+                .into_response());
+            }
+
+            let scheduled_status = scheduled_status
+                .reschedule(body.scheduled_at, &state.db_pool)
+                .await?;
+            Ok(Json(ApiScheduledStatus::new(scheduled_status)?).into_response())
+        },
+        _ => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+// https://docs.joinmastodon.org/methods/scheduled_statuses/#cancel
+pub async fn http_delete_cancel(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let id = DbId::from(id);
+    let scheduled_status = ScheduledStatus::by_id(&id, &state.db_pool).await?;
+    match scheduled_status {
+        Some(scheduled_status) if scheduled_status.user_id == session.user_id => {
+            scheduled_status.delete(&state.db_pool).await?;
+            Ok(EmptyJsonObject::response())
+        },
+        _ => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+pub fn scheduled_statuses(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/scheduled_statuses",
+            get(http_get_get.layer(from_fn_with_state(
+                scope(state, "read:statuses"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/scheduled_statuses/:id",
+            get(http_get_get_one.layer(from_fn_with_state(
+                scope(state, "read:statuses"),
+                auth_middleware,
+            )))
+            .put(http_put_update.layer(from_fn_with_state(
+                scope(state, "write:statuses"),
+                auth_middleware,
+            )))
+            .delete(http_delete_cancel.layer(from_fn_with_state(
+                scope(state, "write:statuses"),
+                auth_middleware,
+            ))),
+        )
+}
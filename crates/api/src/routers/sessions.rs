@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    handler::Handler,
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{delete, get},
+    Extension, Json, Router,
+};
+use db::{
+    models::{Application, Session},
+    types::DbId,
+};
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    common::sessions,
+    entities::Application as ApiApplication,
+    error::ApiError,
+    EmptyJsonObject,
+};
+
+// Cryap extension: where the user is signed in, most recently used first
+pub async fn http_get_sessions(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(sessions::list(&session.user_id, &session.id, &state).await?).into_response())
+}
+
+// Cryap extension: signs the session out, its streaming connections are closed
+pub async fn http_delete_session(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    match Session::by_id(&DbId::from(id), &state.db_pool).await? {
+        Some(revoked) if revoked.user_id == session.user_id => {
+            sessions::revoke(&revoked, &state).await?;
+            Ok(EmptyJsonObject::response())
+        },
+        _ => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+// Cryap extension: applications the user has given access to their account
+pub async fn http_get_authorized_apps(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let applications = Application::authorized_by(&session.user_id, &state.db_pool)
+        .await?
+        .into_iter()
+        .map(|application| ApiApplication::new(application, false))
+        .collect::<Vec<ApiApplication>>();
+
+    Ok(Json(applications).into_response())
+}
+
+// Cryap extension: revokes every token the user has given to the application
+pub async fn http_delete_authorized_app(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    if sessions::revoke_application(&session.user_id, &DbId::from(id), &state).await? {
+        Ok(EmptyJsonObject::response())
+    } else {
+        Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response())
+    }
+}
+
+pub fn sessions(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/cryap/sessions",
+            get(http_get_sessions.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/cryap/sessions/:id",
+            delete(http_delete_session.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/cryap/authorized_apps",
+            get(http_get_authorized_apps.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/cryap/authorized_apps/:id",
+            delete(http_delete_authorized_app.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+}
@@ -10,8 +10,9 @@ use axum::{
     routing::{get, post},
     Extension, Json, Router,
 };
+use chrono::{DateTime, Duration, Utc};
 use db::{
-    models::{Post, Session},
+    models::{Post, ScheduledStatus, Session},
     pagination::PaginationQuery,
     types::{DbId, DbVisibility},
 };
@@ -19,12 +20,15 @@ use serde::Deserialize;
 use web::{errors::AppError, AppState};
 
 use crate::{
-    auth_middleware::{auth_middleware, optional_auth_middleware},
+    auth_middleware::{auth_middleware, optional_auth_middleware, scope},
     common::{self, posts},
-    entities::{Account, Status},
+    entities::{Account, ScheduledStatus as ApiScheduledStatus, Status},
     error::ApiError,
 };
 
+/// Same as in Mastodon: scheduled statuses must be at least 5 minutes in the future
+pub const MIN_SCHEDULED_STATUS_DELAY: i64 = 5;
+
 #[derive(Deserialize)]
 pub struct CreatePostBody {
     status: String,
@@ -33,6 +37,9 @@ pub struct CreatePostBody {
     sensitive: Option<bool>,
     spoiler_text: Option<String>,
     visibility: Option<DbVisibility>,
+    /// Glitch and Hometown extension
+    local_only: Option<bool>,
+    scheduled_at: Option<DateTime<Utc>>,
 }
 
 // https://docs.joinmastodon.org/methods/statuses/#create
@@ -43,28 +50,63 @@ pub async fn http_post_create(
 ) -> Result<impl IntoResponse, AppError> {
     let user = session.user(&state.db_pool).await?;
 
-    let post = common::posts::post(
-        &user,
-        posts::NewPost {
-            visibility: body.visibility.unwrap_or(DbVisibility::Public),
-            content: body.status,
-            in_reply: match body.in_reply_to_id {
-                Some(id) => Post::by_id(&DbId::from(id), &state.db_pool).await?,
-                None => None,
-            },
-            quote: match body.quote_id {
-                Some(id) => Post::by_id(&DbId::from(id), &state.db_pool).await?,
-                None => None,
-            },
-            local_only: false,
-            sensitive: body.sensitive.unwrap_or(false),
-            content_warning: body.spoiler_text,
+    let options = posts::NewPost {
+        visibility: body.visibility.unwrap_or(DbVisibility::Public),
+        content: body.status,
+        in_reply: match body.in_reply_to_id {
+            Some(id) => Post::by_id(&DbId::from(id), &state.db_pool).await?,
+            None => None,
+        },
+        quote: match body.quote_id {
+            Some(id) => Post::by_id(&DbId::from(id), &state.db_pool).await?,
+            None => None,
         },
-        &state,
-    )
-    .await?;
+        local_only: body.local_only.unwrap_or(false),
+        sensitive: body.sensitive.unwrap_or(false),
+        content_warning: body.spoiler_text,
+        article: None,
+    };
+
+    if let Some(quote) = &options.quote {
+        if !posts::accessible_for(quote, Some(&user), &state.db_pool).await? {
+            return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response());
+        }
+        if !matches!(
+            quote.visibility,
+            DbVisibility::Public | DbVisibility::Unlisted
+        ) && quote.author != user.id
+        {
+            return Ok(ApiError::new(
+                "Validation failed: Quote of private posts is not allowed",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        }
+    }
+
+    if let Some(scheduled_at) = body.scheduled_at {
+        if scheduled_at < Utc::now() + Duration::minutes(MIN_SCHEDULED_STATUS_DELAY) {
+            return Ok(ApiError::new(
+                "Validation failed: Scheduled at The scheduled date must be in the future",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        }
+
+        let scheduled_status = ScheduledStatus::create(
+            &user,
+            scheduled_at,
+            &options.to_scheduled_params(),
+            &state.db_pool,
+        )
+        .await?;
+
+        return Ok(Json(ApiScheduledStatus::new(scheduled_status)?).into_response());
+    }
 
-    Ok(Json(Status::build(post, None, &state).await?).into_response())
+    let post = common::posts::post(&user, options, &state).await?;
+
+    Ok(Json(Status::build(post, Some(&user.id), &state).await?).into_response())
 }
 
 // https://docs.joinmastodon.org/methods/statuses/#get
@@ -459,51 +501,65 @@ pub fn statuses(state: &Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route(
             "/api/v1/statuses",
-            post(http_post_create.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_create.layer(from_fn_with_state(
+                scope(state, "write:statuses"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/statuses/:id",
             get(http_get_get.layer(from_fn_with_state(
-                Arc::clone(state),
+                scope(state, "read:statuses"),
                 optional_auth_middleware,
             ))),
         )
         .route(
             "/api/v1/statuses/:id/favourited_by",
             get(http_get_favourited_by.layer(from_fn_with_state(
-                Arc::clone(state),
+                scope(state, "read:statuses"),
                 optional_auth_middleware,
             ))),
         )
         .route(
             "/api/v1/statuses/:id/reblogged_by",
             get(http_get_reblogged_by.layer(from_fn_with_state(
-                Arc::clone(state),
+                scope(state, "read:statuses"),
                 optional_auth_middleware,
             ))),
         )
         .route(
             "/api/v1/statuses/:id/favourite",
-            post(http_post_favourite.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_favourite.layer(from_fn_with_state(
+                scope(state, "write:favourites"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/statuses/:id/unfavourite",
-            post(
-                http_post_unfavourite.layer(from_fn_with_state(Arc::clone(state), auth_middleware)),
-            ),
+            post(http_post_unfavourite.layer(from_fn_with_state(
+                scope(state, "write:favourites"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/statuses/:id/reblog",
-            post(http_post_reblog.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_reblog.layer(from_fn_with_state(
+                scope(state, "write:statuses"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/statuses/:id/bookmark",
-            post(http_post_bookmark.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            post(http_post_bookmark.layer(from_fn_with_state(
+                scope(state, "write:bookmarks"),
+                auth_middleware,
+            ))),
         )
         .route(
             "/api/v1/statuses/:id/unbookmark",
-            post(
-                http_post_unbookmark.layer(from_fn_with_state(Arc::clone(state), auth_middleware)),
-            ),
+            post(http_post_unbookmark.layer(from_fn_with_state(
+                scope(state, "write:bookmarks"),
+                auth_middleware,
+            ))),
         )
 }
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    handler::Handler,
+    http::{header, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use db::{
+    models::{FollowedTag, Session, Tag},
+    pagination::PaginationQuery,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{any_scope, auth_middleware, optional_auth_middleware, scope},
+    entities::Tag as ApiTag,
+    error::ApiError,
+};
+
+lazy_static! {
+    static ref TAG_NAME_RE: Regex = Regex::new(r"^\w{1,100}$").unwrap();
+}
+
+// https://docs.joinmastodon.org/methods/tags/#get
+pub async fn http_get_get(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Option<Session>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    match Tag::by_name(&name, &state.db_pool).await? {
+        Some(tag) => {
+            let following = match session {
+                Some(session) => {
+                    Some(FollowedTag::exists(&session.user_id, &tag, &state.db_pool).await?)
+                },
+                None => None,
+            };
+
+            Ok(Json(ApiTag::new(tag, following, &state.config.web.domain)).into_response())
+        },
+        None => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+// https://docs.joinmastodon.org/methods/tags/#follow
+pub async fn http_post_follow(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    if !TAG_NAME_RE.is_match(&name) {
+        return Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response());
+    }
+
+    let user = session.user(&state.db_pool).await?;
+    let tag = Tag::by_name_or_create(&name, &state.db_pool).await?;
+    FollowedTag::create(&user, &tag, &state.db_pool).await?;
+
+    Ok(Json(ApiTag::new(tag, Some(true), &state.config.web.domain)).into_response())
+}
+
+// https://docs.joinmastodon.org/methods/tags/#unfollow
+pub async fn http_post_unfollow(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    match Tag::by_name(&name, &state.db_pool).await? {
+        Some(tag) => {
+            let user = session.user(&state.db_pool).await?;
+            FollowedTag::delete(&user, &tag, &state.db_pool).await?;
+
+            Ok(Json(ApiTag::new(tag, Some(false), &state.config.web.domain)).into_response())
+        },
+        None => Ok(ApiError::new("Record not found", StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+// https://docs.joinmastodon.org/methods/followed_tags/#get
+pub async fn http_get_followed_tags(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+    let tags = Tag::followed_by(&user, pagination.into(), &state.db_pool).await?;
+    let last_id = tags.last().map(|tag| tag.id.clone());
+    let first_id = tags.first().map(|tag| tag.id.clone());
+    let tags: Vec<ApiTag> = tags
+        .into_iter()
+        .map(|tag| ApiTag::new(tag, Some(true), &state.config.web.domain))
+        .collect();
+
+    if let (Some(last_id), Some(first_id)) = (last_id, first_id) {
+        Ok((
+            [(
+                header::LINK, format!(
+                    "<https://{}/api/v1/followed_tags?max_id={}>; rel=\"next\", <https://{}/api/v1/followed_tags?min_id={}>; rel\"prev\"",
+                    state.config.web.domain, last_id,
+                    state.config.web.domain, first_id
+                )
+            )],
+            Json(tags),
+        ).into_response())
+    } else {
+        Ok(Json(tags).into_response())
+    }
+}
+
+pub fn tags(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/v1/tags/:name",
+            get(http_get_get.layer(from_fn_with_state(
+                any_scope(state),
+                optional_auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/tags/:name/follow",
+            post(http_post_follow.layer(from_fn_with_state(
+                scope(state, "write:follows"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/tags/:name/unfollow",
+            post(http_post_unfollow.layer(from_fn_with_state(
+                scope(state, "write:follows"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/v1/followed_tags",
+            get(http_get_followed_tags.layer(from_fn_with_state(
+                scope(state, "read:follows"),
+                auth_middleware,
+            ))),
+        )
+}
@@ -3,25 +3,59 @@ mod streaming;
 use std::sync::Arc;
 
 use axum::{
-    extract::State, handler::Handler, middleware::from_fn_with_state, response::IntoResponse,
-    routing::get, Extension, Json, Router,
+    extract::{Query, State},
+    handler::Handler,
+    http::header,
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
 };
-use db::models::Session;
+use db::{models::Session, pagination::PaginationQuery};
 use web::{errors::AppError, AppState};
 
-use crate::auth_middleware::auth_middleware;
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    entities::Status,
+};
 
-// https://docs.joinmastodon.org/methods/statuses/#get
+// https://docs.joinmastodon.org/methods/timelines/#home
 pub async fn http_get_home(
-    _state: State<Arc<AppState>>,
-    Extension(_session): Extension<Session>,
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Query(pagination): Query<PaginationQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    Ok(Json::<Vec<()>>(vec![]))
+    let user = session.user(&state.db_pool).await?;
+    let timeline = Status::build_timeline(
+        user.home_timeline(pagination.into(), &state.db_pool)
+            .await?,
+        Some(&user.id),
+        &state,
+    )
+    .await?;
+
+    if timeline.is_empty() {
+        Ok(Json(timeline).into_response())
+    } else {
+        Ok((
+            [(
+                header::LINK, format!(
+                    "<https://{}/api/v1/timelines/home?max_id={}>; rel=\"next\", <https://{}/api/v1/timelines/home?min_id={}>; rel\"prev\"",
+                    state.config.web.domain, timeline.last().unwrap().id.clone(),
+                    state.config.web.domain, timeline.first().unwrap().id.clone()
+                )
+            )],
+            Json(timeline),
+        ).into_response())
+    }
 }
 
 pub fn timelines(state: &Arc<AppState>) -> Router<Arc<AppState>> {
-    Router::new().merge(streaming::streaming(state)).route(
+    Router::new().merge(streaming::streaming()).route(
         "/api/v1/timelines/home",
-        get(http_get_home.layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+        get(http_get_home.layer(from_fn_with_state(
+            scope(state, "read:statuses"),
+            auth_middleware,
+        ))),
     )
 }
@@ -1,80 +1,449 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use ap::common::streaming::{StreamingCategory, StreamingEvent, EVENT_BUS};
+use ap::common::streaming::{
+    StreamingCategory, StreamingEvent, StreamingReceiverGuard, StreamingTopic, EVENT_BUS,
+};
 use async_stream::try_stream;
 use axum::{
     extract::{
         ws::{Message, WebSocket},
         Query, State, WebSocketUpgrade,
     },
-    handler::Handler,
-    http::StatusCode,
-    middleware::from_fn_with_state,
+    http::{
+        header::{AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL},
+        HeaderMap, StatusCode,
+    },
     response::{
         sse::{Event, KeepAlive, Sse},
-        IntoResponse,
+        IntoResponse, Response,
     },
     routing::get,
-    Extension, Router,
+    Router,
 };
-use db::models::Session;
+use db::{common::timelines::TimelineEntry, models::Session, types::DbId};
 use futures::{stream::Stream, SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{mpsc, Mutex};
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc, Mutex},
+    task::JoinHandle,
+};
 use web::{errors::AppError, AppState};
 
-use crate::{auth_middleware::auth_middleware, entities::Notification, error::ApiError};
+use crate::{
+    auth_middleware::{approved_session, outside_scopes},
+    entities::{Notification, Status},
+    error::ApiError,
+    scopes,
+};
 
 // https://docs.joinmastodon.org/methods/streaming/#health
 pub async fn http_get_health() -> impl IntoResponse {
     String::from("OK").into_response()
 }
 
-// https://docs.joinmastodon.org/methods/streaming/#notification
-pub async fn http_get_user_notification(
-    state: State<Arc<AppState>>,
-    Extension(session): Extension<Session>,
+/// Event as clients get it, `stream` has the categories the connection is subscribed to
+struct RenderedEvent {
+    stream: Vec<String>,
+    event: String,
+    payload: String,
+}
+
+/// Name and payload of the event. Statuses are built for the user, who is known to be able to
+/// see them, as events are only sent to their recipients. Anonymous connections only get public
+/// streams, where local-only posts are left out for them
+async fn render_payload(
+    event: StreamingEvent,
+    user_id: Option<&DbId>,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Option<(String, String)>> {
+    let (event, payload) = match event {
+        StreamingEvent::Update { payload, .. } => (
+            "update",
+            serde_json::to_string(&match payload {
+                TimelineEntry::Post(post) if post.local_only && user_id.is_none() => {
+                    return Ok(None)
+                },
+                TimelineEntry::Post(post) => Status::build(post, user_id, state).await?,
+                TimelineEntry::Boost(boost, post) => {
+                    Status::build_from_boost(boost, Some(post), user_id, state).await?
+                },
+            })?,
+        ),
+        StreamingEvent::Delete { payload, .. } => ("delete", payload.to_string()),
+        StreamingEvent::Notification { payload, .. } => (
+            "notification",
+            serde_json::to_string(&Notification::build(payload, state).await?)?,
+        ),
+        StreamingEvent::FiltersChanged { .. } => ("filters_changed", String::new()),
+        StreamingEvent::StatusUpdate { payload, .. } if payload.local_only && user_id.is_none() => {
+            return Ok(None)
+        },
+        StreamingEvent::StatusUpdate { payload, .. } => (
+            "status.update",
+            serde_json::to_string(&Status::build(payload, user_id, state).await?)?,
+        ),
+        StreamingEvent::SessionsRevoked { .. } => return Ok(None),
+    };
+
+    Ok(Some((event.to_string(), payload)))
+}
+
+/// Event of the user's own channel, `None` if it isn't in one of the categories
+async fn render_event(
+    event: StreamingEvent,
+    user_id: &DbId,
+    subscribed: &HashSet<StreamingCategory>,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Option<RenderedEvent>> {
+    let stream = event
+        .categories()
+        .iter()
+        .filter(|category| subscribed.contains(category))
+        .map(|category| category.name())
+        .collect::<Vec<String>>();
+    if stream.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(render_payload(event, Some(user_id), state)
+        .await?
+        .map(|(event, payload)| RenderedEvent {
+            stream,
+            event,
+            payload,
+        }))
+}
+
+/// Where the events of a stream come from
+enum StreamSource {
+    /// The user's own channel, filtered by the category
+    Account(StreamingCategory),
+    Topic(StreamingTopic),
+}
+
+/// Resolves a stream by name, as in the `stream` parameter of the WebSocket. Streams of an
+/// account need a session
+fn stream_source(
+    stream: &str,
+    tag: Option<&str>,
+    session: Option<&Session>,
+) -> Result<StreamSource, ApiError> {
+    let category = StreamingCategory::by_name(stream)
+        .ok_or_else(|| ApiError::new("Unknown stream type", StatusCode::BAD_REQUEST))?;
+    match StreamingTopic::new(category.clone(), tag) {
+        Some(topic) => Ok(StreamSource::Topic(topic)),
+        None if matches!(
+            category,
+            StreamingCategory::Hashtag | StreamingCategory::HashtagLocal
+        ) =>
+        {
+            Err(ApiError::new(
+                "Missing tag name parameter",
+                StatusCode::BAD_REQUEST,
+            ))
+        },
+        None if session.is_none() => Err(ApiError::new(
+            "Missing access token",
+            StatusCode::UNAUTHORIZED,
+        )),
+        None => Ok(StreamSource::Account(category)),
+    }
+}
+
+/// Token from the `Authorization` header, the `Sec-WebSocket-Protocol` header, as browsers can't
+/// set other headers on WebSockets, or the `access_token` query parameter
+fn access_token(headers: &HeaderMap, query: Option<String>) -> Option<String> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .or_else(|| websocket_protocol(headers))
+        .map(String::from)
+        .or(query)
+}
+
+fn websocket_protocol(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.split(',').next())
+        .map(str::trim)
+        .filter(|protocol| !protocol.is_empty())
+}
+
+/// Scope a token needs to follow the stream, as in Mastodon
+fn required_scope(stream: &str) -> &'static str {
+    match stream {
+        "user:notification" => "read:notifications",
+        _ => "read:statuses",
+    }
+}
+
+/// Session of the connection, `None` for anonymous connections, which are only let through when
+/// the `allow_unauthenticated_streaming` setting is enabled
+async fn streaming_session(
+    token: Option<String>,
+    required: &[&str],
+    state: &Arc<AppState>,
+) -> anyhow::Result<Result<Option<Session>, ApiError>> {
+    let token = match token {
+        Some(token) => token,
+        None if state.config.instance.allow_unauthenticated_streaming => return Ok(Ok(None)),
+        None => {
+            return Ok(Err(ApiError::new(
+                "Missing access token",
+                StatusCode::UNAUTHORIZED,
+            )))
+        },
+    };
+
+    Ok(match approved_session(&token, state).await? {
+        Ok(session)
+            if required
+                .iter()
+                .any(|scope| scopes::allows(&session.scopes, scope)) =>
+        {
+            Ok(Some(session))
+        },
+        Ok(_) => Err(outside_scopes()),
+        Err(err) => Err(err),
+    })
+}
+
+/// Receives from the channel if there is one, so that `select!` skips the branch otherwise
+async fn receive(
+    receiver: Option<&mut StreamingReceiverGuard>,
+) -> Option<Result<StreamingEvent, RecvError>> {
+    Some(receiver?.recv().await)
+}
+
+/// Server-sent events of the stream, until the session is revoked. The user's own channel is
+/// followed for public streams too, to notice when that happens
+fn event_stream(
+    session: Option<Session>,
+    source: StreamSource,
+    state: Arc<AppState>,
 ) -> Sse<impl Stream<Item = anyhow::Result<Event>>> {
-    let mut stream = EVENT_BUS.get_receiver(&session.user_id).await;
     Sse::new(try_stream! {
+        let user_id = session.as_ref().map(|session| session.user_id.clone());
+        let (categories, topic) = match source {
+            StreamSource::Account(category) => (HashSet::from([category]), None),
+            StreamSource::Topic(topic) => (HashSet::new(), Some(topic)),
+        };
+        let mut account_receiver = match &user_id {
+            Some(user_id) => Some(EVENT_BUS.get_receiver(user_id).await),
+            None => None,
+        };
+        let mut topic_receiver = match &topic {
+            Some(topic) => Some(EVENT_BUS.get_topic_receiver(topic).await),
+            None => None,
+        };
         yield Event::default().comment(")");
-        while let Ok(event) = stream.recv().await {
-            match event {
-                StreamingEvent::Notification { payload, categories } if categories.contains(&StreamingCategory::UserNotification) => {
-                    yield Event::default().event("notification").json_data(Notification::build(payload, &state).await?)?;
-                },
-                _ => {}
+        loop {
+            let (received, from_topic) = tokio::select! {
+                Some(received) = receive(account_receiver.as_mut()) => (received, false),
+                Some(received) = receive(topic_receiver.as_mut()) => (received, true),
+                else => break,
+            };
+            let event = match received {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            if let (StreamingEvent::SessionsRevoked { payload, .. }, Some(session)) = (&event, &session) {
+                if payload.contains(&session.id) {
+                    break;
+                }
+            }
+
+            let rendered = match (from_topic, &user_id) {
+                (true, _) => render_payload(event, user_id.as_ref(), &state).await?,
+                (false, Some(user_id)) => render_event(event, user_id, &categories, &state)
+                    .await?
+                    .map(|rendered| (rendered.event, rendered.payload)),
+                (false, None) => None,
+            };
+            if let Some((event, payload)) = rendered {
+                yield Event::default().event(event).data(payload);
             }
         }
     })
     .keep_alive(KeepAlive::default().text("thump"))
 }
 
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    access_token: Option<String>,
+    tag: Option<String>,
+    #[serde(default)]
+    only_media: bool,
+}
+
+/// Server-sent events of the stream with the given name
+async fn sse_response(
+    stream: &str,
+    tag: Option<String>,
+    access_token: Option<String>,
+    headers: &HeaderMap,
+    state: Arc<AppState>,
+) -> Result<Response, AppError> {
+    let scope = required_scope(stream);
+    let session =
+        match streaming_session(access_token(headers, access_token), &[scope], &state).await? {
+            Ok(session) => session,
+            Err(error) => return Ok(error.into_response()),
+        };
+    let source = match stream_source(stream, tag.as_deref(), session.as_ref()) {
+        Ok(source) => source,
+        Err(error) => return Ok(error.into_response()),
+    };
+
+    Ok(event_stream(session, source, state).into_response())
+}
+
+/// Name of the stream, with `:media` when only posts with media are asked for
+fn media_stream(stream: &str, only_media: bool) -> String {
+    if only_media {
+        format!("{}:media", stream)
+    } else {
+        String::from(stream)
+    }
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#public
+pub async fn http_get_public(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let stream = media_stream("public", query.only_media);
+    sse_response(&stream, None, query.access_token, &headers, state.0).await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#public-local
+pub async fn http_get_public_local(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let stream = media_stream("public:local", query.only_media);
+    sse_response(&stream, None, query.access_token, &headers, state.0).await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#public-remote
+pub async fn http_get_public_remote(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let stream = media_stream("public:remote", query.only_media);
+    sse_response(&stream, None, query.access_token, &headers, state.0).await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#hashtag
+pub async fn http_get_hashtag(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    sse_response("hashtag", query.tag, query.access_token, &headers, state.0).await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#hashtag-local
+pub async fn http_get_hashtag_local(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    sse_response(
+        "hashtag:local",
+        query.tag,
+        query.access_token,
+        &headers,
+        state.0,
+    )
+    .await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#user
+pub async fn http_get_user(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    sse_response("user", None, query.access_token, &headers, state.0).await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#notification
+pub async fn http_get_user_notification(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    sse_response(
+        "user:notification",
+        None,
+        query.access_token,
+        &headers,
+        state.0,
+    )
+    .await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#list
+// Cryap has no lists, so the stream stays empty
+pub async fn http_get_list(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    sse_response("list", None, query.access_token, &headers, state.0).await
+}
+
+// https://docs.joinmastodon.org/methods/streaming/#direct
+// Cryap has no conversations, so the stream stays empty
+pub async fn http_get_direct(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    sse_response("direct", None, query.access_token, &headers, state.0).await
+}
+
 #[derive(Deserialize)]
 pub struct WebSocketQuery {
     access_token: Option<String>,
     stream: Option<String>,
+    tag: Option<String>,
 }
 
 // https://docs.joinmastodon.org/methods/streaming/#websocket
+// Without an access token, only public streams can be subscribed to, and only when the
+// `allow_unauthenticated_streaming` setting is enabled
 pub async fn http_get_websocket(
     ws: WebSocketUpgrade,
     state: State<Arc<AppState>>,
+    headers: HeaderMap,
     Query(query): Query<WebSocketQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    let session = match query.access_token {
-        Some(token) => match Session::by_token(&token, &state.db_pool).await? {
-            Some(session) => session,
-            None => {
-                return Ok(StatusCode::UNAUTHORIZED.into_response());
-            },
-        },
-        None => {
-            return Ok(StatusCode::UNAUTHORIZED.into_response());
-        },
+    let token = access_token(&headers, query.access_token);
+    let session =
+        match streaming_session(token, &["read:statuses", "read:notifications"], &state).await? {
+            Ok(session) => session,
+            Err(error) => return Ok(error.into_response()),
+        };
+    // The protocol has to be echoed back, or browsers drop the connection
+    let ws = match websocket_protocol(&headers) {
+        Some(protocol) => ws.protocols([protocol.to_string()]),
+        None => ws,
     };
 
-    Ok(ws.on_upgrade(move |socket| handle_websocket(socket, session, query.stream, state)))
+    Ok(ws.on_upgrade(move |socket| {
+        handle_websocket(socket, session, query.stream, query.tag, state)
+    }))
 }
 
 #[derive(Serialize)]
@@ -89,40 +458,112 @@ struct WebSocketMessage {
     #[serde(rename = "type")]
     message_type: String,
     stream: String,
+    tag: Option<String>,
+}
+
+/// What a WebSocket connection is subscribed to. Every public stream has its own task that
+/// forwards its events to the connection
+#[derive(Default)]
+struct Subscriptions {
+    categories: HashSet<StreamingCategory>,
+    topics: HashMap<StreamingTopic, JoinHandle<()>>,
+}
+
+fn websocket_message<T: Serialize>(message: &T) -> Message {
+    Message::Text(serde_json::to_string(message).unwrap()) // Panic safety: I hope it doesn't break
+}
+
+fn forward_topic(
+    topic: StreamingTopic,
+    user_id: Option<DbId>,
+    sender: mpsc::Sender<Message>,
+    state: Arc<AppState>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut receiver = EVENT_BUS.get_topic_receiver(&topic).await;
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            let (event, payload) = match render_payload(event, user_id.as_ref(), &state).await {
+                Ok(Some(rendered)) => rendered,
+                Ok(None) => continue,
+                Err(error) => {
+                    log::error!("Error from route, {:#?}", error);
+                    continue;
+                },
+            };
+            let message = websocket_message(&WebSocketEvent {
+                stream: topic.stream(),
+                event,
+                payload,
+            });
+            if sender.send(message).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Subscribes or unsubscribes the connection, the error is sent to the client. The connection
+/// only needs one of the scopes to open, so every stream's own scope is checked here
+async fn update_subscriptions(
+    subscribe: bool,
+    stream: &str,
+    tag: Option<&str>,
+    subscriptions: &Mutex<Subscriptions>,
+    session: Option<&Session>,
+    sender: &mpsc::Sender<Message>,
+    state: &Arc<AppState>,
+) -> Result<(), ApiError> {
+    if subscribe
+        && session.is_some_and(|session| !scopes::allows(&session.scopes, required_scope(stream)))
+    {
+        return Err(outside_scopes());
+    }
+    let source = stream_source(stream, tag, session)?;
+    let mut subscriptions = subscriptions.lock().await;
+
+    match source {
+        StreamSource::Topic(topic) if subscribe => {
+            if !subscriptions.topics.contains_key(&topic) {
+                let task = forward_topic(
+                    topic.clone(),
+                    session.map(|session| session.user_id.clone()),
+                    sender.clone(),
+                    Arc::clone(state),
+                );
+                subscriptions.topics.insert(topic, task);
+            }
+        },
+        StreamSource::Topic(topic) => {
+            if let Some(task) = subscriptions.topics.remove(&topic) {
+                task.abort();
+            }
+        },
+        StreamSource::Account(category) if subscribe => {
+            subscriptions.categories.insert(category);
+        },
+        StreamSource::Account(category) => {
+            subscriptions.categories.remove(&category);
+        },
+    }
+
+    Ok(())
 }
 
 async fn handle_websocket(
     socket: WebSocket,
-    session: Session,
+    session: Option<Session>,
     stream: Option<String>,
+    tag: Option<String>,
     state: State<Arc<AppState>>,
 ) {
-    let mut event_receiver = EVENT_BUS.get_receiver(&session.user_id).await;
+    let state = state.0;
     let (mut split_sink, mut split_stream) = socket.split();
 
-    let socket_categories = Arc::new(Mutex::new(match stream {
-        Some(stream) => match StreamingCategory::by_name(&stream) {
-            Some(category) => HashSet::from([category]),
-            None => {
-                if split_sink
-                    .send(Message::Text(
-                        serde_json::to_string(&ApiError::new_without_status_code(
-                            "Unknown stream type",
-                        ))
-                        .unwrap(), // Panic safety: hardcoded object
-                    ))
-                    .await
-                    .is_err()
-                {
-                    return;
-                }
-
-                HashSet::new()
-            },
-        },
-        None => HashSet::new(),
-    }));
-
     let (sender, mut receiver) = mpsc::channel::<Message>(16);
     let mut send_task = tokio::spawn(async move {
         while let Some(message) = receiver.recv().await {
@@ -132,83 +573,106 @@ async fn handle_websocket(
         }
     });
 
+    let subscriptions = Arc::new(Mutex::new(Subscriptions::default()));
+    if let Some(stream) = stream {
+        if let Err(error) = update_subscriptions(
+            true,
+            &stream,
+            tag.as_deref(),
+            &subscriptions,
+            session.as_ref(),
+            &sender,
+            &state,
+        )
+        .await
+        {
+            let _ = sender.send(websocket_message(&error)).await;
+        }
+    }
+
     let stream_task_sender = sender.clone();
-    let stream_task_socket_categories = socket_categories.clone();
+    let stream_task_subscriptions = subscriptions.clone();
+    let stream_task_session = session.clone();
+    let stream_task_state = Arc::clone(&state);
     let mut stream_task = tokio::spawn(async move {
-        while let Ok(event) = event_receiver.recv().await {
-            let socket_categories = stream_task_socket_categories.lock().await;
-            match event {
-                StreamingEvent::Notification {
-                    payload,
-                    categories,
-                } if categories
-                    .iter()
-                    .any(|category| socket_categories.contains(category)) =>
-                {
-                    if stream_task_sender
-                        .send(Message::Text(
-                            serde_json::to_string(&WebSocketEvent {
-                                stream: categories
-                                    .into_iter()
-                                    .filter(|category| socket_categories.contains(category))
-                                    .map(|category| category.name())
-                                    .collect(),
-                                event: String::from("notification"),
-                                payload: serde_json::to_string(
-                                    match &Notification::build(payload, &state).await {
-                                        Ok(notification) => notification,
-                                        Err(error) => {
-                                            log::error!("Error from route, {:#?}", error);
-                                            return;
-                                        },
-                                    },
-                                )
-                                .unwrap(), // Panic safety: I hope it doesn't break
-                            })
-                            .unwrap(), // Panic safety: I hope it doesn't break
-                        ))
-                        .await
-                        .is_err()
-                    {
-                        return;
-                    }
+        // Anonymous connections have no channel of their own
+        let session = match stream_task_session {
+            Some(session) => session,
+            None => return std::future::pending().await,
+        };
+        let mut event_receiver = EVENT_BUS.get_receiver(&session.user_id).await;
+        loop {
+            let event = match event_receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            if let StreamingEvent::SessionsRevoked { payload, .. } = &event {
+                if payload.contains(&session.id) {
+                    let _ = stream_task_sender.send(Message::Close(None)).await;
+                    return;
+                }
+            }
+
+            let socket_categories = stream_task_subscriptions.lock().await.categories.clone();
+            let rendered = match render_event(
+                event,
+                &session.user_id,
+                &socket_categories,
+                &stream_task_state,
+            )
+            .await
+            {
+                Ok(Some(rendered)) => rendered,
+                Ok(None) => continue,
+                Err(error) => {
+                    log::error!("Error from route, {:#?}", error);
+                    continue;
                 },
-                _ => {},
             };
+            let message = websocket_message(&WebSocketEvent {
+                stream: rendered.stream,
+                event: rendered.event,
+                payload: rendered.payload,
+            });
+            if stream_task_sender.send(message).await.is_err() {
+                return;
+            }
         }
     });
 
     let receive_task_sender = sender.clone();
-    let receive_task_socket_categories = socket_categories.clone();
+    let receive_task_subscriptions = subscriptions.clone();
     let mut receive_task = tokio::spawn(async move {
         while let Some(Ok(message)) = split_stream.next().await {
             match message {
                 Message::Text(text) => {
-                    if let Ok(payload) = serde_json::from_str::<WebSocketMessage>(&text) {
-                        if payload.message_type == "subscribe"
-                            || payload.message_type == "unsubscribe"
+                    let payload = match serde_json::from_str::<WebSocketMessage>(&text) {
+                        Ok(payload) => payload,
+                        Err(_) => continue,
+                    };
+                    let subscribe = match payload.message_type.as_str() {
+                        "subscribe" => true,
+                        "unsubscribe" => false,
+                        _ => continue,
+                    };
+                    if let Err(error) = update_subscriptions(
+                        subscribe,
+                        &payload.stream,
+                        payload.tag.as_deref(),
+                        &receive_task_subscriptions,
+                        session.as_ref(),
+                        &receive_task_sender,
+                        &state,
+                    )
+                    .await
+                    {
+                        if receive_task_sender
+                            .send(websocket_message(&error))
+                            .await
+                            .is_err()
                         {
-                            if let Some(category) = StreamingCategory::by_name(&payload.stream) {
-                                let mut socket_categories =
-                                    receive_task_socket_categories.lock().await;
-                                match payload.message_type.as_str() {
-                                    "subscribe" => (*socket_categories).insert(category),
-                                    "unsubscribe" => (*socket_categories).remove(&category),
-                                    _ => false, // Crutch: insert and remove return bool type so this is necessary for this line to meet the requirement.
-                                                // Match return value is then ignored
-                                };
-                            } else if receive_task_sender
-                                .send(Message::Text(
-                                    serde_json::to_string(&ApiError::new_without_status_code(
-                                        "Unknown stream type",
-                                    ))
-                                    .unwrap(), // Panic safety: hardcoded object
-                                ))
-                                .await
-                                .is_err()
-                            {
-                                return;
-                            }
+                            return;
                         }
                     }
                 },
@@ -233,15 +697,66 @@ async fn handle_websocket(
             stream_task.abort();
         }
     }
+    for (_, task) in subscriptions.lock().await.topics.drain() {
+        task.abort();
+    }
 }
 
-pub fn streaming(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+pub fn streaming() -> Router<Arc<AppState>> {
     Router::new()
         .route("/api/v1/streaming/health", get(http_get_health))
+        .route("/api/v1/streaming/user", get(http_get_user))
         .route(
             "/api/v1/streaming/user/notification",
-            get(http_get_user_notification
-                .layer(from_fn_with_state(Arc::clone(state), auth_middleware))),
+            get(http_get_user_notification),
+        )
+        .route("/api/v1/streaming/public", get(http_get_public))
+        .route("/api/v1/streaming/public/local", get(http_get_public_local))
+        .route(
+            "/api/v1/streaming/public/remote",
+            get(http_get_public_remote),
+        )
+        .route("/api/v1/streaming/hashtag", get(http_get_hashtag))
+        .route(
+            "/api/v1/streaming/hashtag/local",
+            get(http_get_hashtag_local),
         )
+        .route("/api/v1/streaming/list", get(http_get_list))
+        .route("/api/v1/streaming/direct", get(http_get_direct))
         .route("/api/v1/streaming", get(http_get_websocket))
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{
+        header::{AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL},
+        HeaderMap, HeaderValue,
+    };
+
+    use super::{access_token, required_scope};
+
+    #[test]
+    fn access_token_sources() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            access_token(&headers, Some(String::from("query"))).as_deref(),
+            Some("query")
+        );
+
+        headers.insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static("protocol"));
+        assert_eq!(
+            access_token(&headers, Some(String::from("query"))).as_deref(),
+            Some("protocol")
+        );
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer header"));
+        assert_eq!(access_token(&headers, None).as_deref(), Some("header"));
+    }
+
+    #[test]
+    fn stream_scopes() {
+        assert_eq!(required_scope("user"), "read:statuses");
+        assert_eq!(required_scope("public:local"), "read:statuses");
+        assert_eq!(required_scope("user:notification"), "read:notifications");
+    }
+}
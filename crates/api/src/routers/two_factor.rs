@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    handler::Handler,
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Extension, Form, Json, Router,
+};
+use db::models::{user::UserUpdate, Session};
+use serde::Deserialize;
+use serde_json::json;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    auth_middleware::{auth_middleware, scope},
+    common::{two_factor, users},
+    error::ApiError,
+};
+
+#[derive(Deserialize)]
+pub struct ConfirmTotpBody {
+    password: String,
+    code: String,
+}
+
+#[derive(Deserialize)]
+pub struct DisableTotpBody {
+    password: String,
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#get-apipleromaaccountsmfa
+pub async fn http_get_settings(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    Ok(Json(json!({
+        "settings": {
+            "enabled": user.otp_enabled,
+            "totp": user.otp_enabled,
+        }
+    }))
+    .into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#get-apipleromaaccountsmfasetuptotp
+// Cryap extension: `qr_code` attribute with a PNG data URI of `provisioning_uri`
+pub async fn http_get_setup_totp(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+    if user.otp_enabled {
+        return Ok(ApiError::new(
+            "Two-factor authentication is already enabled",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    // The secret is kept until it's confirmed, `otp_enabled` stays off
+    let secret = two_factor::generate_secret();
+    user.update(
+        UserUpdate {
+            otp_secret: Some(Some(secret.clone())),
+            ..Default::default()
+        },
+        &state.db_pool,
+    )
+    .await?;
+    let provisioning = two_factor::provisioning(&secret, &user, &state)?;
+
+    Ok(Json(json!({
+        "key": provisioning.key,
+        "provisioning_uri": provisioning.provisioning_uri,
+        "qr_code": provisioning.qr_code,
+    }))
+    .into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#post-apipleromaaccountsmfaconfirmtotp
+// Cryap extension: responds with the recovery codes
+pub async fn http_post_confirm_totp(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Form(body): Form<ConfirmTotpBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    if !users::verify_password(&user, &body.password) {
+        return Ok(ApiError::new("Invalid password", StatusCode::FORBIDDEN).into_response());
+    }
+    let secret = match &user.otp_secret {
+        Some(secret) if !user.otp_enabled => secret.clone(),
+        _ => {
+            return Ok(ApiError::new(
+                "Two-factor authentication hasn't been set up",
+                StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        },
+    };
+    if !two_factor::check_totp(&secret, &body.code, &user, &state).await? {
+        return Ok(ApiError::new("Invalid code", StatusCode::UNPROCESSABLE_ENTITY).into_response());
+    }
+
+    let codes = two_factor::enable(&user, secret, &state).await?;
+
+    Ok(Json(json!({ "codes": codes })).into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#delete-apipleromaaccountsmfatotp
+pub async fn http_delete_totp(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+    Form(body): Form<DisableTotpBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+
+    if !users::verify_password(&user, &body.password) {
+        return Ok(ApiError::new("Invalid password", StatusCode::FORBIDDEN).into_response());
+    }
+    if user.admin && state.config.instance.require_two_factor_for_admins {
+        return Ok(ApiError::new(
+            "Two-factor authentication is required for your account",
+            StatusCode::FORBIDDEN,
+        )
+        .into_response());
+    }
+
+    two_factor::disable(&user, &state).await?;
+
+    Ok(Json(json!({})).into_response())
+}
+
+// https://docs.pleroma.social/backend/development/API/pleroma_api/#get-apipleromaaccountsmfabackup_codes
+pub async fn http_get_backup_codes(
+    state: State<Arc<AppState>>,
+    Extension(session): Extension<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = session.user(&state.db_pool).await?;
+    if !user.otp_enabled {
+        return Ok(ApiError::new(
+            "Two-factor authentication isn't enabled",
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    let codes = two_factor::generate_recovery_codes(&user, &state).await?;
+
+    Ok(Json(json!({ "codes": codes })).into_response())
+}
+
+pub fn two_factor(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/pleroma/accounts/mfa",
+            get(http_get_settings.layer(from_fn_with_state(
+                scope(state, "read:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/pleroma/accounts/mfa/setup/totp",
+            get(http_get_setup_totp.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/pleroma/accounts/mfa/confirm/totp",
+            post(http_post_confirm_totp.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/pleroma/accounts/mfa/totp",
+            delete(http_delete_totp.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+        .route(
+            "/api/pleroma/accounts/mfa/backup_codes",
+            get(http_get_backup_codes.layer(from_fn_with_state(
+                scope(state, "write:accounts"),
+                auth_middleware,
+            ))),
+        )
+}
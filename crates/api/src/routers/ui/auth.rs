@@ -1,19 +1,25 @@
 use std::sync::Arc;
 
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use activitypub_federation::config::Data;
 use axum::{
     extract::{Query, State},
-    response::{Html, IntoResponse, Redirect},
+    response::{Html, IntoResponse, Redirect, Response},
     routing::{get, post},
     Form, Router,
 };
-use axum_extra::extract::cookie::{Cookie, CookieJar};
-use db::models::{Session, User};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use db::models::{Session, SignInChallenge, User};
 use serde::Deserialize;
 use tera::Context;
-use web::{errors::AppError, AppState};
+use web::{config::RegistrationMode, errors::AppError, AppState};
 
-use crate::TEMPLATES;
+use crate::{
+    common::{
+        two_factor,
+        users::{self, SignUpAccess},
+    },
+    scopes, TEMPLATES,
+};
 
 #[derive(Deserialize)]
 pub struct SignInQuery {
@@ -39,6 +45,10 @@ pub async fn http_get_sign_in(
     let mut context = Context::new();
     context.insert("title", &state.config.instance.title);
     context.insert("redirect_url", &query.redirect_url);
+    context.insert(
+        "registrations",
+        &(state.config.instance.registrations != RegistrationMode::Closed),
+    );
     Ok(Html(TEMPLATES.render("sign_in.html", &context)?).into_response())
 }
 
@@ -69,37 +79,292 @@ pub async fn http_post_sign_in(
     }
 
     let user = User::local_by_name(&body.username, &state.db_pool).await?;
-    let mut context = Context::new();
+    let mut context = sign_in_context(&state, &redirect_url);
 
     if let Some(user) = user {
-        let hash = user.password_encrypted.unwrap();
-        let parsed_hash = PasswordHash::new(&hash).unwrap();
-        if Argon2::default()
-            .verify_password(body.password.as_bytes(), &parsed_hash)
-            .is_ok()
-        {
-            let session = Session::create(user.id, None, &state.db_pool).await?;
-
-            return Ok((
-                jar.add(
-                    Cookie::build("token", session.token)
-                        .path("/")
-                        .secure(true)
-                        .finish(),
-                ),
-                Redirect::to(&redirect_url),
-            )
-                .into_response());
-        } else {
+        // Deleted accounts have no password
+        if !users::verify_password(&user, &body.password) {
             context.insert("invalid", &true);
+        } else if !user.approved {
+            context.insert("pending", &true);
+        } else if user.otp_enabled || two_factor::is_required(&user, &state) {
+            // The password is right, the session is only created after the second step
+            let otp_secret = if user.otp_enabled {
+                None
+            } else {
+                Some(two_factor::generate_secret())
+            };
+            let challenge =
+                SignInChallenge::create(user.id.clone(), otp_secret, &mut state.redis.clone())
+                    .await?;
+            let context = two_factor_context(&challenge, &user, &redirect_url, &state)?;
+            return Ok(Html(TEMPLATES.render("sign_in.html", &context)?).into_response());
+        } else {
+            let session = Session::create(
+                user.id,
+                None,
+                scopes::WEB_SCOPES.to_string(),
+                None,
+                false,
+                &state.db_pool,
+            )
+            .await?;
+            return Ok(signed_in(jar, session, &redirect_url));
         }
     } else {
         context.insert("invalid", &true);
     }
 
+    Ok(Html(TEMPLATES.render("sign_in.html", &context)?).into_response())
+}
+
+fn sign_in_context(state: &AppState, redirect_url: &str) -> Context {
+    let mut context = Context::new();
+    context.insert("title", &state.config.instance.title);
+    context.insert("redirect_url", redirect_url);
+    context.insert(
+        "registrations",
+        &(state.config.instance.registrations != RegistrationMode::Closed),
+    );
+    context
+}
+
+fn with_session_cookie(jar: CookieJar, session: Session) -> CookieJar {
+    jar.add(
+        Cookie::build("token", session.token)
+            .path("/")
+            .secure(true)
+            // The settings pages change things with plain forms
+            .same_site(SameSite::Lax)
+            .finish(),
+    )
+}
+
+/// Sets the session cookie and goes on to where the user was heading
+fn signed_in(jar: CookieJar, session: Session, redirect_url: &str) -> Response {
+    (
+        with_session_cookie(jar, session),
+        Redirect::to(redirect_url),
+    )
+        .into_response()
+}
+
+/// Second step of the sign-in, which also shows the QR code when the user has to enrol
+fn two_factor_context(
+    challenge: &SignInChallenge,
+    user: &User,
+    redirect_url: &str,
+    state: &Arc<AppState>,
+) -> anyhow::Result<Context> {
+    let mut context = sign_in_context(state, redirect_url);
+    context.insert("challenge", &challenge.token);
+    if let Some(otp_secret) = &challenge.otp_secret {
+        let provisioning = two_factor::provisioning(otp_secret, user, state)?;
+        context.insert("qr_code", &provisioning.qr_code);
+        context.insert("key", &provisioning.key);
+    }
+    Ok(context)
+}
+
+#[derive(Deserialize)]
+pub struct TwoFactorBody {
+    challenge: String,
+    code: String,
+    redirect_url: Option<String>,
+}
+
+pub async fn http_post_two_factor(
+    state: State<Arc<AppState>>,
+    jar: CookieJar,
+    Form(body): Form<TwoFactorBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let redirect_url = body
+        .redirect_url
+        .and_then(|url| if url.is_empty() { None } else { Some(url) })
+        .unwrap_or(String::from("/"));
+    let mut redis = state.redis.clone();
+
+    let challenge = SignInChallenge::by_token(&body.challenge, &mut redis).await?;
+    let user = match &challenge {
+        Some(challenge) => User::by_id(&challenge.user_id, &state.db_pool).await?,
+        None => None,
+    };
+    let (challenge, user) = match (challenge, user) {
+        (Some(challenge), Some(user)) => (challenge, user),
+        _ => {
+            let mut context = sign_in_context(&state, &redirect_url);
+            context.insert("expired", &true);
+            return Ok(Html(TEMPLATES.render("sign_in.html", &context)?).into_response());
+        },
+    };
+
+    let verified = match &challenge.otp_secret {
+        Some(otp_secret) => two_factor::check_totp(otp_secret, &body.code, &user, &state).await?,
+        None => two_factor::verify(&user, &body.code, &state).await?,
+    };
+
+    if !verified {
+        if !challenge.fail(&mut redis).await? {
+            let mut context = sign_in_context(&state, &redirect_url);
+            context.insert("expired", &true);
+            return Ok(Html(TEMPLATES.render("sign_in.html", &context)?).into_response());
+        }
+        let mut context = two_factor_context(&challenge, &user, &redirect_url, &state)?;
+        context.insert("invalid", &true);
+        return Ok(Html(TEMPLATES.render("sign_in.html", &context)?).into_response());
+    }
+
+    challenge.delete(&mut redis).await?;
+    let session = Session::create(
+        user.id.clone(),
+        None,
+        scopes::WEB_SCOPES.to_string(),
+        None,
+        false,
+        &state.db_pool,
+    )
+    .await?;
+
+    match challenge.otp_secret {
+        Some(otp_secret) => {
+            let recovery_codes = two_factor::enable(&user, otp_secret, &state).await?;
+            let mut context = sign_in_context(&state, &redirect_url);
+            context.insert("recovery_codes", &recovery_codes);
+            Ok((
+                with_session_cookie(jar, session),
+                Html(TEMPLATES.render("sign_in.html", &context)?),
+            )
+                .into_response())
+        },
+        None => Ok(signed_in(jar, session, &redirect_url)),
+    }
+}
+
+fn sign_up_context(state: &AppState) -> Context {
+    let mut context = Context::new();
     context.insert("title", &state.config.instance.title);
+    context.insert(
+        "registrations",
+        &(state.config.instance.registrations != RegistrationMode::Closed),
+    );
+    context.insert(
+        "approval_required",
+        &(state.config.instance.registrations == RegistrationMode::ApprovalRequired),
+    );
+    context.insert("rules", &state.config.instance.rules);
+    context
+}
+
+#[derive(Deserialize)]
+pub struct SignUpQuery {
+    redirect_url: Option<String>,
+    invite_code: Option<String>,
+}
+
+pub async fn http_get_sign_up(
+    state: State<Arc<AppState>>,
+    Query(query): Query<SignUpQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut context = sign_up_context(&state);
+    context.insert("redirect_url", &query.redirect_url.unwrap_or_default());
+    context.insert("invite_code", &query.invite_code.unwrap_or_default());
+    context.insert("username", "");
+    context.insert("reason", "");
+    Ok(Html(TEMPLATES.render("sign_up.html", &context)?).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct SignUpBody {
+    username: String,
+    password: String,
+    reason: Option<String>,
+    #[serde(default)]
+    agreement: bool,
+    redirect_url: Option<String>,
+    invite_code: Option<String>,
+}
+
+pub async fn http_post_sign_up(
+    state: Data<Arc<AppState>>,
+    jar: CookieJar,
+    Form(body): Form<SignUpBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let redirect_url = body
+        .redirect_url
+        .and_then(|url| if url.is_empty() { None } else { Some(url) })
+        .unwrap_or(String::from("/"));
+    let mut context = sign_up_context(&state);
     context.insert("redirect_url", &redirect_url);
-    Ok(Html(TEMPLATES.render("sign_in.html", &context)?).into_response())
+    context.insert("invite_code", &body.invite_code.clone().unwrap_or_default());
+    context.insert("username", &body.username);
+    context.insert("reason", &body.reason.clone().unwrap_or_default());
+
+    let (approved, invite) =
+        match users::sign_up_access(body.invite_code.as_deref(), &state).await? {
+            SignUpAccess::Allowed { approved, invite } => (approved, invite),
+            SignUpAccess::Closed => {
+                return Ok(Html(TEMPLATES.render("sign_up.html", &context)?).into_response());
+            },
+            SignUpAccess::InvalidInvite => {
+                context.insert("error", "Invite is invalid or has expired");
+                return Ok(Html(TEMPLATES.render("sign_up.html", &context)?).into_response());
+            },
+        };
+
+    let reason = body
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+    if let Some(error) = users::validate_registration(
+        &body.username,
+        &body.password,
+        body.agreement,
+        reason.as_deref(),
+        &state,
+    )
+    .await?
+    {
+        context.insert("error", &error);
+        return Ok(Html(TEMPLATES.render("sign_up.html", &context)?).into_response());
+    }
+
+    if let Some(invite) = &invite {
+        if !invite.claim(&state.db_pool).await? {
+            context.insert("error", "Invite is invalid or has expired");
+            return Ok(Html(TEMPLATES.render("sign_up.html", &context)?).into_response());
+        }
+    }
+
+    let user = users::register(
+        body.username,
+        body.password,
+        None,
+        None,
+        approved,
+        reason,
+        &state,
+    )
+    .await?
+    .0;
+    if let Some(invite) = &invite {
+        users::accept_invite(&user, invite, &state).await?;
+    }
+
+    if !approved {
+        context.insert("pending", &true);
+        return Ok(Html(TEMPLATES.render("sign_up.html", &context)?).into_response());
+    }
+
+    let session = Session::create(
+        user.id,
+        None,
+        scopes::WEB_SCOPES.to_string(),
+        None,
+        false,
+        &state.db_pool,
+    )
+    .await?;
+    Ok(signed_in(jar, session, &redirect_url))
 }
 
 #[derive(Deserialize)]
@@ -136,6 +401,9 @@ pub fn auth() -> Router<Arc<AppState>> {
     Router::new()
         .route("/auth/sign_in", get(http_get_sign_in))
         .route("/auth/sign_in", post(http_post_sign_in))
+        .route("/auth/sign_in/two_factor", post(http_post_two_factor))
+        .route("/auth/sign_up", get(http_get_sign_up))
+        .route("/auth/sign_up", post(http_post_sign_up))
         .route(
             "/oauth/authorize/native",
             get(http_get_oauth_authorize_native),
@@ -1,4 +1,5 @@
 mod auth;
+mod settings;
 
 use std::sync::Arc;
 
@@ -6,5 +7,7 @@ use axum::Router;
 use web::AppState;
 
 pub fn ui() -> Router<Arc<AppState>> {
-    Router::new().merge(auth::auth())
+    Router::new()
+        .merge(auth::auth())
+        .merge(settings::settings())
 }
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Router,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar};
+use db::{
+    models::{Application, Session},
+    types::DbId,
+};
+use serde::Deserialize;
+use tera::Context;
+use web::{errors::AppError, AppState};
+
+use crate::{
+    common::sessions, entities::Application as ApiApplication, error::ApiError, TEMPLATES,
+};
+
+/// Session of the sign-in cookie
+async fn cookie_session(jar: &CookieJar, state: &Arc<AppState>) -> anyhow::Result<Option<Session>> {
+    match jar.get("token") {
+        Some(token) => Session::by_token(token.value(), &state.db_pool).await,
+        None => Ok(None),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SettingsForm {
+    #[serde(default)]
+    csrf_token: String,
+}
+
+/// Response to forms that weren't submitted from a settings page of the session
+fn invalid_csrf_token() -> Response {
+    ApiError::new(
+        "Invalid CSRF token, reload the page and try again",
+        StatusCode::FORBIDDEN,
+    )
+    .into_response()
+}
+
+pub async fn http_get_sessions(
+    state: State<Arc<AppState>>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AppError> {
+    let session = match cookie_session(&jar, &state).await? {
+        Some(session) => session,
+        None => {
+            return Ok(
+                Redirect::to("/auth/sign_in?redirect_url=/settings/sessions").into_response(),
+            )
+        },
+    };
+
+    let user = session.user(&state.db_pool).await?;
+    let applications = Application::authorized_by(&user.id, &state.db_pool)
+        .await?
+        .into_iter()
+        .map(|application| ApiApplication::new(application, false))
+        .collect::<Vec<ApiApplication>>();
+
+    let mut context = Context::new();
+    context.insert("title", &state.config.instance.title);
+    context.insert("username", &user.display_name.unwrap_or(user.name));
+    context.insert(
+        "sessions",
+        &sessions::list(&session.user_id, &session.id, &state).await?,
+    );
+    context.insert("applications", &applications);
+    context.insert("csrf_token", &sessions::csrf_token(&session));
+    Ok(Html(TEMPLATES.render("sessions.html", &context)?).into_response())
+}
+
+pub async fn http_post_revoke_session(
+    state: State<Arc<AppState>>,
+    jar: CookieJar,
+    Path(id): Path<String>,
+    Form(body): Form<SettingsForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = match cookie_session(&jar, &state).await? {
+        Some(session) => session,
+        None => return Ok(Redirect::to("/auth/sign_in").into_response()),
+    };
+    if body.csrf_token != sessions::csrf_token(&session) {
+        return Ok(invalid_csrf_token());
+    }
+
+    match Session::by_id(&DbId::from(id), &state.db_pool).await? {
+        Some(revoked) if revoked.user_id == session.user_id => {
+            sessions::revoke(&revoked, &state).await?;
+            if revoked.id == session.id {
+                return Ok((
+                    jar.remove(Cookie::build("token", "").path("/").finish()),
+                    Redirect::to("/auth/sign_in"),
+                )
+                    .into_response());
+            }
+        },
+        _ => {},
+    }
+
+    Ok(Redirect::to("/settings/sessions").into_response())
+}
+
+pub async fn http_post_revoke_application(
+    state: State<Arc<AppState>>,
+    jar: CookieJar,
+    Path(id): Path<String>,
+    Form(body): Form<SettingsForm>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = match cookie_session(&jar, &state).await? {
+        Some(session) => session,
+        None => return Ok(Redirect::to("/auth/sign_in").into_response()),
+    };
+    if body.csrf_token != sessions::csrf_token(&session) {
+        return Ok(invalid_csrf_token());
+    }
+
+    sessions::revoke_application(&session.user_id, &DbId::from(id), &state).await?;
+
+    Ok(Redirect::to("/settings/sessions").into_response())
+}
+
+pub fn settings() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/settings/sessions", get(http_get_sessions))
+        .route(
+            "/settings/sessions/:id/revoke",
+            post(http_post_revoke_session),
+        )
+        .route(
+            "/settings/applications/:id/revoke",
+            post(http_post_revoke_application),
+        )
+}
@@ -0,0 +1,149 @@
+//! OAuth scopes, same as in Mastodon: <https://docs.joinmastodon.org/api/oauth-scopes/>. Scopes
+//! are stored as space-separated strings on applications and tokens
+
+/// Given when an application or an authorization request doesn't ask for any scopes
+pub const DEFAULT_SCOPES: &str = "read";
+/// Granted to sessions of the sign-in page
+pub const WEB_SCOPES: &str = "read write follow push";
+
+pub const TOP_LEVEL_SCOPES: [&str; 6] = [
+    "read",
+    "write",
+    "follow",
+    "push",
+    "admin:read",
+    "admin:write",
+];
+
+pub const GRANULAR_SCOPES: [&str; 38] = [
+    "read:accounts",
+    "read:blocks",
+    "read:bookmarks",
+    "read:favourites",
+    "read:filters",
+    "read:follows",
+    "read:lists",
+    "read:mutes",
+    "read:notifications",
+    "read:search",
+    "read:statuses",
+    "write:accounts",
+    "write:blocks",
+    "write:bookmarks",
+    "write:conversations",
+    "write:favourites",
+    "write:filters",
+    "write:follows",
+    "write:lists",
+    "write:media",
+    "write:mutes",
+    "write:notifications",
+    "write:reports",
+    "write:statuses",
+    "admin:read:accounts",
+    "admin:read:reports",
+    "admin:read:domain_allows",
+    "admin:read:domain_blocks",
+    "admin:read:ip_blocks",
+    "admin:read:email_domain_blocks",
+    "admin:read:canonical_email_blocks",
+    "admin:write:accounts",
+    "admin:write:reports",
+    "admin:write:domain_allows",
+    "admin:write:domain_blocks",
+    "admin:write:ip_blocks",
+    "admin:write:email_domain_blocks",
+    "admin:write:canonical_email_blocks",
+];
+
+/// The deprecated `follow` scope only covers these
+const FOLLOW_SCOPES: [&str; 6] = [
+    "read:blocks",
+    "write:blocks",
+    "read:follows",
+    "write:follows",
+    "read:mutes",
+    "write:mutes",
+];
+
+/// Splits space-separated scopes, leaving out duplicates. `None` if one of them is unknown
+pub fn parse(scopes: &str) -> Option<Vec<String>> {
+    let mut parsed: Vec<String> = vec![];
+    for scope in scopes.split_whitespace() {
+        if !TOP_LEVEL_SCOPES.contains(&scope) && !GRANULAR_SCOPES.contains(&scope) {
+            return None;
+        }
+        if !parsed.iter().any(|parsed| parsed == scope) {
+            parsed.push(scope.to_string());
+        }
+    }
+    Some(parsed)
+}
+
+/// Whether one of the space-separated `granted` scopes covers `required`. A scope covers itself
+/// and the scopes under it, e.g. `read` covers `read:statuses`
+pub fn allows(granted: &str, required: &str) -> bool {
+    granted.split_whitespace().any(|scope| {
+        scope == required
+            || required
+                .strip_prefix(scope)
+                .is_some_and(|rest| rest.starts_with(':'))
+            || (scope == "follow" && FOLLOW_SCOPES.contains(&required))
+    })
+}
+
+/// Whether every one of the space-separated `requested` scopes is covered by `granted`
+pub fn allows_all(granted: &str, requested: &str) -> bool {
+    requested
+        .split_whitespace()
+        .all(|scope| allows(granted, scope))
+}
+
+/// What the scope lets an application do, shown on the authorization page
+pub fn describe(scope: &str) -> String {
+    match scope {
+        "read" => String::from("Read all of your account's data"),
+        "write" => String::from("Modify all of your account's data"),
+        "follow" => String::from("Modify account relationships"),
+        "push" => String::from("Receive your push notifications"),
+        "admin:read" => String::from("Read all data on the server"),
+        "admin:write" => String::from("Modify all data on the server"),
+        _ => {
+            let (access, resource) = scope.rsplit_once(':').unwrap_or(("", scope));
+            let resource = resource.replace('_', " ");
+            match access {
+                "read" => format!("Read your {resource}"),
+                "write" => format!("Modify your {resource}"),
+                "admin:read" => format!("Read {resource} on the server"),
+                "admin:write" => format!("Moderate {resource} on the server"),
+                _ => scope.to_string(),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_scopes() {
+        assert_eq!(
+            parse("read  write:statuses read"),
+            Some(vec![String::from("read"), String::from("write:statuses")])
+        );
+        assert_eq!(parse("read everything"), None);
+    }
+
+    #[test]
+    fn top_level_scopes_cover_granular_ones() {
+        assert!(allows("read", "read:statuses"));
+        assert!(allows("write:statuses read", "write:statuses"));
+        assert!(allows("admin:read", "admin:read:accounts"));
+        assert!(allows("follow", "write:follows"));
+        assert!(!allows("follow", "write:statuses"));
+        assert!(!allows("read", "write:statuses"));
+        assert!(!allows("read:statuses", "read"));
+        assert!(!allows("admin:read", "admin:write:accounts"));
+    }
+}
@@ -0,0 +1,62 @@
+use std::{sync::Arc, time::Duration};
+
+use activitypub_federation::config::Data;
+use ap::common::archive;
+use chrono::Utc;
+use db::models::{Archive, User};
+use web::AppState;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const BATCH_SIZE: i64 = 5;
+/// How long a worker has to generate an archive before another one may take it over
+const CLAIM_DURATION: Duration = Duration::from_secs(30 * 60);
+
+async fn generate(archive: &Archive, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+    let user = match User::by_id(&archive.user_id, &data.db_pool).await? {
+        Some(user) => user,
+        None => return Ok(()),
+    };
+
+    let zip = archive::build(&user, data).await?;
+    archive.finish(zip, &data.db_pool).await?;
+    // Only the newest archive is kept, like in Mastodon
+    archive.delete_older(&data.db_pool).await
+}
+
+async fn process(archive: Archive, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+    // Claiming the row guarantees that only one worker generates it
+    let claimed_until = Utc::now() + chrono::Duration::from_std(CLAIM_DURATION)?;
+    if !archive.claim(claimed_until, &data.db_pool).await? {
+        return Ok(());
+    }
+
+    if let Err(err) = generate(&archive, data).await {
+        log::error!("Failed to generate archive {}: {:?}", archive.id, err);
+        // The failure is shown to the user, who can request a new one right away
+        archive.fail(err.to_string(), &data.db_pool).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn start(data: Arc<Data<Arc<AppState>>>) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let archives = match Archive::pending(BATCH_SIZE, &data.db_pool).await {
+            Ok(archives) => archives,
+            Err(err) => {
+                log::error!("Failed to fetch pending archives: {:?}", err);
+                continue;
+            },
+        };
+
+        for archive in archives {
+            let id = archive.id.clone();
+            if let Err(err) = process(archive, &data).await {
+                log::error!("Failed to process archive {}: {:?}", id, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use activitypub_federation::config::Data;
+use ap::common::delivery::{self, NEW_DELIVERIES};
+use chrono::Utc;
+use db::models::{DeliveryStats, OutgoingDelivery};
+use tokio::sync::{Mutex, Semaphore};
+use web::AppState;
+
+/// Waits between checks for deliveries that are due, new activities wake the worker up earlier
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Deliveries sent at the same time, there's at most one per inbox
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+/// Wait before the first retry, it doubles with every failed attempt
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(60);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(6 * 60 * 60);
+/// How long a worker has to send a delivery before another process may take it over
+const CLAIM_DURATION: Duration = Duration::from_secs(5 * 60);
+/// Deliveries that would still be retried this long after they were queued are given up on
+const MAX_RETRY_WINDOW: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
+fn retry_delay(attempts: i32) -> Duration {
+    FIRST_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.max(0) as u32))
+        .min(MAX_RETRY_DELAY)
+}
+
+async fn process(delivery: OutgoingDelivery, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+    // Other processes see the same head of the inbox, only the one that claims it sends it. The
+    // deliveries after it aren't heads until it's gone, so they still go out in order
+    let claimed_until = Utc::now() + chrono::Duration::from_std(CLAIM_DURATION)?;
+    if !delivery.claim(claimed_until, &data.db_pool).await? {
+        return Ok(());
+    }
+
+    let error = match delivery::deliver(&delivery, data).await {
+        Ok(()) => {
+            delivery.delete(&data.db_pool).await?;
+            return DeliveryStats::record_delivered(&delivery.instance, &data.db_pool).await;
+        },
+        Err(err) => err.to_string(),
+    };
+
+    let next_attempt_at = Utc::now() + chrono::Duration::from_std(retry_delay(delivery.attempts))?;
+    let gave_up = (next_attempt_at - delivery.published)
+        .to_std()
+        .is_ok_and(|queued_for| queued_for > MAX_RETRY_WINDOW);
+    if gave_up {
+        log::warn!(
+            "Giving up on delivering an activity to {}: {}",
+            delivery.inbox,
+            error
+        );
+        delivery.delete(&data.db_pool).await?;
+    } else {
+        delivery
+            .reschedule(next_attempt_at, error.clone(), &data.db_pool)
+            .await?;
+    }
+
+    DeliveryStats::record_failed(&delivery.instance, &error, gave_up, &data.db_pool).await
+}
+
+/// Sends the queued activities, oldest first for every inbox
+pub async fn start(data: Arc<Data<Arc<AppState>>>) {
+    let in_flight = Arc::new(Mutex::new(HashSet::<String>::new()));
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES));
+
+    loop {
+        let deliveries = match OutgoingDelivery::heads(&data.db_pool).await {
+            Ok(deliveries) => deliveries,
+            Err(err) => {
+                log::error!("Failed to fetch queued deliveries: {:?}", err);
+                vec![]
+            },
+        };
+
+        let now = Utc::now();
+        for delivery in deliveries {
+            if delivery.next_attempt_at > now
+                || delivery
+                    .claimed_until
+                    .is_some_and(|claimed_until| claimed_until > now)
+                || !in_flight.lock().await.insert(delivery.inbox.clone())
+            {
+                continue;
+            }
+            let permit = match Arc::clone(&semaphore).acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+
+            let data = Arc::clone(&data);
+            let in_flight = Arc::clone(&in_flight);
+            tokio::spawn(async move {
+                let id = delivery.id.clone();
+                let inbox = delivery.inbox.clone();
+                if let Err(err) = process(delivery, &data).await {
+                    log::error!("Failed to process delivery {}: {:?}", id, err);
+                }
+                in_flight.lock().await.remove(&inbox);
+                drop(permit);
+                // The next activity for the inbox can go now
+                NEW_DELIVERIES.notify_one();
+            });
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {},
+            _ = NEW_DELIVERIES.notified() => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{retry_delay, MAX_RETRY_DELAY};
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_maximum() {
+        assert_eq!(retry_delay(0), Duration::from_secs(60));
+        assert_eq!(retry_delay(3), Duration::from_secs(8 * 60));
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+    }
+}
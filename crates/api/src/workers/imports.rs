@@ -0,0 +1,151 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
+use anyhow::anyhow;
+use ap::{common::follows, objects::note::ApNote};
+use db::{
+    models::{Import, User},
+    types::{DbImportMode, DbImportType},
+};
+use web::AppState;
+
+use crate::common::{posts, users};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const BATCH_SIZE: i64 = 5;
+/// Progress is saved after this many rows
+const PROGRESS_INTERVAL: i32 = 20;
+
+/// `name@instance` in lowercase, local users may be written without the instance
+fn normalize_acct(acct: &str, domain: &str) -> String {
+    let acct = acct.trim().trim_start_matches('@').to_lowercase();
+    if acct.contains('@') {
+        acct
+    } else {
+        format!("{}@{}", acct, domain)
+    }
+}
+
+async fn import_follow(user: &User, acct: &str, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+    let target = users::resolve_acct(acct, data)
+        .await?
+        .ok_or_else(|| anyhow!("Couldn't resolve {}", acct))?;
+    if target.id == user.id
+        || user.follows(&target, &data.db_pool).await?
+        || user.wants_to_follow(&target, &data.db_pool).await?
+    {
+        return Ok(());
+    }
+
+    follows::want_to_follow(user, &target, data).await
+}
+
+async fn import_bookmark(user: &User, url: &str, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+    let post = ObjectId::<ApNote>::parse(url)?.dereference(data).await?.0;
+    if !posts::accessible_for(&post, Some(user), &data.db_pool).await? {
+        return Err(anyhow!("{} is not accessible", url));
+    }
+
+    post.bookmark(user, &data.db_pool).await?;
+    Ok(())
+}
+
+/// Removes everything that isn't in the import, for the overwrite mode
+async fn remove_unlisted(
+    import: &Import,
+    user: &User,
+    rows: &[String],
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    match import.import_type {
+        DbImportType::Following => {
+            let accts = rows
+                .iter()
+                .map(|acct| normalize_acct(acct, &data.config.web.domain))
+                .collect::<HashSet<String>>();
+            for followed in user.all_following(&data.db_pool).await? {
+                let acct = format!("{}@{}", followed.name, followed.instance).to_lowercase();
+                if !accts.contains(&acct) {
+                    follows::unfollow(user, &followed, data).await?;
+                }
+            }
+        },
+        DbImportType::Bookmarks => {
+            let urls = rows.iter().collect::<HashSet<&String>>();
+            for post in user.all_bookmarked_posts(&data.db_pool).await? {
+                if !urls.contains(&post.ap_id) && !urls.contains(&post.url) {
+                    post.unbookmark(user, &data.db_pool).await?;
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+async fn process(import: Import, data: &Data<Arc<AppState>>) -> anyhow::Result<()> {
+    if !import.start(&data.db_pool).await? {
+        return Ok(());
+    }
+
+    let user = match User::by_id(&import.user_id, &data.db_pool).await? {
+        Some(user) => user,
+        None => return Ok(()),
+    };
+    let rows = import.rows();
+
+    // Interrupted imports continue from the saved progress, so this is only done once
+    if import.mode == DbImportMode::Overwrite && import.processed_items == 0 {
+        remove_unlisted(&import, &user, &rows, data).await?;
+    }
+
+    let mut processed_items = import.processed_items;
+    let mut failed_items = import.failed_items;
+    for row in rows.iter().skip(processed_items.try_into()?) {
+        let result = match import.import_type {
+            DbImportType::Following => import_follow(&user, row, data).await,
+            DbImportType::Bookmarks => import_bookmark(&user, row, data).await,
+        };
+        if let Err(err) = result {
+            log::warn!("Failed to import {} for {}: {:?}", row, user.ap_id, err);
+            failed_items += 1;
+        }
+
+        processed_items += 1;
+        if processed_items % PROGRESS_INTERVAL == 0 {
+            import
+                .save_progress(processed_items, failed_items, &data.db_pool)
+                .await?;
+        }
+    }
+
+    import
+        .finish(processed_items, failed_items, &data.db_pool)
+        .await
+}
+
+pub async fn start(data: Arc<Data<Arc<AppState>>>) {
+    if let Err(err) = Import::reschedule_interrupted(&data.db_pool).await {
+        log::error!("Failed to reschedule interrupted imports: {:?}", err);
+    }
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let imports = match Import::scheduled(BATCH_SIZE, &data.db_pool).await {
+            Ok(imports) => imports,
+            Err(err) => {
+                log::error!("Failed to fetch scheduled imports: {:?}", err);
+                continue;
+            },
+        };
+
+        for import in imports {
+            let id = import.id.clone();
+            if let Err(err) = process(import, &data).await {
+                log::error!("Failed to process import {}: {:?}", id, err);
+            }
+        }
+    }
+}
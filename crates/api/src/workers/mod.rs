@@ -0,0 +1,5 @@
+pub mod archives;
+pub mod deliveries;
+pub mod imports;
+pub mod push;
+pub mod scheduled_statuses;
@@ -0,0 +1,234 @@
+use std::{sync::Arc, time::Duration};
+
+use activitypub_federation::config::Data;
+use ap::common::push::PUSH_QUEUE;
+use db::{
+    models::{Notification, PushSubscription, Session, User},
+    types::DbNotificationType,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::json;
+use url::Url;
+use web::AppState;
+
+use crate::{
+    common::push::{self, PushOutcome},
+    entities::Account,
+};
+
+/// Waits before each new attempt when the push service is unavailable
+const RETRY_DELAYS: [Duration; 4] = [
+    Duration::from_secs(30),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(2 * 60 * 60),
+];
+/// Length of the body of a message, as in Mastodon
+const BODY_LENGTH: usize = 140;
+
+lazy_static! {
+    static ref TAG_REGEX: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+fn plain_text(html: &str) -> String {
+    let text = html
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</p><p>", "\n\n");
+    let text = html_escape::decode_html_entities(&TAG_REGEX.replace_all(&text, "")).to_string();
+    match text.char_indices().nth(BODY_LENGTH) {
+        Some((index, _)) => format!("{}…", text[..index].trim_end()),
+        None => text,
+    }
+}
+
+/// Whether the notification passes the `policy` of the subscription
+async fn policy_allows(
+    policy: &str,
+    actor: &User,
+    receiver: &User,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<bool> {
+    Ok(match policy {
+        "all" => true,
+        "followed" => receiver.follows(actor, &data.db_pool).await?,
+        "follower" => actor.follows(receiver, &data.db_pool).await?,
+        _ => false,
+    })
+}
+
+/// Message in the format Mastodon sends, so that clients can show it without another request
+async fn payload(
+    notification: &Notification,
+    notification_type: &str,
+    actor: &User,
+    session: &Session,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<Vec<u8>> {
+    let account = Account::new(actor.clone(), false);
+    let name = account.display_name.clone();
+    let title = match notification.notification_type {
+        DbNotificationType::Mention => format!("{} mentioned you", name),
+        DbNotificationType::Reblog => format!("{} boosted your post", name),
+        DbNotificationType::Follow => format!("{} followed you", name),
+        DbNotificationType::FollowRequest => format!("{} requested to follow you", name),
+        DbNotificationType::Favourite => format!("{} favourited your post", name),
+        DbNotificationType::Quote => format!("{} quoted your post", name),
+        DbNotificationType::EmojiReaction => format!(
+            "{} reacted with {}",
+            name,
+            notification.reaction.clone().unwrap_or_default()
+        ),
+    };
+    let body = match notification.post(&data.db_pool).await? {
+        Some(post) => plain_text(&post.content),
+        None => format!("@{}", account.acct),
+    };
+
+    Ok(json!({
+        "access_token": session.token,
+        "preferred_locale": "en",
+        "notification_id": notification.id.to_string(),
+        "notification_type": notification_type,
+        "icon": account.avatar,
+        "title": title,
+        "body": body,
+    })
+    .to_string()
+    .into_bytes())
+}
+
+async fn deliver(subscription: PushSubscription, payload: Vec<u8>, data: Arc<Data<Arc<AppState>>>) {
+    let client = match Url::parse(&subscription.endpoint) {
+        Ok(endpoint) => push::client(&endpoint, false).await,
+        Err(err) => Err(err.into()),
+    };
+    let client = match client {
+        Ok(client) => client,
+        Err(err) => {
+            log::warn!(
+                "Can't send messages to push subscription {}: {:?}",
+                subscription.id,
+                err
+            );
+            return;
+        },
+    };
+
+    for attempt in 0..=RETRY_DELAYS.len() {
+        let outcome = push::send(
+            &subscription,
+            &payload,
+            &data.vapid_key,
+            &data.config.web.domain,
+            &client,
+        )
+        .await;
+        match outcome {
+            Ok(PushOutcome::Delivered) => return,
+            Ok(PushOutcome::Gone) => {
+                if let Err(err) = subscription.delete(&data.db_pool).await {
+                    log::error!(
+                        "Failed to delete push subscription {}: {:?}",
+                        subscription.id,
+                        err
+                    );
+                }
+                return;
+            },
+            Ok(PushOutcome::Failed) => {
+                log::warn!(
+                    "Push service rejected a message for subscription {}",
+                    subscription.id
+                );
+                return;
+            },
+            Ok(PushOutcome::Retry) => {},
+            Err(err) => {
+                log::error!(
+                    "Failed to send a message to push subscription {}: {:?}",
+                    subscription.id,
+                    err
+                );
+                return;
+            },
+        }
+        match RETRY_DELAYS.get(attempt) {
+            Some(delay) => tokio::time::sleep(*delay).await,
+            None => log::warn!(
+                "Giving up on a message for push subscription {}",
+                subscription.id
+            ),
+        }
+    }
+}
+
+async fn process(
+    notification: Notification,
+    data: &Arc<Data<Arc<AppState>>>,
+) -> anyhow::Result<()> {
+    let notification_type = serde_json::to_value(&notification.notification_type)?
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let subscriptions = PushSubscription::get_for_user(&notification.receiver_id, &data.db_pool)
+        .await?
+        .into_iter()
+        .filter(|subscription| {
+            subscription.policy != "none" && subscription.has_alert(&notification_type)
+        })
+        .collect::<Vec<PushSubscription>>();
+    if subscriptions.is_empty() {
+        return Ok(());
+    }
+    let actor = notification.actor(&data.db_pool).await?;
+    let receiver = notification.receiver(&data.db_pool).await?;
+
+    for subscription in subscriptions {
+        if !policy_allows(&subscription.policy, &actor, &receiver, data).await? {
+            continue;
+        }
+        let session = match Session::by_id(&subscription.session_id, &data.db_pool).await? {
+            Some(session) => session,
+            None => continue,
+        };
+
+        let payload = payload(&notification, &notification_type, &actor, &session, data).await?;
+        tokio::spawn(deliver(subscription, payload, Arc::clone(data)));
+    }
+
+    Ok(())
+}
+
+/// Sends new notifications to the push subscriptions of their receivers
+pub async fn start(data: Arc<Data<Arc<AppState>>>) {
+    let mut receiver = match PUSH_QUEUE.take_receiver().await {
+        Some(receiver) => receiver,
+        None => return,
+    };
+    while let Some(notification) = receiver.recv().await {
+        let id = notification.id.clone();
+        if let Err(err) = process(notification, &data).await {
+            log::error!("Failed to push notification {}: {:?}", id, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plain_text, BODY_LENGTH};
+
+    #[test]
+    fn bodies_are_plain_text() {
+        assert_eq!(
+            plain_text("<p>Hi <a href=\"https://cryap.example/u/cat\">@cat</a> &amp; all</p>"),
+            "Hi @cat & all"
+        );
+        assert_eq!(
+            plain_text(&"a".repeat(200)).chars().count(),
+            BODY_LENGTH + 1
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::{sync::Arc, time::Duration};
+
+use activitypub_federation::config::Data;
+use chrono::Utc;
+use db::models::{ScheduledStatus, User};
+use web::AppState;
+
+use crate::common::posts::{self, NewPost};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const BATCH_SIZE: i64 = 50;
+/// How long a worker has to publish a status before another one may take it over
+const CLAIM_DURATION: Duration = Duration::from_secs(5 * 60);
+/// Wait before the first retry, it doubles with every failed attempt
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Statuses that failed this many times are left for the user to reschedule or delete
+const MAX_ATTEMPTS: i32 = 5;
+
+fn retry_delay(attempts: i32) -> Duration {
+    FIRST_RETRY_DELAY.saturating_mul(2u32.saturating_pow(attempts.max(0) as u32))
+}
+
+async fn post(
+    scheduled_status: &ScheduledStatus,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    let user = match User::by_id(&scheduled_status.user_id, &data.db_pool).await? {
+        Some(user) => user,
+        None => return Ok(()),
+    };
+    let options = NewPost::from_scheduled_params(scheduled_status.params()?, &data.db_pool).await?;
+    posts::post(&user, options, data).await?;
+
+    Ok(())
+}
+
+async fn publish(
+    scheduled_status: ScheduledStatus,
+    data: &Data<Arc<AppState>>,
+) -> anyhow::Result<()> {
+    // Claiming the row guarantees that only one worker publishes it, it's kept until the post
+    // is out so that nothing is lost if posting fails
+    let claimed_until = Utc::now() + chrono::Duration::from_std(CLAIM_DURATION)?;
+    if !scheduled_status.claim(claimed_until, &data.db_pool).await? {
+        return Ok(());
+    }
+
+    match post(&scheduled_status, data).await {
+        Ok(()) => {
+            scheduled_status.delete(&data.db_pool).await?;
+        },
+        Err(err) => {
+            log::warn!(
+                "Failed to publish scheduled status {} (attempt {}): {:?}",
+                scheduled_status.id,
+                scheduled_status.attempts + 1,
+                err
+            );
+            let retry_at =
+                Utc::now() + chrono::Duration::from_std(retry_delay(scheduled_status.attempts))?;
+            scheduled_status
+                .fail(err.to_string(), retry_at, &data.db_pool)
+                .await?;
+        },
+    }
+
+    Ok(())
+}
+
+pub async fn start(data: Arc<Data<Arc<AppState>>>) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let scheduled_statuses =
+            match ScheduledStatus::due(BATCH_SIZE, MAX_ATTEMPTS, &data.db_pool).await {
+                Ok(scheduled_statuses) => scheduled_statuses,
+                Err(err) => {
+                    log::error!("Failed to fetch scheduled statuses: {:?}", err);
+                    continue;
+                },
+            };
+
+        for scheduled_status in scheduled_statuses {
+            let id = scheduled_status.id.clone();
+            if let Err(err) = publish(scheduled_status, &data).await {
+                log::error!("Failed to publish scheduled status {}: {:?}", id, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE scheduled_statuses (
+    id char(27) primary key unique,
+    user_id char(27) not null REFERENCES users(id),
+    scheduled_at timestamptz not null,
+    params jsonb not null,
+    published timestamptz not null default now()
+);
+
+CREATE INDEX scheduled_statuses_scheduled_at_idx ON scheduled_statuses (scheduled_at);
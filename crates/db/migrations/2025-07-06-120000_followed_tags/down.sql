@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE followed_tags;
+DROP TABLE post_tags;
+DROP TABLE tags;
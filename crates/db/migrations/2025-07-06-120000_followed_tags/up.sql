@@ -0,0 +1,23 @@
+-- Your SQL goes here
+
+CREATE TABLE tags (
+    id char(27) primary key unique,
+    name varchar(100) not null unique,
+    published timestamptz not null default now()
+);
+
+CREATE TABLE post_tags (
+    post_id char(27) not null REFERENCES posts(id) ON DELETE CASCADE,
+    tag_id char(27) not null REFERENCES tags(id) ON DELETE CASCADE,
+    primary key (post_id, tag_id)
+);
+
+CREATE INDEX post_tags_tag_id_idx ON post_tags (tag_id);
+
+CREATE TABLE followed_tags (
+    id char(27) primary key unique,
+    user_id char(27) not null REFERENCES users(id) ON DELETE CASCADE,
+    tag_id char(27) not null REFERENCES tags(id) ON DELETE CASCADE,
+    published timestamptz not null default now(),
+    unique (user_id, tag_id)
+);
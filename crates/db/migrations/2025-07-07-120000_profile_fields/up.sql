@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE users ADD fields JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE users DROP COLUMN emojis;
+DROP TABLE post_emojis;
+DROP TABLE custom_emojis;
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+
+CREATE TABLE custom_emojis (
+    id char(27) primary key unique,
+    shortcode varchar(100) not null,
+    instance varchar(100) not null,
+    local boolean not null,
+    ap_id varchar(200) not null,
+    image_url varchar(500) not null,
+    media_type varchar(100),
+    category varchar(100),
+    visible_in_picker boolean not null default true,
+    published timestamptz not null default now(),
+    updated timestamptz,
+    unique (shortcode, instance)
+);
+
+CREATE TABLE post_emojis (
+    post_id char(27) not null REFERENCES posts(id) ON DELETE CASCADE,
+    emoji_id char(27) not null REFERENCES custom_emojis(id) ON DELETE CASCADE,
+    primary key (post_id, emoji_id)
+);
+
+ALTER TABLE users ADD emojis JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM notifications WHERE notification_type = 'emoji_reaction';
+ALTER TABLE notifications DROP COLUMN reaction;
+ALTER TYPE notification_type RENAME TO notification_type_old;
+CREATE TYPE notification_type AS ENUM ('mention', 'reblog', 'follow', 'follow_request', 'favourite', 'quote');
+ALTER TABLE notifications ALTER COLUMN notification_type TYPE notification_type USING notification_type::text::notification_type;
+DROP TYPE notification_type_old;
+
+DROP TABLE post_reactions;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+CREATE TABLE post_reactions (
+    id char(27) primary key unique,
+    ap_id varchar(200) unique,
+    post_id char(27) not null REFERENCES posts(id) ON DELETE CASCADE,
+    actor_id char(27) not null REFERENCES users(id) ON DELETE CASCADE,
+    name varchar(200) not null,
+    emoji_id char(27) REFERENCES custom_emojis(id) ON DELETE CASCADE,
+    published timestamptz not null default now(),
+    unique (post_id, actor_id, name)
+);
+
+ALTER TYPE notification_type ADD VALUE 'emoji_reaction';
+ALTER TABLE notifications ADD reaction varchar(200);
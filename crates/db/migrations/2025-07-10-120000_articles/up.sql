@@ -0,0 +1,8 @@
+-- Your SQL goes here
+
+CREATE TABLE articles (
+    post_id char(27) primary key REFERENCES posts(id) ON DELETE CASCADE,
+    title varchar(500) not null,
+    summary text,
+    content text not null
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE users DROP COLUMN moved_to_id;
+ALTER TABLE users DROP COLUMN also_known_as;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+ALTER TABLE users ADD also_known_as JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE users ADD moved_to_id char(27) REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE imports;
+DROP TYPE import_state;
+DROP TYPE import_mode;
+DROP TYPE import_type;
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+CREATE TYPE import_type AS ENUM ('following', 'bookmarks');
+CREATE TYPE import_mode AS ENUM ('merge', 'overwrite');
+CREATE TYPE import_state AS ENUM ('scheduled', 'in_progress', 'finished');
+
+CREATE TABLE imports (
+    id char(27) primary key unique,
+    user_id char(27) not null REFERENCES users(id) ON DELETE CASCADE,
+    import_type import_type not null,
+    mode import_mode not null,
+    state import_state not null default 'scheduled',
+    rows jsonb not null,
+    total_items integer not null,
+    processed_items integer not null default 0,
+    failed_items integer not null default 0,
+    published timestamptz not null default now(),
+    finished_at timestamptz
+);
+
+CREATE INDEX imports_state_idx ON imports (state);
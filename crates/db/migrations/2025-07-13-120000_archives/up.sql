@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE archives (
+    id char(27) primary key unique,
+    user_id char(27) not null REFERENCES users(id) ON DELETE CASCADE,
+    data bytea,
+    published timestamptz not null default now(),
+    finished_at timestamptz
+);
+
+CREATE INDEX archives_user_id_idx ON archives (user_id);
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE users ADD deleted_at TIMESTAMPTZ;
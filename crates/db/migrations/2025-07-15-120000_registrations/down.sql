@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE app_tokens;
+ALTER TABLE users DROP COLUMN registration_reason;
+ALTER TABLE users DROP COLUMN approved;
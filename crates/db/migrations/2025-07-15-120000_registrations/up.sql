@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+ALTER TABLE users ADD approved BOOLEAN NOT NULL DEFAULT true;
+ALTER TABLE users ADD registration_reason TEXT;
+
+CREATE TABLE app_tokens (
+    id char(27) primary key unique,
+    token char(60) not null unique,
+    application_id char(27) not null REFERENCES applications(id) ON DELETE CASCADE,
+    published timestamptz not null default now()
+);
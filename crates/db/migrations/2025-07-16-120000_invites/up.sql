@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+CREATE TABLE invites (
+    id char(27) primary key unique,
+    code varchar(32) not null unique,
+    user_id char(27) REFERENCES users(id) ON DELETE CASCADE,
+    max_uses integer,
+    uses integer not null default 0,
+    expires_at timestamptz,
+    autofollow boolean not null default false,
+    published timestamptz not null default now()
+);
+
+CREATE INDEX invites_user_id_idx ON invites (user_id);
+
+ALTER TABLE users ADD invite_id char(27) REFERENCES invites(id) ON DELETE SET NULL;
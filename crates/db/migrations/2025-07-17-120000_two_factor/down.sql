@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE recovery_codes;
+ALTER TABLE users DROP otp_enabled;
+ALTER TABLE users DROP otp_secret;
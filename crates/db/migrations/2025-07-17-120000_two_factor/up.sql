@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+ALTER TABLE users ADD otp_secret TEXT;
+ALTER TABLE users ADD otp_enabled BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE recovery_codes (
+    id char(27) primary key unique,
+    user_id char(27) not null REFERENCES users(id) ON DELETE CASCADE,
+    code_hash char(64) not null,
+    published timestamptz not null default now()
+);
+
+CREATE INDEX recovery_codes_user_id_idx ON recovery_codes (user_id);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE app_tokens DROP scopes;
+ALTER TABLE sessions DROP scopes;
+ALTER TABLE applications DROP scopes;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+ALTER TABLE applications ADD scopes TEXT NOT NULL DEFAULT 'read';
+ALTER TABLE sessions ADD scopes TEXT NOT NULL DEFAULT 'read';
+ALTER TABLE app_tokens ADD scopes TEXT NOT NULL DEFAULT 'read';
+
+-- Apps and tokens from before scopes existed had full access
+UPDATE applications SET scopes = 'read write follow push';
+UPDATE sessions SET scopes = 'read write follow push';
+UPDATE app_tokens SET scopes = 'read write follow push';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE app_tokens DROP expires_at;
+ALTER TABLE sessions DROP refresh_token;
+ALTER TABLE sessions DROP expires_at;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+ALTER TABLE sessions ADD expires_at TIMESTAMPTZ;
+ALTER TABLE sessions ADD refresh_token CHAR(60) UNIQUE;
+ALTER TABLE app_tokens ADD expires_at TIMESTAMPTZ;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE sessions DROP user_agent;
+ALTER TABLE sessions DROP ip;
+ALTER TABLE sessions DROP last_used_at;
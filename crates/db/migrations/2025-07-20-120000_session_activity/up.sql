@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+ALTER TABLE sessions ADD last_used_at TIMESTAMPTZ;
+ALTER TABLE sessions ADD ip TEXT;
+ALTER TABLE sessions ADD user_agent TEXT;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+CREATE TABLE push_subscriptions (
+    id char(27) primary key unique,
+    session_id char(27) not null unique REFERENCES sessions(id) ON DELETE CASCADE,
+    user_id char(27) not null REFERENCES users(id) ON DELETE CASCADE,
+    endpoint text not null,
+    p256dh text not null,
+    auth text not null,
+    alerts text not null default '',
+    policy varchar(16) not null default 'all',
+    published timestamptz not null default now()
+);
+
+CREATE INDEX push_subscriptions_user_id_idx ON push_subscriptions (user_id);
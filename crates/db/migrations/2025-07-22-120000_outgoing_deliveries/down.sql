@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE delivery_stats;
+DROP TABLE outgoing_deliveries;
@@ -0,0 +1,25 @@
+-- Your SQL goes here
+
+CREATE TABLE outgoing_deliveries (
+    id char(27) primary key unique,
+    actor_id char(27) not null REFERENCES users(id) ON DELETE CASCADE,
+    inbox text not null,
+    instance text not null,
+    activity jsonb not null,
+    attempts integer not null default 0,
+    next_attempt_at timestamptz not null default now(),
+    last_error text,
+    published timestamptz not null default now()
+);
+
+CREATE INDEX outgoing_deliveries_inbox_idx ON outgoing_deliveries (inbox, published, id);
+
+CREATE TABLE delivery_stats (
+    instance text primary key unique,
+    delivered bigint not null default 0,
+    retried bigint not null default 0,
+    failed bigint not null default 0,
+    last_delivered_at timestamptz,
+    last_failed_at timestamptz,
+    last_error text
+);
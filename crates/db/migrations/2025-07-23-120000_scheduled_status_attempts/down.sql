@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE scheduled_statuses
+    DROP COLUMN claimed_until,
+    DROP COLUMN attempts,
+    DROP COLUMN last_error;
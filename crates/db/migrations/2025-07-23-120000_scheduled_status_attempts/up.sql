@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+ALTER TABLE scheduled_statuses
+    ADD COLUMN claimed_until timestamptz,
+    ADD COLUMN attempts integer not null default 0,
+    ADD COLUMN last_error text;
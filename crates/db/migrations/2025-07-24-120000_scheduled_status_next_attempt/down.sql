@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE scheduled_statuses DROP COLUMN next_attempt_at;
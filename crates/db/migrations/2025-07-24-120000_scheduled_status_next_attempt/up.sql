@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE scheduled_statuses ADD COLUMN next_attempt_at timestamptz;
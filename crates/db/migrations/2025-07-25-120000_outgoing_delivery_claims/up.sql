@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE outgoing_deliveries ADD COLUMN claimed_until timestamptz;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE archives
+    DROP COLUMN claimed_until,
+    DROP COLUMN failed_at,
+    DROP COLUMN error;
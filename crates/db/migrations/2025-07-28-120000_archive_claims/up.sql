@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+ALTER TABLE archives
+    ADD COLUMN claimed_until timestamptz,
+    ADD COLUMN failed_at timestamptz,
+    ADD COLUMN error text;
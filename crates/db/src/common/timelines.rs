@@ -318,6 +318,103 @@ pub async fn get_user_posts(
     }
 }
 
+pub async fn get_home_timeline(
+    user_id: &DbId,
+    pagination: Pagination,
+    db_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<Vec<TimelineEntry>> {
+    let query = format!(
+        "
+        SELECT * FROM (
+            SELECT
+                'post' AS post_type,
+                posts.id as id,
+                author,
+                ap_id,
+                local_only,
+                content_warning,
+                content,
+                sensitive,
+                in_reply,
+                published,
+                updated,
+                url,
+                quote,
+                visibility,
+                NULL AS boost_id,
+                NULL AS boost_ap_id,
+                NULL AS boost_actor_id,
+                NULL AS boost_visibility,
+                NULL AS post_published 
+            FROM posts
+            LEFT JOIN post_mention ON posts.id = post_mention.post_id AND post_mention.mentioned_user_id = $1
+            WHERE author = $1
+                OR (
+                    author IN (SELECT follower_id FROM user_followers WHERE actor_id = $1)
+                    AND (posts.visibility != 'direct' OR post_mention.post_id IS NOT NULL)
+                )
+                OR (
+                    posts.visibility = 'public'
+                    AND posts.id IN (
+                        SELECT post_tags.post_id FROM post_tags
+                        JOIN followed_tags ON post_tags.tag_id = followed_tags.tag_id
+                        WHERE followed_tags.user_id = $1
+                    )
+                )
+            UNION ALL
+            SELECT
+                'boost' AS post_type,
+                posts.id AS id,
+                posts.author AS author,
+                posts.ap_id AS ap_id,
+                posts.local_only AS local_only,
+                posts.content_warning AS content_warning,
+                posts.content AS content,
+                posts.sensitive AS sensitive,
+                posts.in_reply AS in_reply,
+                post_boost.published AS published,
+                posts.updated AS updated,
+                posts.url AS url,
+                posts.quote AS quote,
+                posts.visibility AS visibility,
+                post_boost.id AS boost_id,
+                post_boost.ap_id AS boost_ap_id,
+                post_boost.actor_id AS boost_actor_id,
+                post_boost.visibility AS boost_visibility,
+                posts.published AS post_published
+            FROM post_boost
+            JOIN posts ON post_boost.post_id = posts.id
+            WHERE (
+                post_boost.actor_id = $1
+                OR post_boost.actor_id IN (SELECT follower_id FROM user_followers WHERE actor_id = $1)
+            ) AND post_boost.visibility != 'direct'
+        ) results {}
+        ",
+        match pagination {
+            Pagination::MaxId(_, _) => "WHERE id > $2 ORDER BY published DESC LIMIT $3;",
+            Pagination::MinId(_, _) => "WHERE id < $2 ORDER BY published DESC LIMIT $3;",
+            Pagination::None(_) => "ORDER BY published DESC LIMIT $3",
+        }
+    );
+
+    Ok(sql_query(query)
+        .bind::<Bpchar, _>(user_id)
+        .bind::<Varchar, _>(match pagination {
+            Pagination::MaxId(ref id, _) | Pagination::MinId(ref id, _) => id,
+            Pagination::None(_) => "",
+        })
+        .bind::<Integer, _>(match pagination {
+            Pagination::MaxId(_, limit) | Pagination::MinId(_, limit) | Pagination::None(limit) => {
+                limit
+            },
+        })
+        .load::<TimelineResult>(&mut db_pool.get().await?)
+        .await?
+        .into_iter()
+        .map(TimelineEntry::from)
+        .collect())
+}
+
 async fn follows(
     user_id: &DbId,
     actor_id: Option<&DbId>,
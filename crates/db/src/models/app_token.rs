@@ -0,0 +1,72 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use diesel::{insert_into, prelude::*, result::Error::NotFound};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{models::Application, schema::app_tokens, types::DbId, utils::random_string};
+
+/// Token of an application that isn't tied to any user, obtained with the `client_credentials`
+/// grant
+#[derive(Queryable, Identifiable, Insertable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = app_tokens)]
+pub struct AppToken {
+    pub id: DbId,
+    pub token: String,
+    pub application_id: DbId,
+    pub published: DateTime<Utc>,
+    /// Space-separated OAuth scopes granted to the token
+    pub scopes: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl AppToken {
+    pub async fn create(
+        application: &Application,
+        scopes: String,
+        expires_at: Option<DateTime<Utc>>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let app_token = AppToken {
+            id: DbId::default(),
+            token: random_string(60),
+            application_id: application.id.clone(),
+            published: Utc::now(),
+            scopes,
+            expires_at,
+        };
+
+        Ok(insert_into(app_tokens::table)
+            .values(app_token)
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_token(
+        token: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let app_token = app_tokens::table
+            .filter(app_tokens::token.eq(token))
+            .filter(
+                app_tokens::expires_at
+                    .is_null()
+                    .or(app_tokens::expires_at.gt(Utc::now())),
+            )
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match app_token {
+            Ok(app_token) => Ok(Some(app_token)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn application(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Application> {
+        Application::by_id(&self.application_id, db_pool)
+            .await?
+            .ok_or(anyhow!("This wasn't supposed to happen"))
+    }
+}
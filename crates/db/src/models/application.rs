@@ -2,7 +2,11 @@ use chrono::{DateTime, Utc};
 use diesel::{insert_into, prelude::*, result::Error::NotFound};
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
 
-use crate::{schema::applications, types::DbId, utils::random_string};
+use crate::{
+    schema::{applications, sessions},
+    types::DbId,
+    utils::random_string,
+};
 
 #[derive(Queryable, Identifiable, Insertable, Selectable, Debug, PartialEq, Clone, Eq)]
 #[diesel(table_name = applications)]
@@ -14,6 +18,8 @@ pub struct Application {
     pub client_id: String,
     pub client_secret: String,
     pub published: DateTime<Utc>,
+    /// Space-separated OAuth scopes the application may request
+    pub scopes: String,
 }
 
 impl Application {
@@ -21,6 +27,7 @@ impl Application {
         name: String,
         website: Option<String>,
         redirect_url: String,
+        scopes: String,
         db_pool: &Pool<AsyncPgConnection>,
     ) -> anyhow::Result<Self> {
         let application = Application {
@@ -31,6 +38,7 @@ impl Application {
             client_id: random_string(32),
             client_secret: random_string(32),
             published: Utc::now(),
+            scopes,
         };
 
         Ok(insert_into(applications::table)
@@ -54,6 +62,24 @@ impl Application {
         }
     }
 
+    /// Applications the user has given a token to
+    pub async fn authorized_by(
+        user_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(applications::table
+            .filter(
+                applications::id.nullable().eq_any(
+                    sessions::table
+                        .filter(sessions::user_id.eq(user_id))
+                        .select(sessions::application_id),
+                ),
+            )
+            .order(applications::name.asc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
     pub async fn by_client_id(
         client_id: &str,
         db_pool: &Pool<AsyncPgConnection>,
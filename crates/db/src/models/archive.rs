@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use diesel::{delete, insert_into, prelude::*, result::Error::NotFound, update};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{models::User, schema::archives, types::DbId};
+
+/// Account archive requested by a user. The ZIP file itself is only loaded by [`Archive::data`],
+/// it's ready once `finished_at` is set
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = archives)]
+pub struct Archive {
+    pub id: DbId,
+    pub user_id: DbId,
+    pub published: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Set while a worker is generating it, other workers leave it alone until then
+    pub claimed_until: Option<DateTime<Utc>>,
+    /// Set if it couldn't be generated, it isn't tried again
+    pub failed_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+impl Archive {
+    pub async fn create(user: &User, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Self> {
+        let archive = Archive {
+            id: DbId::default(),
+            user_id: user.id.clone(),
+            published: Utc::now(),
+            finished_at: None,
+            claimed_until: None,
+            failed_at: None,
+            error: None,
+        };
+
+        Ok(insert_into(archives::table)
+            .values(archive)
+            .returning(Archive::as_returning())
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_id(
+        id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let archive = archives::table
+            .filter(archives::id.eq(id))
+            .select(Archive::as_select())
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match archive {
+            Ok(archive) => Ok(Some(archive)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Archives of the user, newest first
+    pub async fn get_for_user(
+        user_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(archives::table
+            .filter(archives::user_id.eq(user_id))
+            .select(Archive::as_select())
+            .order(archives::published.desc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Archives that haven't been generated yet, oldest first
+    pub async fn pending(
+        limit: i64,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let now = Utc::now();
+        Ok(archives::table
+            .filter(archives::finished_at.is_null())
+            .filter(archives::failed_at.is_null())
+            .filter(
+                archives::claimed_until
+                    .is_null()
+                    .or(archives::claimed_until.lt(now)),
+            )
+            .select(Archive::as_select())
+            .order(archives::published.asc())
+            .limit(limit)
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Marks the archive as being generated until `claimed_until`, after which another worker
+    /// can take it over if this one crashed. Returns `false` if another worker claimed it first
+    pub async fn claim(
+        &self,
+        claimed_until: DateTime<Utc>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let rows_affected = update(
+            archives::table
+                .filter(archives::id.eq(&self.id))
+                .filter(archives::finished_at.is_null())
+                .filter(
+                    archives::claimed_until
+                        .is_null()
+                        .or(archives::claimed_until.lt(Utc::now())),
+                ),
+        )
+        .set(archives::claimed_until.eq(claimed_until))
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(rows_affected == 1)
+    }
+
+    pub async fn finish(
+        &self,
+        data: Vec<u8>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        update(archives::table.filter(archives::id.eq(&self.id)))
+            .set((
+                archives::data.eq(data),
+                archives::finished_at.eq(Utc::now()),
+                archives::claimed_until.eq(None::<DateTime<Utc>>),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fail(
+        &self,
+        error: String,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        update(archives::table.filter(archives::id.eq(&self.id)))
+            .set((
+                archives::failed_at.eq(Utc::now()),
+                archives::error.eq(error),
+                archives::claimed_until.eq(None::<DateTime<Utc>>),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes the archives the user requested before this one, so that only the newest ZIP
+    /// file is kept
+    pub async fn delete_older(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+        delete(
+            archives::table
+                .filter(archives::user_id.eq(&self.user_id))
+                .filter(archives::published.lt(self.published))
+                .filter(archives::id.ne(&self.id)),
+        )
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn data(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(archives::table
+            .filter(archives::id.eq(&self.id))
+            .select(archives::data)
+            .first::<Option<Vec<u8>>>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn delete(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+        delete(archives::table.filter(archives::id.eq(&self.id)))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+}
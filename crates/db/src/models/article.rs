@@ -0,0 +1,45 @@
+use diesel::{insert_into, prelude::*, result::Error::NotFound};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{schema::articles, types::DbId};
+
+/// Long-form part of a post. The post itself keeps a short fallback in `content` for clients
+/// and servers that don't know about articles
+#[derive(
+    Queryable, Insertable, Identifiable, Selectable, AsChangeset, Debug, PartialEq, Clone, Eq,
+)]
+#[diesel(table_name = articles, primary_key(post_id), treat_none_as_null = true)]
+pub struct Article {
+    pub post_id: DbId,
+    pub title: String,
+    pub summary: Option<String>,
+    /// HTML
+    pub content: String,
+}
+
+impl Article {
+    pub async fn upsert(article: Self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Self> {
+        Ok(insert_into(articles::table)
+            .values(article.clone())
+            .on_conflict(articles::post_id)
+            .do_update()
+            .set(article)
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_post(
+        post_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let article = articles::table
+            .filter(articles::post_id.eq(post_id))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match article {
+            Ok(article) => Ok(Some(article)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
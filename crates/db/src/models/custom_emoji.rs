@@ -0,0 +1,181 @@
+use chrono::{DateTime, Utc};
+use diesel::{delete, insert_into, prelude::*, result::Error::NotFound};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    schema::{custom_emojis, post_emojis},
+    types::DbId,
+};
+
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = custom_emojis)]
+pub struct CustomEmoji {
+    pub id: DbId,
+    /// Without the surrounding colons
+    pub shortcode: String,
+    pub instance: String,
+    pub local: bool,
+    pub ap_id: String,
+    pub image_url: String,
+    pub media_type: Option<String>,
+    pub category: Option<String>,
+    pub visible_in_picker: bool,
+    pub published: DateTime<Utc>,
+    pub updated: Option<DateTime<Utc>>,
+}
+
+#[derive(Queryable, Insertable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = post_emojis)]
+pub struct PostEmoji {
+    pub post_id: DbId,
+    pub emoji_id: DbId,
+}
+
+impl CustomEmoji {
+    pub async fn create(emoji: Self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Self> {
+        Ok(insert_into(custom_emojis::table)
+            .values(emoji)
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Saves an emoji received from another server, updating the image if it's already known
+    pub async fn upsert_remote(
+        emoji: Self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        Ok(insert_into(custom_emojis::table)
+            .values(emoji.clone())
+            .on_conflict((custom_emojis::shortcode, custom_emojis::instance))
+            .do_update()
+            .set((
+                custom_emojis::ap_id.eq(emoji.ap_id),
+                custom_emojis::image_url.eq(emoji.image_url),
+                custom_emojis::media_type.eq(emoji.media_type),
+                custom_emojis::updated.eq(emoji.updated),
+            ))
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_id(
+        id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let emoji = custom_emojis::table
+            .filter(custom_emojis::id.eq(id))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match emoji {
+            Ok(emoji) => Ok(Some(emoji)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn local_by_shortcode(
+        shortcode: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let emoji = custom_emojis::table
+            .filter(custom_emojis::local.eq(true))
+            .filter(custom_emojis::shortcode.eq(shortcode))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match emoji {
+            Ok(emoji) => Ok(Some(emoji)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn local(db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Vec<Self>> {
+        Ok(custom_emojis::table
+            .filter(custom_emojis::local.eq(true))
+            .order(custom_emojis::shortcode.asc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_shortcodes(
+        shortcodes: &[String],
+        instance: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        if shortcodes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(custom_emojis::table
+            .filter(custom_emojis::instance.eq(instance))
+            .filter(custom_emojis::shortcode.eq_any(shortcodes))
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_post(
+        post_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(post_emojis::table
+            .filter(post_emojis::post_id.eq(post_id))
+            .inner_join(custom_emojis::table)
+            .select(custom_emojis::all_columns)
+            .order(custom_emojis::shortcode.asc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Emojis of several posts at once, paired with the ID of the post using them
+    pub async fn by_posts(
+        post_ids: Vec<&DbId>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<(DbId, Self)>> {
+        if post_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(post_emojis::table
+            .filter(post_emojis::post_id.eq_any(post_ids))
+            .inner_join(custom_emojis::table)
+            .select((post_emojis::post_id, custom_emojis::all_columns))
+            .order(custom_emojis::shortcode.asc())
+            .load::<(DbId, Self)>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn attach_to_post(
+        post_id: &DbId,
+        emojis: &[Self],
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        if emojis.is_empty() {
+            return Ok(());
+        }
+
+        insert_into(post_emojis::table)
+            .values(
+                emojis
+                    .iter()
+                    .map(|emoji| PostEmoji {
+                        post_id: post_id.clone(),
+                        emoji_id: emoji.id.clone(),
+                    })
+                    .collect::<Vec<PostEmoji>>(),
+            )
+            .on_conflict((post_emojis::post_id, post_emojis::emoji_id))
+            .do_nothing()
+            .execute(&mut db_pool.get().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns `false` if the emoji has already been deleted
+    pub async fn delete(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<bool> {
+        let deleted = delete(custom_emojis::table.filter(custom_emojis::id.eq(&self.id)))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(deleted > 0)
+    }
+}
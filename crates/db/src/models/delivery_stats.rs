@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use diesel::{insert_into, prelude::*};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::schema::delivery_stats;
+
+/// Outcomes of the deliveries to an instance, since it was first delivered to
+#[derive(Queryable, Identifiable, Insertable, Selectable, Debug, PartialEq, Eq, Clone)]
+#[diesel(table_name = delivery_stats, primary_key(instance))]
+pub struct DeliveryStats {
+    pub instance: String,
+    pub delivered: i64,
+    /// Failed attempts that were retried later
+    pub retried: i64,
+    /// Deliveries given up on
+    pub failed: i64,
+    pub last_delivered_at: Option<DateTime<Utc>>,
+    pub last_failed_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl DeliveryStats {
+    fn empty(instance: &str) -> Self {
+        Self {
+            instance: String::from(instance),
+            delivered: 0,
+            retried: 0,
+            failed: 0,
+            last_delivered_at: None,
+            last_failed_at: None,
+            last_error: None,
+        }
+    }
+
+    pub async fn all(db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Vec<Self>> {
+        Ok(delivery_stats::table
+            .order(delivery_stats::instance.asc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn record_delivered(
+        instance: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        let now = Utc::now();
+        insert_into(delivery_stats::table)
+            .values(Self {
+                delivered: 1,
+                last_delivered_at: Some(now),
+                ..Self::empty(instance)
+            })
+            .on_conflict(delivery_stats::instance)
+            .do_update()
+            .set((
+                delivery_stats::delivered.eq(delivery_stats::delivered + 1),
+                delivery_stats::last_delivered_at.eq(now),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// `gave_up` is whether the delivery won't be attempted again
+    pub async fn record_failed(
+        instance: &str,
+        error: &str,
+        gave_up: bool,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let (retried, failed) = if gave_up { (0, 1) } else { (1, 0) };
+        insert_into(delivery_stats::table)
+            .values(Self {
+                retried,
+                failed,
+                last_failed_at: Some(now),
+                last_error: Some(String::from(error)),
+                ..Self::empty(instance)
+            })
+            .on_conflict(delivery_stats::instance)
+            .do_update()
+            .set((
+                delivery_stats::retried.eq(delivery_stats::retried + retried),
+                delivery_stats::failed.eq(delivery_stats::failed + failed),
+                delivery_stats::last_failed_at.eq(now),
+                delivery_stats::last_error.eq(error),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+}
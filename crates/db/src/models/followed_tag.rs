@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use diesel::{delete, dsl::sql, insert_into, prelude::*, result::Error::NotFound, sql_types::Bool};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    models::{Tag, User},
+    schema::followed_tags,
+    types::DbId,
+};
+
+#[derive(Queryable, Insertable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = followed_tags)]
+pub struct FollowedTag {
+    pub id: DbId,
+    pub user_id: DbId,
+    pub tag_id: DbId,
+    pub published: DateTime<Utc>,
+}
+
+impl FollowedTag {
+    pub async fn create(
+        user: &User,
+        tag: &Tag,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let rows_affected = insert_into(followed_tags::table)
+            .values(vec![FollowedTag {
+                id: DbId::default(),
+                user_id: user.id.clone(),
+                tag_id: tag.id.clone(),
+                published: Utc::now(),
+            }])
+            .on_conflict((followed_tags::user_id, followed_tags::tag_id))
+            .do_nothing()
+            .execute(&mut db_pool.get().await?)
+            .await
+            .optional()?;
+
+        Ok(rows_affected == Some(1))
+    }
+
+    pub async fn delete(
+        user: &User,
+        tag: &Tag,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let rows_affected = delete(
+            followed_tags::table
+                .filter(followed_tags::user_id.eq(&user.id))
+                .filter(followed_tags::tag_id.eq(&tag.id)),
+        )
+        .execute(&mut db_pool.get().await?)
+        .await
+        .optional()?;
+
+        Ok(rows_affected == Some(1))
+    }
+
+    pub async fn exists(
+        user_id: &DbId,
+        tag: &Tag,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let result = followed_tags::table
+            .select(sql::<Bool>("true"))
+            .filter(followed_tags::user_id.eq(user_id))
+            .filter(followed_tags::tag_id.eq(&tag.id))
+            .first::<bool>(&mut db_pool.get().await?)
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(NotFound) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use diesel::{insert_into, prelude::*, result::Error::NotFound, update};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    models::User,
+    paginate,
+    pagination::Pagination,
+    schema::imports,
+    types::{DbId, DbImportMode, DbImportState, DbImportType},
+};
+
+/// CSV import processed in the background. `rows` are the first columns of the file, as a JSON
+/// array of strings
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone)]
+#[diesel(table_name = imports)]
+pub struct Import {
+    pub id: DbId,
+    pub user_id: DbId,
+    pub import_type: DbImportType,
+    pub mode: DbImportMode,
+    pub state: DbImportState,
+    pub rows: serde_json::Value,
+    pub total_items: i32,
+    pub processed_items: i32,
+    pub failed_items: i32,
+    pub published: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Import {
+    pub async fn create(
+        user: &User,
+        import_type: DbImportType,
+        mode: DbImportMode,
+        rows: &[String],
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let import = Import {
+            id: DbId::default(),
+            user_id: user.id.clone(),
+            import_type,
+            mode,
+            state: DbImportState::Scheduled,
+            rows: serde_json::to_value(rows)?,
+            total_items: rows.len().try_into()?,
+            processed_items: 0,
+            failed_items: 0,
+            published: Utc::now(),
+            finished_at: None,
+        };
+
+        Ok(insert_into(imports::table)
+            .values(import)
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_id(
+        id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let import = imports::table
+            .filter(imports::id.eq(id))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match import {
+            Ok(import) => Ok(Some(import)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn get_for_user(
+        user_id: &DbId,
+        pagination: Pagination,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = imports::table
+            .filter(imports::user_id.eq(user_id))
+            .select(imports::all_columns)
+            .order(imports::id.desc())
+            .into_boxed();
+        let query = paginate!(query, imports::id, pagination);
+
+        Ok(query.load::<Self>(&mut db_pool.get().await?).await?)
+    }
+
+    /// Whether the user has an import that hasn't finished yet
+    pub async fn has_unfinished(
+        user_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let count = imports::table
+            .filter(imports::user_id.eq(user_id))
+            .filter(imports::state.ne(DbImportState::Finished))
+            .count()
+            .get_result::<i64>(&mut db_pool.get().await?)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Scheduled imports, oldest first
+    pub async fn scheduled(
+        limit: i64,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(imports::table
+            .filter(imports::state.eq(DbImportState::Scheduled))
+            .order(imports::id.asc())
+            .limit(limit)
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Schedules imports that were interrupted by a restart again. They continue from the last
+    /// saved progress
+    pub async fn reschedule_interrupted(db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+        update(imports::table.filter(imports::state.eq(DbImportState::InProgress)))
+            .set(imports::state.eq(DbImportState::Scheduled))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns `false` if the import has already been started (e.g. by another worker)
+    pub async fn start(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<bool> {
+        let rows_affected = update(
+            imports::table
+                .filter(imports::id.eq(&self.id))
+                .filter(imports::state.eq(DbImportState::Scheduled)),
+        )
+        .set(imports::state.eq(DbImportState::InProgress))
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(rows_affected == 1)
+    }
+
+    pub async fn save_progress(
+        &self,
+        processed_items: i32,
+        failed_items: i32,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        update(imports::table.filter(imports::id.eq(&self.id)))
+            .set((
+                imports::processed_items.eq(processed_items),
+                imports::failed_items.eq(failed_items),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn finish(
+        &self,
+        processed_items: i32,
+        failed_items: i32,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        update(imports::table.filter(imports::id.eq(&self.id)))
+            .set((
+                imports::state.eq(DbImportState::Finished),
+                imports::processed_items.eq(processed_items),
+                imports::failed_items.eq(failed_items),
+                imports::finished_at.eq(Utc::now()),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        serde_json::from_value(self.rows.clone()).unwrap_or_default()
+    }
+}
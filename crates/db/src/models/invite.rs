@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use diesel::{insert_into, prelude::*, result::Error::NotFound, update};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    models::User,
+    schema::{invites, users},
+    types::DbId,
+    utils::random_string,
+};
+
+/// Code that lets someone sign up even when registrations are closed or require approval.
+/// Invites created by admins over RPC have no `user_id`
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = invites)]
+pub struct Invite {
+    pub id: DbId,
+    pub code: String,
+    pub user_id: Option<DbId>,
+    /// Unlimited if `None`
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    /// Never expires if `None`
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether users who sign up with the invite follow its creator
+    pub autofollow: bool,
+    pub published: DateTime<Utc>,
+}
+
+impl Invite {
+    pub async fn create(
+        user_id: Option<DbId>,
+        max_uses: Option<i32>,
+        expires_at: Option<DateTime<Utc>>,
+        autofollow: bool,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let invite = Invite {
+            id: DbId::default(),
+            code: random_string(8),
+            user_id,
+            max_uses,
+            uses: 0,
+            expires_at,
+            autofollow,
+            published: Utc::now(),
+        };
+
+        Ok(insert_into(invites::table)
+            .values(invite)
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_id(
+        id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let invite = invites::table
+            .filter(invites::id.eq(id))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match invite {
+            Ok(invite) => Ok(Some(invite)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn by_code(
+        code: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let invite = invites::table
+            .filter(invites::code.eq(code))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match invite {
+            Ok(invite) => Ok(Some(invite)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Invites created by the user, newest first
+    pub async fn get_for_user(
+        user_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(invites::table
+            .filter(invites::user_id.eq(user_id))
+            .order(invites::published.desc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Every invite on the instance, newest first
+    pub async fn all(db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Vec<Self>> {
+        Ok(invites::table
+            .order(invites::published.desc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.max_uses.map_or(true, |max_uses| self.uses < max_uses)
+            && self
+                .expires_at
+                .map_or(true, |expires_at| expires_at > Utc::now())
+    }
+
+    /// Counts a sign-up with the invite. Returns `false` if the invite has been used up or has
+    /// expired in the meantime
+    pub async fn claim(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<bool> {
+        let claimed = update(
+            invites::table
+                .filter(invites::id.eq(&self.id))
+                .filter(
+                    invites::max_uses
+                        .is_null()
+                        .or(invites::uses.nullable().lt(invites::max_uses)),
+                )
+                .filter(
+                    invites::expires_at
+                        .is_null()
+                        .or(invites::expires_at.gt(Utc::now())),
+                ),
+        )
+        .set(invites::uses.eq(invites::uses + 1))
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(claimed > 0)
+    }
+
+    /// Makes the invite unusable, it's kept to know who the users that signed up with it were
+    /// invited by
+    pub async fn expire(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+        update(invites::table.filter(invites::id.eq(&self.id)))
+            .set(invites::expires_at.eq(Utc::now()))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Users who signed up with the invite, oldest first
+    pub async fn invited_users(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<User>> {
+        Ok(users::table
+            .filter(users::invite_id.eq(&self.id))
+            .order(users::published.asc())
+            .load::<User>(&mut db_pool.get().await?)
+            .await?)
+    }
+}
@@ -1,27 +1,57 @@
 pub mod activities;
+pub mod app_token;
 pub mod application;
+pub mod archive;
+pub mod article;
 pub mod bookmark;
+pub mod custom_emoji;
+pub mod delivery_stats;
+pub mod followed_tag;
+pub mod import;
+pub mod invite;
 pub mod notification;
+pub mod outgoing_delivery;
 pub mod post;
 pub mod post_boost;
 pub mod post_like;
+pub mod post_reaction;
 pub mod private_note;
+pub mod push_subscription;
+pub mod recovery_code;
 pub mod redirect_code;
+pub mod scheduled_status;
 pub mod session;
+pub mod sign_in_challenge;
+pub mod tag;
 pub mod user;
 pub mod user_follow_request;
 pub mod user_follower;
 
 pub use activities::ReceivedActivity;
+pub use app_token::AppToken;
 pub use application::Application;
+pub use archive::Archive;
+pub use article::Article;
 pub use bookmark::Bookmark;
+pub use custom_emoji::{CustomEmoji, PostEmoji};
+pub use delivery_stats::DeliveryStats;
+pub use followed_tag::FollowedTag;
+pub use import::Import;
+pub use invite::Invite;
 pub use notification::Notification;
+pub use outgoing_delivery::OutgoingDelivery;
 pub use post::{Post, PostMention};
 pub use post_boost::PostBoost;
 pub use post_like::PostLike;
+pub use post_reaction::{PostReaction, ReactionSummary};
 pub use private_note::PrivateNote;
+pub use push_subscription::PushSubscription;
+pub use recovery_code::RecoveryCode;
 pub use redirect_code::RedirectCode;
+pub use scheduled_status::{ScheduledStatus, ScheduledStatusParams};
 pub use session::Session;
-pub use user::{User, UserInsert};
+pub use sign_in_challenge::SignInChallenge;
+pub use tag::{PostTag, Tag};
+pub use user::{User, UserEmoji, UserField, UserInsert};
 pub use user_follow_request::UserFollowRequest;
 pub use user_follower::UserFollower;
@@ -20,6 +20,8 @@ pub struct Notification {
     pub post_id: Option<DbId>,
     pub notification_type: DbNotificationType,
     pub published: DateTime<Utc>,
+    /// Name of the reaction for [`DbNotificationType::EmojiReaction`] notifications
+    pub reaction: Option<String>,
 }
 
 impl Notification {
@@ -37,6 +39,7 @@ impl Notification {
             post_id,
             notification_type,
             published: Utc::now(),
+            reaction: None,
         };
 
         Ok(insert_into(notifications::table)
@@ -113,6 +116,50 @@ impl Notification {
         .await
     }
 
+    pub async fn create_reaction(
+        actor: &User,
+        receiver: &User,
+        post: &Post,
+        reaction: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let notification = Notification {
+            id: DbId::default(),
+            actor_id: actor.id.clone(),
+            receiver_id: receiver.id.clone(),
+            post_id: Some(post.id.clone()),
+            notification_type: DbNotificationType::EmojiReaction,
+            published: Utc::now(),
+            reaction: Some(reaction.to_string()),
+        };
+
+        Ok(insert_into(notifications::table)
+            .values(notification)
+            .get_result::<Notification>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn delete_reaction(
+        actor: &User,
+        receiver: &User,
+        post: &Post,
+        reaction: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        delete(
+            notifications::table
+                .filter(notifications::actor_id.eq(&actor.id))
+                .filter(notifications::receiver_id.eq(&receiver.id))
+                .filter(notifications::notification_type.eq(DbNotificationType::EmojiReaction))
+                .filter(notifications::post_id.eq(&post.id))
+                .filter(notifications::reaction.eq(reaction)),
+        )
+        .execute(&mut db_pool.get().await?)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn by_id(
         id: &DbId,
         db_pool: &Pool<AsyncPgConnection>,
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use diesel::{delete, dsl::count_star, insert_into, prelude::*, update};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{schema::outgoing_deliveries, types::DbId};
+
+/// Activity waiting to be delivered to a remote inbox, removed once it's delivered or given up on
+#[derive(Queryable, Identifiable, Insertable, Selectable, Debug, PartialEq, Clone)]
+#[diesel(table_name = outgoing_deliveries)]
+pub struct OutgoingDelivery {
+    pub id: DbId,
+    /// Local user who signs the activity
+    pub actor_id: DbId,
+    pub inbox: String,
+    /// Domain of the inbox, which the statistics are kept for
+    pub instance: String,
+    pub activity: serde_json::Value,
+    /// Failed attempts so far
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub published: DateTime<Utc>,
+    /// Set while a worker is sending it, workers of other processes leave the inbox alone until
+    /// then
+    pub claimed_until: Option<DateTime<Utc>>,
+}
+
+impl OutgoingDelivery {
+    /// Queues the activity for each inbox
+    pub async fn create(
+        actor_id: &DbId,
+        inboxes: Vec<(String, String)>,
+        activity: serde_json::Value,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let deliveries = inboxes
+            .into_iter()
+            .map(|(inbox, instance)| OutgoingDelivery {
+                id: DbId::default(),
+                actor_id: actor_id.clone(),
+                inbox,
+                instance,
+                activity: activity.clone(),
+                attempts: 0,
+                next_attempt_at: now,
+                last_error: None,
+                published: now,
+                claimed_until: None,
+            })
+            .collect::<Vec<Self>>();
+
+        insert_into(outgoing_deliveries::table)
+            .values(deliveries)
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Oldest delivery of every inbox. Later activities for an inbox wait until the ones before
+    /// them are delivered or given up on, so that they arrive in order
+    pub async fn heads(db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Vec<Self>> {
+        Ok(outgoing_deliveries::table
+            .distinct_on(outgoing_deliveries::inbox)
+            .order((
+                outgoing_deliveries::inbox,
+                outgoing_deliveries::published.asc(),
+                outgoing_deliveries::id.asc(),
+            ))
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Number of queued deliveries for every instance
+    pub async fn count_by_instance(
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<(String, i64)>> {
+        Ok(outgoing_deliveries::table
+            .group_by(outgoing_deliveries::instance)
+            .select((outgoing_deliveries::instance, count_star()))
+            .load::<(String, i64)>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Marks the delivery as being sent until `claimed_until`, after which another worker can
+    /// take it over if this one crashed. Returns `false` if another worker claimed it first or it
+    /// was deleted
+    pub async fn claim(
+        &self,
+        claimed_until: DateTime<Utc>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let rows_affected = update(
+            outgoing_deliveries::table
+                .filter(outgoing_deliveries::id.eq(&self.id))
+                .filter(
+                    outgoing_deliveries::claimed_until
+                        .is_null()
+                        .or(outgoing_deliveries::claimed_until.lt(Utc::now())),
+                ),
+        )
+        .set(outgoing_deliveries::claimed_until.eq(claimed_until))
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(rows_affected == 1)
+    }
+
+    /// Releases the claim and records the failure
+    pub async fn reschedule(
+        &self,
+        next_attempt_at: DateTime<Utc>,
+        error: String,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        Ok(
+            update(outgoing_deliveries::table.filter(outgoing_deliveries::id.eq(&self.id)))
+                .set((
+                    outgoing_deliveries::attempts.eq(outgoing_deliveries::attempts + 1),
+                    outgoing_deliveries::next_attempt_at.eq(next_attempt_at),
+                    outgoing_deliveries::last_error.eq(error),
+                    outgoing_deliveries::claimed_until.eq(None::<DateTime<Utc>>),
+                ))
+                .get_result::<Self>(&mut db_pool.get().await?)
+                .await?,
+        )
+    }
+
+    pub async fn delete(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+        delete(outgoing_deliveries::table.filter(outgoing_deliveries::id.eq(&self.id)))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+}
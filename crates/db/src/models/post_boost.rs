@@ -30,21 +30,20 @@ impl PostBoost {
             .await?)
     }
 
+    /// The removed boost, `None` if the post wasn't boosted by the actor
     pub async fn delete(
         actor: &User,
         post: &Post,
         db_pool: &Pool<AsyncPgConnection>,
-    ) -> anyhow::Result<bool> {
-        let rows_affected = delete(
+    ) -> anyhow::Result<Option<Self>> {
+        Ok(delete(
             post_boost::table
                 .filter(post_boost::actor_id.eq(actor.id.clone()))
                 .filter(post_boost::post_id.eq(post.id.clone())),
         )
-        .execute(&mut db_pool.get().await?)
+        .get_result::<Self>(&mut db_pool.get().await?)
         .await
-        .optional()?;
-
-        Ok(rows_affected == Some(1))
+        .optional()?)
     }
 
     pub async fn by_id(
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use diesel::{
+    delete, insert_into,
+    pg::sql_types::Array,
+    prelude::*,
+    result::Error::NotFound,
+    sql_query,
+    sql_types::{BigInt, Bool, Bpchar, Nullable, Varchar},
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    models::{Post, User},
+    schema::{post_reactions, users},
+    types::DbId,
+};
+
+/// Emoji reaction to a post. `name` is either an Unicode emoji, a shortcode of a local custom
+/// emoji or `shortcode@instance` for remote custom emojis, like in Pleroma
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = post_reactions)]
+pub struct PostReaction {
+    pub id: DbId,
+    pub ap_id: Option<String>,
+    pub post_id: DbId,
+    pub actor_id: DbId,
+    pub name: String,
+    pub emoji_id: Option<DbId>,
+    pub published: DateTime<Utc>,
+}
+
+/// Reactions of the same kind grouped together
+#[derive(QueryableByName, Debug, Clone)]
+pub struct ReactionSummary {
+    #[diesel(sql_type = Bpchar)]
+    pub post_id: DbId,
+    #[diesel(sql_type = Varchar)]
+    pub name: String,
+    #[diesel(sql_type = BigInt)]
+    pub count: i64,
+    /// Whether the user the summary was requested for reacted this way
+    #[diesel(sql_type = Bool)]
+    pub me: bool,
+    #[diesel(sql_type = Nullable<Varchar>)]
+    pub image_url: Option<String>,
+}
+
+impl PostReaction {
+    /// Returns `false` if the actor has already reacted to the post this way
+    pub async fn create(reaction: Self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<bool> {
+        let rows_affected = insert_into(post_reactions::table)
+            .values(reaction)
+            .on_conflict((
+                post_reactions::post_id,
+                post_reactions::actor_id,
+                post_reactions::name,
+            ))
+            .do_nothing()
+            .execute(&mut db_pool.get().await?)
+            .await?;
+
+        Ok(rows_affected == 1)
+    }
+
+    pub async fn by_post_actor_and_name(
+        post: &Post,
+        actor: &User,
+        name: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let reaction = post_reactions::table
+            .filter(post_reactions::post_id.eq(&post.id))
+            .filter(post_reactions::actor_id.eq(&actor.id))
+            .filter(post_reactions::name.eq(name))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match reaction {
+            Ok(reaction) => Ok(Some(reaction)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn by_ap_id(
+        ap_id: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let reaction = post_reactions::table
+            .filter(post_reactions::ap_id.eq(ap_id))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match reaction {
+            Ok(reaction) => Ok(Some(reaction)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns `false` if the reaction has already been deleted
+    pub async fn delete(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<bool> {
+        let deleted = delete(post_reactions::table.filter(post_reactions::id.eq(&self.id)))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Summaries of reactions to several posts at once, in the order the reactions first appeared
+    pub async fn summaries(
+        post_ids: Vec<&DbId>,
+        user_id: Option<&DbId>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<ReactionSummary>> {
+        if post_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(sql_query(
+            "
+            SELECT
+                post_reactions.post_id,
+                post_reactions.name,
+                COUNT(*) AS count,
+                COALESCE(BOOL_OR(post_reactions.actor_id = $2), false) AS me,
+                MAX(custom_emojis.image_url) AS image_url
+            FROM post_reactions
+            LEFT JOIN custom_emojis ON custom_emojis.id = post_reactions.emoji_id
+            WHERE post_reactions.post_id = ANY($1)
+            GROUP BY post_reactions.post_id, post_reactions.name
+            ORDER BY MIN(post_reactions.published);
+            ",
+        )
+        .bind::<Array<Bpchar>, _>(post_ids)
+        .bind::<Nullable<Bpchar>, _>(user_id.cloned())
+        .load::<ReactionSummary>(&mut db_pool.get().await?)
+        .await?)
+    }
+
+    /// Users who reacted to the post, paired with the name of their reaction
+    pub async fn reactors(
+        post: &Post,
+        name: Option<&str>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<(String, User)>> {
+        let mut query = post_reactions::table
+            .filter(post_reactions::post_id.eq(&post.id))
+            .inner_join(users::table.on(users::id.eq(post_reactions::actor_id)))
+            .select((post_reactions::name, users::all_columns))
+            .order(post_reactions::published.asc())
+            .into_boxed();
+        if let Some(name) = name {
+            query = query.filter(post_reactions::name.eq(name.to_string()));
+        }
+
+        Ok(query
+            .load::<(String, User)>(&mut db_pool.get().await?)
+            .await?)
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use diesel::{delete, insert_into, prelude::*, result::Error::NotFound, update};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{models::Session, schema::push_subscriptions, types::DbId};
+
+/// Web Push subscription, each session can have one. Removed along with the session
+#[derive(Queryable, Identifiable, Insertable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = push_subscriptions)]
+pub struct PushSubscription {
+    pub id: DbId,
+    pub session_id: DbId,
+    pub user_id: DbId,
+    pub endpoint: String,
+    /// Base64url-encoded P-256 public key of the subscriber, see RFC 8291
+    pub p256dh: String,
+    /// Base64url-encoded authentication secret of the subscriber
+    pub auth: String,
+    /// Space-separated notification types that are pushed
+    pub alerts: String,
+    /// Whose notifications are pushed: `all`, `followed`, `follower` or `none`
+    pub policy: String,
+    pub published: DateTime<Utc>,
+}
+
+impl PushSubscription {
+    /// Replaces the subscription of the session
+    pub async fn create(
+        session: &Session,
+        endpoint: String,
+        p256dh: String,
+        auth: String,
+        alerts: String,
+        policy: String,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let subscription = PushSubscription {
+            id: DbId::default(),
+            session_id: session.id.clone(),
+            user_id: session.user_id.clone(),
+            endpoint,
+            p256dh,
+            auth,
+            alerts,
+            policy,
+            published: Utc::now(),
+        };
+
+        Ok(insert_into(push_subscriptions::table)
+            .values(subscription.clone())
+            .on_conflict(push_subscriptions::session_id)
+            .do_update()
+            .set((
+                push_subscriptions::id.eq(&subscription.id),
+                push_subscriptions::endpoint.eq(&subscription.endpoint),
+                push_subscriptions::p256dh.eq(&subscription.p256dh),
+                push_subscriptions::auth.eq(&subscription.auth),
+                push_subscriptions::alerts.eq(&subscription.alerts),
+                push_subscriptions::policy.eq(&subscription.policy),
+                push_subscriptions::published.eq(&subscription.published),
+            ))
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_session(
+        session_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let subscription = push_subscriptions::table
+            .filter(push_subscriptions::session_id.eq(session_id))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match subscription {
+            Ok(subscription) => Ok(Some(subscription)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn get_for_user(
+        user_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(push_subscriptions::table
+            .filter(push_subscriptions::user_id.eq(user_id))
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Changes the alerts and the policy, the endpoint and the keys stay the same
+    pub async fn update(
+        &self,
+        alerts: String,
+        policy: String,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        Ok(
+            update(push_subscriptions::table.filter(push_subscriptions::id.eq(&self.id)))
+                .set((
+                    push_subscriptions::alerts.eq(alerts),
+                    push_subscriptions::policy.eq(policy),
+                ))
+                .get_result::<Self>(&mut db_pool.get().await?)
+                .await?,
+        )
+    }
+
+    pub async fn delete(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+        delete(push_subscriptions::table.filter(push_subscriptions::id.eq(&self.id)))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    pub fn has_alert(&self, notification_type: &str) -> bool {
+        self.alerts
+            .split_whitespace()
+            .any(|alert| alert == notification_type)
+    }
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use diesel::{delete, insert_into, prelude::*};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{schema::recovery_codes, types::DbId};
+
+/// Single-use code that can be given instead of a TOTP code when signing in. Only SHA-256 hashes
+/// of the codes are stored
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = recovery_codes)]
+pub struct RecoveryCode {
+    pub id: DbId,
+    pub user_id: DbId,
+    pub code_hash: String,
+    pub published: DateTime<Utc>,
+}
+
+impl RecoveryCode {
+    /// Replaces the codes of the user with new ones
+    pub async fn replace_for_user(
+        user_id: &DbId,
+        code_hashes: Vec<String>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        let mut conn = db_pool.get().await?;
+        delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user_id)))
+            .execute(&mut conn)
+            .await?;
+
+        let codes: Vec<RecoveryCode> = code_hashes
+            .into_iter()
+            .map(|code_hash| RecoveryCode {
+                id: DbId::default(),
+                user_id: user_id.clone(),
+                code_hash,
+                published: Utc::now(),
+            })
+            .collect();
+        insert_into(recovery_codes::table)
+            .values(codes)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes the code if the user has it. Returns `false` if there was no such code
+    pub async fn redeem(
+        user_id: &DbId,
+        code_hash: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let deleted = delete(
+            recovery_codes::table
+                .filter(recovery_codes::user_id.eq(user_id))
+                .filter(recovery_codes::code_hash.eq(code_hash)),
+        )
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    pub async fn delete_for_user(
+        user_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user_id)))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+}
@@ -10,27 +10,38 @@ pub struct RedirectCode {
     pub code: String,
     pub client_id: String,
     pub user_id: DbId,
+    /// Space-separated OAuth scopes the user has granted
+    pub scopes: String,
+    /// PKCE challenge of public clients, see RFC 7636
+    pub code_challenge: Option<String>,
+    /// `S256` or `plain`, set along with the challenge
+    pub code_challenge_method: Option<String>,
 }
 
 impl RedirectCode {
     pub async fn create(
         client_id: String,
         user_id: DbId,
+        scopes: String,
+        code_challenge: Option<String>,
+        code_challenge_method: Option<String>,
         redis: &mut ConnectionManager,
     ) -> anyhow::Result<Self> {
         let code = random_string(32);
         let key = format!("codes:{}", code);
 
-        redis
-            .hset_multiple(
-                &key,
-                &[
-                    ("code", &code),
-                    ("client_id", &client_id),
-                    ("user_id", &user_id.to_string()),
-                ],
-            )
-            .await?;
+        let user_id_string = user_id.to_string();
+        let mut fields = vec![
+            ("code", &code),
+            ("client_id", &client_id),
+            ("user_id", &user_id_string),
+            ("scopes", &scopes),
+        ];
+        if let (Some(challenge), Some(method)) = (&code_challenge, &code_challenge_method) {
+            fields.push(("code_challenge", challenge));
+            fields.push(("code_challenge_method", method));
+        }
+        redis.hset_multiple(&key, &fields).await?;
 
         redis.expire(&key, 60).await?;
 
@@ -38,6 +49,9 @@ impl RedirectCode {
             code,
             client_id,
             user_id,
+            scopes,
+            code_challenge,
+            code_challenge_method,
         })
     }
 
@@ -55,6 +69,13 @@ impl RedirectCode {
                 code: hash.get("code").unwrap().clone(),
                 client_id: hash.get("client_id").unwrap().clone(),
                 user_id: hash.get("user_id").unwrap().clone().into(),
+                // Codes issued before scopes existed
+                scopes: hash
+                    .get("scopes")
+                    .cloned()
+                    .unwrap_or_else(|| String::from("read")),
+                code_challenge: hash.get("code_challenge").cloned(),
+                code_challenge_method: hash.get("code_challenge_method").cloned(),
             }))
         }
     }
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use diesel::{delete, insert_into, prelude::*, result::Error::NotFound, update};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::User,
+    paginate,
+    pagination::Pagination,
+    schema::scheduled_statuses,
+    types::{DbId, DbVisibility},
+};
+
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone)]
+#[diesel(table_name = scheduled_statuses)]
+pub struct ScheduledStatus {
+    pub id: DbId,
+    pub user_id: DbId,
+    pub scheduled_at: DateTime<Utc>,
+    pub params: serde_json::Value,
+    pub published: DateTime<Utc>,
+    /// Set while a worker is publishing it, other workers leave it alone until then
+    pub claimed_until: Option<DateTime<Utc>>,
+    /// Failed attempts to publish it
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// When a failed status is tried again, `scheduled_at` stays as the user set it
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+/// Everything needed to publish the post later, stored as JSON in `params`
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ScheduledStatusParams {
+    pub text: String,
+    pub visibility: DbVisibility,
+    pub in_reply_to_id: Option<String>,
+    pub quote_id: Option<String>,
+    pub local_only: bool,
+    pub sensitive: bool,
+    pub spoiler_text: Option<String>,
+}
+
+impl ScheduledStatus {
+    pub async fn create(
+        user: &User,
+        scheduled_at: DateTime<Utc>,
+        params: &ScheduledStatusParams,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let scheduled_status = ScheduledStatus {
+            id: DbId::default(),
+            user_id: user.id.clone(),
+            scheduled_at,
+            params: serde_json::to_value(params)?,
+            published: Utc::now(),
+            claimed_until: None,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
+        };
+
+        Ok(insert_into(scheduled_statuses::table)
+            .values(scheduled_status)
+            .get_result::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn by_id(
+        id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let scheduled_status = scheduled_statuses::table
+            .filter(scheduled_statuses::id.eq(id))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match scheduled_status {
+            Ok(scheduled_status) => Ok(Some(scheduled_status)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn get_for_user(
+        user_id: &DbId,
+        pagination: Pagination,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = scheduled_statuses::table
+            .filter(scheduled_statuses::user_id.eq(user_id))
+            .select(scheduled_statuses::all_columns)
+            .order(scheduled_statuses::id.desc())
+            .into_boxed();
+        let query = paginate!(query, scheduled_statuses::id, pagination);
+
+        Ok(query.load::<Self>(&mut db_pool.get().await?).await?)
+    }
+
+    /// Scheduled statuses whose time has come and that aren't claimed by a worker, oldest first.
+    /// Those that failed `max_attempts` times are left for the user to reschedule or delete
+    pub async fn due(
+        limit: i64,
+        max_attempts: i32,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let now = Utc::now();
+        Ok(scheduled_statuses::table
+            .filter(scheduled_statuses::scheduled_at.le(now))
+            .filter(
+                scheduled_statuses::next_attempt_at
+                    .is_null()
+                    .or(scheduled_statuses::next_attempt_at.le(now)),
+            )
+            .filter(scheduled_statuses::attempts.lt(max_attempts))
+            .filter(
+                scheduled_statuses::claimed_until
+                    .is_null()
+                    .or(scheduled_statuses::claimed_until.lt(now)),
+            )
+            .order(scheduled_statuses::scheduled_at.asc())
+            .limit(limit)
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub fn params(&self) -> anyhow::Result<ScheduledStatusParams> {
+        Ok(serde_json::from_value(self.params.clone())?)
+    }
+
+    pub async fn reschedule(
+        &self,
+        scheduled_at: DateTime<Utc>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        Ok(
+            update(scheduled_statuses::table.filter(scheduled_statuses::id.eq(&self.id)))
+                .set((
+                    scheduled_statuses::scheduled_at.eq(scheduled_at),
+                    scheduled_statuses::attempts.eq(0),
+                    scheduled_statuses::last_error.eq(None::<String>),
+                    scheduled_statuses::next_attempt_at.eq(None::<DateTime<Utc>>),
+                ))
+                .get_result::<Self>(&mut db_pool.get().await?)
+                .await?,
+        )
+    }
+
+    /// Marks the scheduled status as being published until `claimed_until`, after which another
+    /// worker can take it over if this one crashed. Returns `false` if another worker claimed it
+    /// first or it was deleted
+    pub async fn claim(
+        &self,
+        claimed_until: DateTime<Utc>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let rows_affected = update(
+            scheduled_statuses::table
+                .filter(scheduled_statuses::id.eq(&self.id))
+                .filter(
+                    scheduled_statuses::claimed_until
+                        .is_null()
+                        .or(scheduled_statuses::claimed_until.lt(Utc::now())),
+                ),
+        )
+        .set(scheduled_statuses::claimed_until.eq(claimed_until))
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(rows_affected == 1)
+    }
+
+    /// Releases the claim and records the failure, the status is tried again at `retry_at`
+    pub async fn fail(
+        &self,
+        error: String,
+        retry_at: DateTime<Utc>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        update(scheduled_statuses::table.filter(scheduled_statuses::id.eq(&self.id)))
+            .set((
+                scheduled_statuses::next_attempt_at.eq(retry_at),
+                scheduled_statuses::claimed_until.eq(None::<DateTime<Utc>>),
+                scheduled_statuses::attempts.eq(scheduled_statuses::attempts + 1),
+                scheduled_statuses::last_error.eq(error),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns `false` if the scheduled status has already been deleted (e.g.
+    /// it was published by another worker in the meantime)
+    pub async fn delete(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<bool> {
+        let deleted = delete(scheduled_statuses::table.filter(scheduled_statuses::id.eq(&self.id)))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(deleted > 0)
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::anyhow;
-use chrono::{DateTime, Utc};
-use diesel::{delete, insert_into, prelude::*, result::Error::NotFound};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{delete, insert_into, prelude::*, result::Error::NotFound, update};
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
 
 use crate::{
@@ -18,12 +18,32 @@ pub struct Session {
     pub user_id: DbId,
     pub published: DateTime<Utc>,
     pub application_id: Option<DbId>,
+    /// Space-separated OAuth scopes granted to the token
+    pub scopes: String,
+    /// Only set for tokens issued to applications when access tokens have a lifetime
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Lets the application get a new token once this one expires, set along with `expires_at`
+    pub refresh_token: Option<String>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// IP address of the last request made with the token
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Whether the token was issued for a code exchanged with PKCE. Only then can it be refreshed
+    /// without the client secret
+    pub pkce: bool,
 }
 
+/// How long the last use of a session is kept before it's recorded again, so that not every
+/// request writes to the database
+const TOUCH_INTERVAL: i64 = 60;
+
 impl Session {
     pub async fn create(
         user_id: DbId,
         application_id: Option<DbId>,
+        scopes: String,
+        expires_at: Option<DateTime<Utc>>,
+        pkce: bool,
         db_pool: &Pool<AsyncPgConnection>,
     ) -> anyhow::Result<Self> {
         let session = Session {
@@ -32,6 +52,13 @@ impl Session {
             user_id,
             published: Utc::now(),
             application_id,
+            scopes,
+            expires_at,
+            refresh_token: expires_at.map(|_| random_string(60)),
+            last_used_at: None,
+            ip: None,
+            user_agent: None,
+            pkce,
         };
 
         Ok(insert_into(sessions::table)
@@ -46,6 +73,67 @@ impl Session {
     ) -> anyhow::Result<Option<Self>> {
         let session = sessions::table
             .filter(sessions::token.eq(token.to_string()))
+            .filter(
+                sessions::expires_at
+                    .is_null()
+                    .or(sessions::expires_at.gt(Utc::now())),
+            )
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match session {
+            Ok(session) => Ok(Some(session)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Refresh tokens stay valid after the access token has expired
+    pub async fn by_refresh_token(
+        refresh_token: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let session = sessions::table
+            .filter(sessions::refresh_token.eq(refresh_token.to_string()))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match session {
+            Ok(session) => Ok(Some(session)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Replaces both the access and the refresh token, so that the old ones can't be used again.
+    /// `None` if the refresh token has already been used, e.g. by a concurrent request
+    pub async fn rotate(
+        &self,
+        scopes: String,
+        expires_at: Option<DateTime<Utc>>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        Ok(update(
+            sessions::table
+                .filter(sessions::id.eq(&self.id))
+                .filter(sessions::refresh_token.eq(&self.refresh_token)),
+        )
+        .set((
+            sessions::token.eq(random_string(60)),
+            sessions::refresh_token.eq(expires_at.map(|_| random_string(60))),
+            sessions::published.eq(Utc::now()),
+            sessions::scopes.eq(scopes),
+            sessions::expires_at.eq(expires_at),
+        ))
+        .get_result::<Self>(&mut db_pool.get().await?)
+        .await
+        .optional()?)
+    }
+
+    pub async fn by_id(
+        id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let session = sessions::table
+            .filter(sessions::id.eq(id))
             .first::<Self>(&mut db_pool.get().await?)
             .await;
         match session {
@@ -55,6 +143,63 @@ impl Session {
         }
     }
 
+    /// Sessions of the user, most recently used first
+    pub async fn get_for_user(
+        user_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(sessions::table
+            .filter(sessions::user_id.eq(user_id))
+            .order((
+                sessions::last_used_at.desc().nulls_last(),
+                sessions::published.desc(),
+            ))
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Records the last use of the session
+    pub async fn touch(
+        &self,
+        ip: Option<String>,
+        user_agent: Option<String>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        let recent = self.last_used_at.is_some_and(|last_used_at| {
+            Utc::now() - last_used_at < Duration::seconds(TOUCH_INTERVAL)
+        });
+        if recent && self.ip == ip && self.user_agent == user_agent {
+            return Ok(());
+        }
+
+        update(sessions::table.filter(sessions::id.eq(&self.id)))
+            .set((
+                sessions::last_used_at.eq(Utc::now()),
+                sessions::ip.eq(ip),
+                sessions::user_agent.eq(user_agent),
+            ))
+            .execute(&mut db_pool.get().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes every token the user has given to the application and returns the IDs of the
+    /// deleted sessions
+    pub async fn delete_for_application(
+        user_id: &DbId,
+        application_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<DbId>> {
+        Ok(delete(
+            sessions::table
+                .filter(sessions::user_id.eq(user_id))
+                .filter(sessions::application_id.eq(application_id)),
+        )
+        .returning(sessions::id)
+        .get_results::<DbId>(&mut db_pool.get().await?)
+        .await?)
+    }
+
     pub async fn user(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<User> {
         User::by_id(&self.user_id, db_pool)
             .await?
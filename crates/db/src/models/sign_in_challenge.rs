@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use crate::{types::DbId, utils::random_string};
+
+/// Sign-in that has passed the password check and waits for a TOTP or recovery code. Stored in
+/// Redis for a few minutes
+pub struct SignInChallenge {
+    pub token: String,
+    pub user_id: DbId,
+    /// Secret the user is enrolling with, when 2FA is required but hasn't been set up yet
+    pub otp_secret: Option<String>,
+}
+
+impl SignInChallenge {
+    /// Wrong codes allowed before the challenge is dropped and the password has to be given again
+    pub const MAX_ATTEMPTS: i64 = 5;
+
+    pub async fn create(
+        user_id: DbId,
+        otp_secret: Option<String>,
+        redis: &mut ConnectionManager,
+    ) -> anyhow::Result<Self> {
+        let token = random_string(32);
+        let key = format!("sign_in_challenges:{}", token);
+
+        let mut fields = vec![("user_id", user_id.to_string())];
+        if let Some(otp_secret) = &otp_secret {
+            fields.push(("otp_secret", otp_secret.clone()));
+        }
+        redis.hset_multiple(&key, &fields).await?;
+        redis.expire(&key, 300).await?;
+
+        Ok(SignInChallenge {
+            token,
+            user_id,
+            otp_secret,
+        })
+    }
+
+    pub async fn by_token(
+        token: &str,
+        redis: &mut ConnectionManager,
+    ) -> anyhow::Result<Option<Self>> {
+        let hash: HashMap<String, String> = redis
+            .hgetall(format!("sign_in_challenges:{}", token))
+            .await?;
+
+        match hash.get("user_id") {
+            Some(user_id) => Ok(Some(SignInChallenge {
+                token: token.to_string(),
+                user_id: user_id.clone().into(),
+                otp_secret: hash.get("otp_secret").cloned(),
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Counts a wrong code, dropping the challenge after [`SignInChallenge::MAX_ATTEMPTS`]. Returns
+    /// `false` if it has been dropped
+    pub async fn fail(&self, redis: &mut ConnectionManager) -> anyhow::Result<bool> {
+        let attempts: i64 = redis
+            .hincr(format!("sign_in_challenges:{}", self.token), "attempts", 1)
+            .await?;
+        if attempts >= Self::MAX_ATTEMPTS {
+            self.delete(redis).await?;
+            Ok(false)
+        } else {
+            Ok(true)
+        }
+    }
+
+    pub async fn delete(&self, redis: &mut ConnectionManager) -> anyhow::Result<()> {
+        redis
+            .del(format!("sign_in_challenges:{}", self.token))
+            .await?;
+        Ok(())
+    }
+}
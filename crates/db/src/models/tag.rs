@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use diesel::{insert_into, prelude::*, result::Error::NotFound};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    models::User,
+    paginate,
+    pagination::Pagination,
+    schema::{followed_tags, post_tags, tags},
+    types::DbId,
+};
+
+#[derive(Queryable, Insertable, Identifiable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = tags)]
+pub struct Tag {
+    pub id: DbId,
+    /// Always lowercase and without the leading `#`
+    pub name: String,
+    pub published: DateTime<Utc>,
+}
+
+#[derive(Queryable, Insertable, Selectable, Debug, PartialEq, Clone, Eq)]
+#[diesel(table_name = post_tags)]
+pub struct PostTag {
+    pub post_id: DbId,
+    pub tag_id: DbId,
+}
+
+impl Tag {
+    pub fn normalize_name(name: &str) -> String {
+        name.trim_start_matches('#').to_lowercase()
+    }
+
+    pub async fn by_name(
+        name: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Option<Self>> {
+        let tag = tags::table
+            .filter(tags::name.eq(Self::normalize_name(name)))
+            .first::<Self>(&mut db_pool.get().await?)
+            .await;
+        match tag {
+            Ok(tag) => Ok(Some(tag)),
+            Err(NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn by_name_or_create(
+        name: &str,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Self> {
+        let mut conn = db_pool.get().await?;
+        let name = Self::normalize_name(name);
+
+        insert_into(tags::table)
+            .values(Tag {
+                id: DbId::default(),
+                name: name.clone(),
+                published: Utc::now(),
+            })
+            .on_conflict(tags::name)
+            .do_nothing()
+            .execute(&mut conn)
+            .await?;
+
+        Ok(tags::table
+            .filter(tags::name.eq(name))
+            .first::<Self>(&mut conn)
+            .await?)
+    }
+
+    pub async fn by_post(
+        post_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(post_tags::table
+            .filter(post_tags::post_id.eq(post_id))
+            .inner_join(tags::table)
+            .select(tags::all_columns)
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    pub async fn attach_to_post(
+        post_id: &DbId,
+        names: Vec<String>,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<()> {
+        let mut post_tags = vec![];
+        for name in names {
+            let name = Self::normalize_name(&name);
+            if name.is_empty() || name.chars().count() > 100 {
+                continue;
+            }
+
+            let tag = Self::by_name_or_create(&name, db_pool).await?;
+            post_tags.push(PostTag {
+                post_id: post_id.clone(),
+                tag_id: tag.id,
+            });
+        }
+
+        if !post_tags.is_empty() {
+            insert_into(post_tags::table)
+                .values(post_tags)
+                .on_conflict((post_tags::post_id, post_tags::tag_id))
+                .do_nothing()
+                .execute(&mut db_pool.get().await?)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn followed_by(
+        user: &User,
+        pagination: Pagination,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = followed_tags::table
+            .filter(followed_tags::user_id.eq(&user.id))
+            .inner_join(tags::table)
+            .select(tags::all_columns)
+            .order(followed_tags::published.desc())
+            .into_boxed();
+        let query = paginate!(query, tags::id, pagination);
+
+        Ok(query.load::<Self>(&mut db_pool.get().await?).await?)
+    }
+
+    /// Local users following at least one of the post's tags
+    pub async fn post_followers(
+        post_id: &DbId,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<DbId>> {
+        Ok(post_tags::table
+            .filter(post_tags::post_id.eq(post_id))
+            .inner_join(followed_tags::table.on(followed_tags::tag_id.eq(post_tags::tag_id)))
+            .select(followed_tags::user_id)
+            .distinct()
+            .load::<DbId>(&mut db_pool.get().await?)
+            .await?)
+    }
+}
@@ -6,15 +6,20 @@ use diesel::{
     select, sql_query,
     sql_types::{Bool, Bpchar, Varchar},
 };
-use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use diesel_async::{
+    pooled_connection::deadpool::Pool, scoped_futures::ScopedFutureExt, AsyncConnection,
+    AsyncPgConnection, RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     common::timelines::{self, TimelineEntry},
-    models::{user_follow_request::UserFollowRequest, Post},
+    models::{user_follow_request::UserFollowRequest, CustomEmoji, Post, PostBoost},
     paginate,
     pagination::Pagination,
     schema::{
-        bookmarks, post_boost, post_like, posts, user_follow_requests, user_followers, users,
+        bookmarks, post_boost, post_like, posts, sessions, user_follow_requests, user_followers,
+        users,
     },
     types::DbId,
     utils::coalesce,
@@ -53,6 +58,29 @@ pub struct User {
     pub posts_count: i32,
     /// Updated by database triggers defined in `../../migrations/2025-06-29-211313_save_user_stats/up.sql`
     pub last_post_published: Option<DateTime<Utc>>,
+    /// Profile metadata, see [`UserField`]
+    pub fields: serde_json::Value,
+    /// Custom emojis used in the display name and bio, see [`UserEmoji`]
+    pub emojis: serde_json::Value,
+    /// IDs of ActivityPub actors the user has said to also be, see [`User::also_known_as`]
+    pub also_known_as: serde_json::Value,
+    /// Account the user has moved to
+    pub moved_to_id: Option<DbId>,
+    /// Set when the account has been deleted, the row is kept as a tombstone, see [`User::purge`]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// `false` while the registration waits for an admin
+    pub approved: bool,
+    /// Why the user wants to join, given when registrations require approval
+    pub registration_reason: Option<String>,
+    /// Invite the user signed up with, see [`Invite`](crate::models::Invite)
+    pub invite_id: Option<DbId>,
+    /// Base32 TOTP secret, set during enrolment before `otp_enabled` is
+    pub otp_secret: Option<String>,
+    /// Whether sign-in asks for a TOTP or recovery code, see
+    /// [`RecoveryCode`](crate::models::RecoveryCode)
+    pub otp_enabled: bool,
+    /// Time step of the last TOTP code that was accepted, codes up to it can't be used again
+    pub otp_consumed_timestep: Option<i64>,
 }
 
 #[derive(Clone, Insertable, AsChangeset)]
@@ -78,6 +106,12 @@ pub struct UserInsert {
     pub manually_approves_followers: bool,
     pub is_cat: bool,
     pub bot: bool,
+    pub fields: serde_json::Value,
+    pub emojis: serde_json::Value,
+    pub also_known_as: serde_json::Value,
+    pub approved: bool,
+    pub registration_reason: Option<String>,
+    pub invite_id: Option<DbId>,
 }
 
 #[derive(AsChangeset, Clone)]
@@ -93,6 +127,62 @@ pub struct UserUpdate {
     pub manually_approves_followers: Option<bool>,
     pub is_cat: Option<bool>,
     pub bot: Option<bool>,
+    pub fields: Option<serde_json::Value>,
+    pub emojis: Option<serde_json::Value>,
+    pub also_known_as: Option<serde_json::Value>,
+    pub moved_to_id: Option<Option<DbId>>,
+    pub approved: Option<bool>,
+    pub invite_id: Option<Option<DbId>>,
+    pub otp_secret: Option<Option<String>>,
+    pub otp_enabled: Option<bool>,
+}
+
+/// Profile metadata field. Values of local users are stored as plain text, values of remote users
+/// are stored as HTML received from their servers
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Eq)]
+pub struct UserField {
+    pub name: String,
+    pub value: String,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl UserField {
+    /// Keeps `verified_at` of fields that haven't changed
+    pub fn merge(old_fields: &[UserField], new_fields: Vec<UserField>) -> Vec<UserField> {
+        new_fields
+            .into_iter()
+            .map(|mut field| {
+                field.verified_at = old_fields
+                    .iter()
+                    .find(|old_field| {
+                        old_field.name == field.name && old_field.value == field.value
+                    })
+                    .and_then(|old_field| old_field.verified_at);
+                field
+            })
+            .collect()
+    }
+}
+
+/// Copy of a [`CustomEmoji`](crate::models::CustomEmoji) used by the user, so that accounts can be
+/// rendered without looking emojis up
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Eq)]
+pub struct UserEmoji {
+    pub shortcode: String,
+    pub url: String,
+    pub ap_id: String,
+    pub media_type: Option<String>,
+}
+
+impl From<&CustomEmoji> for UserEmoji {
+    fn from(emoji: &CustomEmoji) -> Self {
+        Self {
+            shortcode: emoji.shortcode.clone(),
+            url: emoji.image_url.clone(),
+            ap_id: emoji.ap_id.clone(),
+            media_type: emoji.media_type.clone(),
+        }
+    }
 }
 
 pub struct UserRelationship {
@@ -187,6 +277,163 @@ impl User {
         Ok(())
     }
 
+    /// Local registrations waiting for an admin, oldest first
+    pub async fn pending(db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Vec<Self>> {
+        Ok(users::table
+            .filter(users::local.eq(true))
+            .filter(users::approved.eq(false))
+            .order(users::published.asc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Removes a registration that hasn't been approved, which frees the name. Returns `false` if
+    /// the user isn't pending
+    pub async fn delete_pending(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<bool> {
+        if self.approved {
+            return Ok(false);
+        }
+
+        let mut conn = db_pool.get().await?;
+        diesel::delete(sessions::table.filter(sessions::user_id.eq(&self.id)))
+            .execute(&mut conn)
+            .await?;
+        let deleted = diesel::delete(
+            users::table
+                .filter(users::id.eq(&self.id))
+                .filter(users::approved.eq(false)),
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Removes everything the user has created or takes part in and turns the row into a
+    /// tombstone, so that the name can't be taken again and the actor isn't fetched again. Either
+    /// everything is removed or nothing is
+    pub async fn purge(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<()> {
+        const QUERIES: [&str; 21] = [
+            // Rows that reference posts of the user
+            "UPDATE posts SET in_reply = NULL WHERE in_reply IN (SELECT id FROM posts WHERE author = $1);",
+            "UPDATE posts SET quote = NULL WHERE quote IN (SELECT id FROM posts WHERE author = $1);",
+            "DELETE FROM notifications WHERE actor_id = $1 OR receiver_id = $1 OR post_id IN (SELECT id FROM posts WHERE author = $1);",
+            "DELETE FROM post_mention WHERE mentioned_user_id = $1 OR post_id IN (SELECT id FROM posts WHERE author = $1);",
+            "DELETE FROM post_like WHERE actor_id = $1 OR post_id IN (SELECT id FROM posts WHERE author = $1);",
+            "DELETE FROM post_boost WHERE actor_id = $1 OR post_id IN (SELECT id FROM posts WHERE author = $1);",
+            "DELETE FROM bookmarks WHERE actor_id = $1 OR post_id IN (SELECT id FROM posts WHERE author = $1);",
+            "DELETE FROM post_reactions WHERE actor_id = $1;",
+            "DELETE FROM posts WHERE author = $1;",
+            "DELETE FROM user_followers WHERE actor_id = $1 OR follower_id = $1;",
+            "DELETE FROM user_follow_requests WHERE actor_id = $1 OR follower_id = $1;",
+            "DELETE FROM sessions WHERE user_id = $1;",
+            // Signing up with them would follow the tombstone
+            "UPDATE invites SET expires_at = now() WHERE user_id = $1 AND (expires_at IS NULL OR expires_at > now());",
+            "DELETE FROM private_notes WHERE actor_id = $1 OR user_id = $1;",
+            "DELETE FROM scheduled_statuses WHERE user_id = $1;",
+            "DELETE FROM followed_tags WHERE user_id = $1;",
+            "DELETE FROM imports WHERE user_id = $1;",
+            "DELETE FROM archives WHERE user_id = $1;",
+            "DELETE FROM recovery_codes WHERE user_id = $1;",
+            "UPDATE users SET moved_to_id = NULL WHERE moved_to_id = $1;",
+            "
+            UPDATE users SET
+                display_name = NULL,
+                bio = NULL,
+                password_encrypted = NULL,
+                fields = '[]',
+                emojis = '[]',
+                also_known_as = '[]',
+                moved_to_id = NULL,
+                otp_secret = NULL,
+                otp_enabled = false,
+                deleted_at = now()
+            WHERE id = $1;
+            ",
+        ];
+
+        let id = self.id.clone();
+        db_pool
+            .get()
+            .await?
+            .transaction::<_, anyhow::Error, _>(move |conn| {
+                async move {
+                    for query in QUERIES {
+                        sql_query(query)
+                            .bind::<Bpchar, _>(id.clone())
+                            .execute(conn)
+                            .await?;
+                    }
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    pub fn fields(&self) -> Vec<UserField> {
+        serde_json::from_value(self.fields.clone()).unwrap_or_default()
+    }
+
+    pub fn emojis(&self) -> Vec<UserEmoji> {
+        serde_json::from_value(self.emojis.clone()).unwrap_or_default()
+    }
+
+    pub fn also_known_as(&self) -> Vec<String> {
+        serde_json::from_value(self.also_known_as.clone()).unwrap_or_default()
+    }
+
+    /// Local users following the user
+    pub async fn local_followers(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(user_followers::table
+            .filter(user_followers::follower_id.eq(&self.id))
+            .filter(users::local.eq(true))
+            .inner_join(users::table.on(users::id.eq(user_followers::actor_id)))
+            .select(users::all_columns)
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// Doesn't overwrite fields if they were changed since `self` was fetched. Returns `false` in
+    /// that case
+    /// Records that a TOTP code of the time step was used. Returns `false` if a code of the same
+    /// or a later step was already used, so that codes can't be replayed
+    pub async fn consume_otp_timestep(
+        &self,
+        timestep: i64,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let rows_affected = diesel::update(
+            users::table.filter(users::id.eq(&self.id)).filter(
+                users::otp_consumed_timestep
+                    .is_null()
+                    .or(users::otp_consumed_timestep.lt(timestep)),
+            ),
+        )
+        .set(users::otp_consumed_timestep.eq(timestep))
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(rows_affected == 1)
+    }
+
+    pub async fn replace_fields(
+        &self,
+        fields: &[UserField],
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<bool> {
+        let rows_affected = diesel::update(
+            users::table
+                .filter(users::id.eq(&self.id))
+                .filter(users::fields.eq(&self.fields)),
+        )
+        .set(users::fields.eq(serde_json::to_value(fields)?))
+        .execute(&mut db_pool.get().await?)
+        .await?;
+        Ok(rows_affected == 1)
+    }
+
     pub async fn posts(
         &self,
         pagination: Pagination,
@@ -206,6 +453,14 @@ impl User {
         .await
     }
 
+    pub async fn home_timeline(
+        &self,
+        pagination: Pagination,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<TimelineEntry>> {
+        timelines::get_home_timeline(&self.id, pagination, db_pool).await
+    }
+
     pub async fn follows_by_id(
         &self,
         user_id: &DbId,
@@ -313,6 +568,34 @@ impl User {
         Ok(query.load::<Self>(&mut db_pool.get().await?).await?)
     }
 
+    /// All followers at once, for exports
+    pub async fn all_followers(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(user_followers::table
+            .filter(user_followers::follower_id.eq(&self.id))
+            .inner_join(users::table.on(users::id.eq(user_followers::actor_id)))
+            .select(users::all_columns)
+            .order(users::id.asc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// All followed users at once, for exports
+    pub async fn all_following(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Self>> {
+        Ok(user_followers::table
+            .filter(user_followers::actor_id.eq(&self.id))
+            .inner_join(users::table.on(users::id.eq(user_followers::follower_id)))
+            .select(users::all_columns)
+            .order(users::id.asc())
+            .load::<Self>(&mut db_pool.get().await?)
+            .await?)
+    }
+
     pub async fn follow_requests(
         &self,
         pagination: Pagination,
@@ -361,6 +644,41 @@ impl User {
             .await?)
     }
 
+    /// All posts of the user at once, oldest first, for archives
+    pub async fn all_posts(&self, db_pool: &Pool<AsyncPgConnection>) -> anyhow::Result<Vec<Post>> {
+        Ok(posts::table
+            .filter(posts::author.eq(&self.id))
+            .order(posts::published.asc())
+            .load::<Post>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// All boosts of the user at once, oldest first, for archives
+    pub async fn all_boosts(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<PostBoost>> {
+        Ok(post_boost::table
+            .filter(post_boost::actor_id.eq(&self.id))
+            .order(post_boost::published.asc())
+            .load::<PostBoost>(&mut db_pool.get().await?)
+            .await?)
+    }
+
+    /// All liked posts at once, newest likes first, for archives
+    pub async fn all_liked_posts(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Post>> {
+        Ok(post_like::table
+            .filter(post_like::actor_id.eq(&self.id))
+            .inner_join(posts::table.on(posts::id.eq(post_like::post_id)))
+            .select(posts::all_columns)
+            .order(post_like::published.desc())
+            .load::<Post>(&mut db_pool.get().await?)
+            .await?)
+    }
+
     pub async fn liked_posts(
         &self,
         pagination: Pagination,
@@ -392,6 +710,20 @@ impl User {
 
         Ok(query.load::<Post>(&mut db_pool.get().await?).await?)
     }
+
+    /// All bookmarked posts at once, newest bookmarks first, for exports
+    pub async fn all_bookmarked_posts(
+        &self,
+        db_pool: &Pool<AsyncPgConnection>,
+    ) -> anyhow::Result<Vec<Post>> {
+        Ok(bookmarks::table
+            .filter(bookmarks::actor_id.eq(&self.id))
+            .inner_join(posts::table.on(posts::id.eq(bookmarks::post_id)))
+            .select(posts::all_columns)
+            .order(bookmarks::published.desc())
+            .load::<Post>(&mut db_pool.get().await?)
+            .await?)
+    }
 }
 
 impl UserUpdate {
@@ -406,6 +738,14 @@ impl UserUpdate {
             manually_approves_followers: None,
             is_cat: None,
             bot: None,
+            fields: None,
+            emojis: None,
+            also_known_as: None,
+            moved_to_id: None,
+            approved: None,
+            invite_id: None,
+            otp_secret: None,
+            otp_enabled: None,
         }
     }
 }
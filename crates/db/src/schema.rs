@@ -1,6 +1,18 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "import_mode"))]
+    pub struct ImportMode;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "import_state"))]
+    pub struct ImportState;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "import_type"))]
+    pub struct ImportType;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "notification_type"))]
     pub struct NotificationType;
@@ -10,6 +22,20 @@ pub mod sql_types {
     pub struct Visibility;
 }
 
+diesel::table! {
+    app_tokens (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 60]
+        token -> Bpchar,
+        #[max_length = 27]
+        application_id -> Bpchar,
+        published -> Timestamptz,
+        scopes -> Text,
+        expires_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     applications (id) {
         #[max_length = 27]
@@ -25,6 +51,33 @@ diesel::table! {
         #[max_length = 32]
         client_secret -> Bpchar,
         published -> Timestamptz,
+        scopes -> Text,
+    }
+}
+
+diesel::table! {
+    archives (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 27]
+        user_id -> Bpchar,
+        data -> Nullable<Bytea>,
+        published -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+        claimed_until -> Nullable<Timestamptz>,
+        failed_at -> Nullable<Timestamptz>,
+        error -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    articles (post_id) {
+        #[max_length = 27]
+        post_id -> Bpchar,
+        #[max_length = 500]
+        title -> Varchar,
+        summary -> Nullable<Text>,
+        content -> Text,
     }
 }
 
@@ -40,6 +93,92 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    custom_emojis (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 100]
+        shortcode -> Varchar,
+        #[max_length = 100]
+        instance -> Varchar,
+        local -> Bool,
+        #[max_length = 200]
+        ap_id -> Varchar,
+        #[max_length = 500]
+        image_url -> Varchar,
+        #[max_length = 100]
+        media_type -> Nullable<Varchar>,
+        #[max_length = 100]
+        category -> Nullable<Varchar>,
+        visible_in_picker -> Bool,
+        published -> Timestamptz,
+        updated -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    delivery_stats (instance) {
+        instance -> Text,
+        delivered -> Int8,
+        retried -> Int8,
+        failed -> Int8,
+        last_delivered_at -> Nullable<Timestamptz>,
+        last_failed_at -> Nullable<Timestamptz>,
+        last_error -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    followed_tags (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 27]
+        user_id -> Bpchar,
+        #[max_length = 27]
+        tag_id -> Bpchar,
+        published -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    invites (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 32]
+        code -> Varchar,
+        #[max_length = 27]
+        user_id -> Nullable<Bpchar>,
+        max_uses -> Nullable<Int4>,
+        uses -> Int4,
+        expires_at -> Nullable<Timestamptz>,
+        autofollow -> Bool,
+        published -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ImportType;
+    use super::sql_types::ImportMode;
+    use super::sql_types::ImportState;
+
+    imports (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 27]
+        user_id -> Bpchar,
+        import_type -> ImportType,
+        mode -> ImportMode,
+        state -> ImportState,
+        rows -> Jsonb,
+        total_items -> Int4,
+        processed_items -> Int4,
+        failed_items -> Int4,
+        published -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::NotificationType;
@@ -55,6 +194,25 @@ diesel::table! {
         post_id -> Nullable<Bpchar>,
         notification_type -> NotificationType,
         published -> Timestamptz,
+        #[max_length = 200]
+        reaction -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    outgoing_deliveries (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 27]
+        actor_id -> Bpchar,
+        inbox -> Text,
+        instance -> Text,
+        activity -> Jsonb,
+        attempts -> Int4,
+        next_attempt_at -> Timestamptz,
+        last_error -> Nullable<Text>,
+        published -> Timestamptz,
+        claimed_until -> Nullable<Timestamptz>,
     }
 }
 
@@ -76,6 +234,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_emojis (post_id, emoji_id) {
+        #[max_length = 27]
+        post_id -> Bpchar,
+        #[max_length = 27]
+        emoji_id -> Bpchar,
+    }
+}
+
 diesel::table! {
     post_like (post_id, actor_id) {
         #[max_length = 200]
@@ -99,6 +266,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_reactions (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 200]
+        ap_id -> Nullable<Varchar>,
+        #[max_length = 27]
+        post_id -> Bpchar,
+        #[max_length = 27]
+        actor_id -> Bpchar,
+        #[max_length = 200]
+        name -> Varchar,
+        #[max_length = 27]
+        emoji_id -> Nullable<Bpchar>,
+        published -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    post_tags (post_id, tag_id) {
+        #[max_length = 27]
+        post_id -> Bpchar,
+        #[max_length = 27]
+        tag_id -> Bpchar,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::Visibility;
@@ -139,6 +333,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    push_subscriptions (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 27]
+        session_id -> Bpchar,
+        #[max_length = 27]
+        user_id -> Bpchar,
+        endpoint -> Text,
+        p256dh -> Text,
+        auth -> Text,
+        alerts -> Text,
+        #[max_length = 16]
+        policy -> Varchar,
+        published -> Timestamptz,
+    }
+}
+
 diesel::table! {
     received_activities (ap_id) {
         ap_id -> Text,
@@ -146,6 +358,34 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    recovery_codes (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 27]
+        user_id -> Bpchar,
+        #[max_length = 64]
+        code_hash -> Bpchar,
+        published -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    scheduled_statuses (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 27]
+        user_id -> Bpchar,
+        scheduled_at -> Timestamptz,
+        params -> Jsonb,
+        published -> Timestamptz,
+        claimed_until -> Nullable<Timestamptz>,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     sessions (id) {
         #[max_length = 27]
@@ -157,6 +397,24 @@ diesel::table! {
         published -> Timestamptz,
         #[max_length = 27]
         application_id -> Nullable<Bpchar>,
+        scopes -> Text,
+        expires_at -> Nullable<Timestamptz>,
+        #[max_length = 60]
+        refresh_token -> Nullable<Bpchar>,
+        last_used_at -> Nullable<Timestamptz>,
+        ip -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        pkce -> Bool,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        #[max_length = 27]
+        id -> Bpchar,
+        #[max_length = 100]
+        name -> Varchar,
+        published -> Timestamptz,
     }
 }
 
@@ -220,33 +478,81 @@ diesel::table! {
         follow_requests_count -> Int4,
         posts_count -> Int4,
         last_post_published -> Nullable<Timestamptz>,
+        fields -> Jsonb,
+        emojis -> Jsonb,
+        also_known_as -> Jsonb,
+        #[max_length = 27]
+        moved_to_id -> Nullable<Bpchar>,
+        deleted_at -> Nullable<Timestamptz>,
+        approved -> Bool,
+        registration_reason -> Nullable<Text>,
+        #[max_length = 27]
+        invite_id -> Nullable<Bpchar>,
+        otp_secret -> Nullable<Text>,
+        otp_enabled -> Bool,
+        otp_consumed_timestep -> Nullable<Int8>,
     }
 }
 
+diesel::joinable!(app_tokens -> applications (application_id));
+diesel::joinable!(archives -> users (user_id));
+diesel::joinable!(articles -> posts (post_id));
 diesel::joinable!(bookmarks -> posts (post_id));
 diesel::joinable!(bookmarks -> users (actor_id));
+diesel::joinable!(followed_tags -> tags (tag_id));
+diesel::joinable!(followed_tags -> users (user_id));
+diesel::joinable!(imports -> users (user_id));
+diesel::joinable!(invites -> users (user_id));
 diesel::joinable!(notifications -> posts (post_id));
+diesel::joinable!(outgoing_deliveries -> users (actor_id));
 diesel::joinable!(post_boost -> posts (post_id));
 diesel::joinable!(post_boost -> users (actor_id));
+diesel::joinable!(post_emojis -> custom_emojis (emoji_id));
+diesel::joinable!(post_emojis -> posts (post_id));
 diesel::joinable!(post_like -> posts (post_id));
 diesel::joinable!(post_like -> users (actor_id));
 diesel::joinable!(post_mention -> posts (post_id));
 diesel::joinable!(post_mention -> users (mentioned_user_id));
+diesel::joinable!(post_reactions -> custom_emojis (emoji_id));
+diesel::joinable!(post_reactions -> posts (post_id));
+diesel::joinable!(post_reactions -> users (actor_id));
+diesel::joinable!(post_tags -> posts (post_id));
+diesel::joinable!(post_tags -> tags (tag_id));
 diesel::joinable!(posts -> users (author));
+diesel::joinable!(push_subscriptions -> sessions (session_id));
+diesel::joinable!(push_subscriptions -> users (user_id));
+diesel::joinable!(recovery_codes -> users (user_id));
+diesel::joinable!(scheduled_statuses -> users (user_id));
 diesel::joinable!(sessions -> applications (application_id));
 diesel::joinable!(sessions -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    app_tokens,
     applications,
+    archives,
+    articles,
     bookmarks,
+    custom_emojis,
+    delivery_stats,
+    followed_tags,
+    imports,
+    invites,
     notifications,
+    outgoing_deliveries,
     post_boost,
+    post_emojis,
     post_like,
     post_mention,
+    post_reactions,
+    post_tags,
     posts,
     private_notes,
+    push_subscriptions,
     received_activities,
+    recovery_codes,
+    scheduled_statuses,
     sessions,
+    tags,
     user_follow_requests,
     user_followers,
     users,
@@ -67,6 +67,8 @@ pub enum DbNotificationType {
     FollowRequest,
     Favourite,
     Quote,
+    #[serde(rename = "pleroma:emoji_reaction")]
+    EmojiReaction,
 }
 
 impl DbNotificationType {
@@ -78,7 +80,61 @@ impl DbNotificationType {
             "follow_request" => Some(Self::FollowRequest),
             "favourite" => Some(Self::Favourite),
             "quote" => Some(Self::Quote),
+            "pleroma:emoji_reaction" => Some(Self::EmojiReaction),
             _ => None,
         }
     }
 }
+
+#[derive(
+    diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+#[ExistingTypePath = "crate::schema::sql_types::ImportType"]
+pub enum DbImportType {
+    Following,
+    Bookmarks,
+}
+
+impl DbImportType {
+    pub fn from_string(string: &str) -> Option<Self> {
+        match string {
+            "following" => Some(Self::Following),
+            "bookmarks" => Some(Self::Bookmarks),
+            _ => None,
+        }
+    }
+}
+
+#[derive(
+    diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+#[ExistingTypePath = "crate::schema::sql_types::ImportMode"]
+pub enum DbImportMode {
+    /// Keep everything that isn't in the file
+    Merge,
+    /// Remove everything that isn't in the file
+    Overwrite,
+}
+
+impl DbImportMode {
+    pub fn from_string(string: &str) -> Option<Self> {
+        match string {
+            "merge" => Some(Self::Merge),
+            "overwrite" => Some(Self::Overwrite),
+            _ => None,
+        }
+    }
+}
+
+#[derive(
+    diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+#[ExistingTypePath = "crate::schema::sql_types::ImportState"]
+pub enum DbImportState {
+    Scheduled,
+    InProgress,
+    Finished,
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use api::common::emojis;
+use serde::{Deserialize, Serialize};
+use web::AppState;
+
+fn default_visible_in_picker() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct RpcAddEmojiData {
+    shortcode: String,
+    image_url: String,
+    category: Option<String>,
+    #[serde(default = "default_visible_in_picker")]
+    visible_in_picker: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcAddEmojiResponse {
+    ok: bool,
+}
+
+pub(crate) struct RpcAddEmoji;
+
+impl RpcAddEmoji {
+    pub(crate) async fn call(
+        request: RpcAddEmojiData,
+        data: &Data<Arc<AppState>>,
+    ) -> RpcAddEmojiResponse {
+        let emoji = emojis::add(
+            request.shortcode,
+            request.image_url,
+            request.category,
+            request.visible_in_picker,
+            data,
+        )
+        .await;
+        match emoji {
+            Ok(_) => RpcAddEmojiResponse { ok: true },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcAddEmojiResponse { ok: false }
+            },
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use db::models::{user::UserUpdate, User};
+use serde::Serialize;
+use web::AppState;
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcApproveUserResponse {
+    ok: bool,
+}
+
+pub(crate) struct RpcApproveUser;
+
+impl RpcApproveUser {
+    pub(crate) async fn call(
+        request: String,
+        data: &Data<Arc<AppState>>,
+    ) -> RpcApproveUserResponse {
+        match approve(&request, data).await {
+            Ok(approved) => RpcApproveUserResponse { ok: approved },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcApproveUserResponse { ok: false }
+            },
+        }
+    }
+}
+
+async fn approve(name: &str, data: &Data<Arc<AppState>>) -> anyhow::Result<bool> {
+    let user = match User::local_by_name(name, &data.db_pool).await? {
+        Some(user) if !user.approved => user,
+        _ => return Ok(false),
+    };
+
+    let mut updated_user = UserUpdate::new();
+    updated_user.approved = Some(true);
+    user.update(updated_user, &data.db_pool).await?;
+    Ok(true)
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use api::common::users::INVITE_MAX_EXPIRES_IN;
+use chrono::{Duration, Utc};
+use db::models::Invite;
+use serde::{Deserialize, Serialize};
+use web::AppState;
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct RpcCreateInviteData {
+    max_uses: Option<i32>,
+    expires_in: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcCreateInviteResponse {
+    ok: bool,
+    code: Option<String>,
+    url: Option<String>,
+}
+
+pub(crate) struct RpcCreateInvite;
+
+impl RpcCreateInvite {
+    pub(crate) async fn call(
+        request: RpcCreateInviteData,
+        data: &Data<Arc<AppState>>,
+    ) -> RpcCreateInviteResponse {
+        if request.max_uses.is_some_and(|max_uses| max_uses < 1)
+            || request
+                .expires_in
+                .is_some_and(|expires_in| !(1..=INVITE_MAX_EXPIRES_IN).contains(&expires_in))
+        {
+            return RpcCreateInviteResponse {
+                ok: false,
+                code: None,
+                url: None,
+            };
+        }
+
+        let invite = Invite::create(
+            None,
+            request.max_uses,
+            request
+                .expires_in
+                .map(|expires_in| Utc::now() + Duration::seconds(expires_in)),
+            false,
+            &data.db_pool,
+        )
+        .await;
+        match invite {
+            Ok(invite) => RpcCreateInviteResponse {
+                ok: true,
+                url: Some(format!(
+                    "https://{}/auth/sign_up?invite_code={}",
+                    data.config.web.domain, invite.code
+                )),
+                code: Some(invite.code),
+            },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcCreateInviteResponse {
+                    ok: false,
+                    code: None,
+                    url: None,
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use activitypub_federation::config::Data;
+use chrono::{DateTime, Utc};
+use db::models::{DeliveryStats, OutgoingDelivery};
+use serde::Serialize;
+use web::AppState;
+
+#[derive(Serialize, Debug, Default)]
+pub(crate) struct RpcInstanceDeliveries {
+    instance: String,
+    /// Activities waiting to be delivered
+    queued: i64,
+    delivered: i64,
+    retried: i64,
+    failed: i64,
+    last_delivered_at: Option<DateTime<Utc>>,
+    last_failed_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcDeliveriesResponse {
+    ok: bool,
+    instances: Vec<RpcInstanceDeliveries>,
+}
+
+pub(crate) struct RpcDeliveries;
+
+impl RpcDeliveries {
+    pub(crate) async fn call(data: &Data<Arc<AppState>>) -> RpcDeliveriesResponse {
+        match deliveries(data).await {
+            Ok(instances) => RpcDeliveriesResponse {
+                ok: true,
+                instances,
+            },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcDeliveriesResponse {
+                    ok: false,
+                    instances: vec![],
+                }
+            },
+        }
+    }
+}
+
+async fn deliveries(data: &Data<Arc<AppState>>) -> anyhow::Result<Vec<RpcInstanceDeliveries>> {
+    let mut instances = BTreeMap::new();
+    for stats in DeliveryStats::all(&data.db_pool).await? {
+        instances.insert(
+            stats.instance.clone(),
+            RpcInstanceDeliveries {
+                instance: stats.instance,
+                queued: 0,
+                delivered: stats.delivered,
+                retried: stats.retried,
+                failed: stats.failed,
+                last_delivered_at: stats.last_delivered_at,
+                last_failed_at: stats.last_failed_at,
+                last_error: stats.last_error,
+            },
+        );
+    }
+    // Instances that nothing was attempted for yet have no statistics
+    for (instance, queued) in OutgoingDelivery::count_by_instance(&data.db_pool).await? {
+        instances
+            .entry(instance.clone())
+            .or_insert_with(|| RpcInstanceDeliveries {
+                instance,
+                ..Default::default()
+            })
+            .queued = queued;
+    }
+
+    Ok(instances.into_values().collect())
+}
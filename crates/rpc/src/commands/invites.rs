@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use chrono::{DateTime, Utc};
+use db::models::{Invite, User};
+use serde::Serialize;
+use web::AppState;
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcInvite {
+    code: String,
+    /// Name of the user who created the invite, `None` for invites created by admins over RPC
+    created_by: Option<String>,
+    uses: i32,
+    max_uses: Option<i32>,
+    expires_at: Option<DateTime<Utc>>,
+    /// Names of the users who signed up with the invite
+    invited: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcInvitesResponse {
+    ok: bool,
+    invites: Vec<RpcInvite>,
+}
+
+pub(crate) struct RpcInvites;
+
+impl RpcInvites {
+    pub(crate) async fn call(data: &Data<Arc<AppState>>) -> RpcInvitesResponse {
+        match invites(data).await {
+            Ok(invites) => RpcInvitesResponse { ok: true, invites },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcInvitesResponse {
+                    ok: false,
+                    invites: vec![],
+                }
+            },
+        }
+    }
+}
+
+async fn invites(data: &Data<Arc<AppState>>) -> anyhow::Result<Vec<RpcInvite>> {
+    let mut invites = vec![];
+    for invite in Invite::all(&data.db_pool).await? {
+        let created_by = match &invite.user_id {
+            Some(user_id) => User::by_id(user_id, &data.db_pool)
+                .await?
+                .map(|user| user.name),
+            None => None,
+        };
+        let invited = invite
+            .invited_users(&data.db_pool)
+            .await?
+            .into_iter()
+            .map(|user| user.name)
+            .collect();
+        invites.push(RpcInvite {
+            code: invite.code,
+            created_by,
+            uses: invite.uses,
+            max_uses: invite.max_uses,
+            expires_at: invite.expires_at,
+            invited,
+        });
+    }
+    Ok(invites)
+}
@@ -1,10 +1,26 @@
+pub(crate) mod addemoji;
+pub(crate) mod approveuser;
+pub(crate) mod createinvite;
+pub(crate) mod deliveries;
+pub(crate) mod invites;
+pub(crate) mod pendingusers;
 pub(crate) mod register;
+pub(crate) mod rejectuser;
+pub(crate) mod removeemoji;
 pub(crate) mod userfetch;
 
 use serde::{Deserialize, Serialize};
 
 use crate::commands::{
+    addemoji::{RpcAddEmojiData, RpcAddEmojiResponse},
+    approveuser::RpcApproveUserResponse,
+    createinvite::{RpcCreateInviteData, RpcCreateInviteResponse},
+    deliveries::RpcDeliveriesResponse,
+    invites::RpcInvitesResponse,
+    pendingusers::RpcPendingUsersResponse,
     register::{RpcRegisterUserData, RpcRegisterUserResponse},
+    rejectuser::RpcRejectUserResponse,
+    removeemoji::RpcRemoveEmojiResponse,
     userfetch::RpcUserFetchResponse,
 };
 
@@ -13,6 +29,14 @@ use crate::commands::{
 pub(crate) enum RpcCommandData {
     UserFetch(String),
     RegisterUser(RpcRegisterUserData),
+    AddEmoji(RpcAddEmojiData),
+    RemoveEmoji(String),
+    PendingUsers,
+    ApproveUser(String),
+    RejectUser(String),
+    CreateInvite(RpcCreateInviteData),
+    Invites,
+    Deliveries,
 }
 
 #[derive(Serialize, Debug)]
@@ -20,4 +44,12 @@ pub(crate) enum RpcCommandData {
 pub(crate) enum RpcCommandResponse {
     UserFetch(RpcUserFetchResponse),
     RegisterUser(RpcRegisterUserResponse),
+    AddEmoji(RpcAddEmojiResponse),
+    RemoveEmoji(RpcRemoveEmojiResponse),
+    PendingUsers(RpcPendingUsersResponse),
+    ApproveUser(RpcApproveUserResponse),
+    RejectUser(RpcRejectUserResponse),
+    CreateInvite(RpcCreateInviteResponse),
+    Invites(RpcInvitesResponse),
+    Deliveries(RpcDeliveriesResponse),
 }
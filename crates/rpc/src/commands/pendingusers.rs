@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use chrono::{DateTime, Utc};
+use db::models::User;
+use serde::Serialize;
+use web::AppState;
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcPendingUser {
+    name: String,
+    reason: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcPendingUsersResponse {
+    ok: bool,
+    users: Vec<RpcPendingUser>,
+}
+
+pub(crate) struct RpcPendingUsers;
+
+impl RpcPendingUsers {
+    pub(crate) async fn call(data: &Data<Arc<AppState>>) -> RpcPendingUsersResponse {
+        match User::pending(&data.db_pool).await {
+            Ok(users) => RpcPendingUsersResponse {
+                ok: true,
+                users: users
+                    .into_iter()
+                    .map(|user| RpcPendingUser {
+                        name: user.name,
+                        reason: user.registration_reason,
+                        created_at: user.published,
+                    })
+                    .collect(),
+            },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcPendingUsersResponse {
+                    ok: false,
+                    users: vec![],
+                }
+            },
+        }
+    }
+}
@@ -30,6 +30,8 @@ impl RpcRegisterUser {
             request.password,
             request.bio,
             request.display_name,
+            true,
+            None,
             data,
         )
         .await;
@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use db::models::User;
+use serde::Serialize;
+use web::AppState;
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcRejectUserResponse {
+    ok: bool,
+}
+
+pub(crate) struct RpcRejectUser;
+
+impl RpcRejectUser {
+    pub(crate) async fn call(request: String, data: &Data<Arc<AppState>>) -> RpcRejectUserResponse {
+        let user = User::local_by_name(&request, &data.db_pool).await;
+        let rejected = match user {
+            Ok(Some(user)) => user.delete_pending(&data.db_pool).await,
+            Ok(None) => Ok(false),
+            Err(err) => Err(err),
+        };
+        match rejected {
+            Ok(rejected) => RpcRejectUserResponse { ok: rejected },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcRejectUserResponse { ok: false }
+            },
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::Data;
+use api::common::emojis;
+use serde::Serialize;
+use web::AppState;
+
+#[derive(Serialize, Debug)]
+pub(crate) struct RpcRemoveEmojiResponse {
+    ok: bool,
+}
+
+pub(crate) struct RpcRemoveEmoji;
+
+impl RpcRemoveEmoji {
+    pub(crate) async fn call(
+        request: String,
+        data: &Data<Arc<AppState>>,
+    ) -> RpcRemoveEmojiResponse {
+        match emojis::remove(request.trim_matches(':'), data).await {
+            Ok(removed) => RpcRemoveEmojiResponse { ok: removed },
+            Err(err) => {
+                log::error!("Error from RPC command, {:#?}", err);
+                RpcRemoveEmojiResponse { ok: false }
+            },
+        }
+    }
+}
@@ -12,7 +12,10 @@ use tokio::{
 use web::AppState;
 
 use crate::commands::{
-    register::RpcRegisterUser, userfetch::RpcUserFetch, RpcCommandData, RpcCommandResponse,
+    addemoji::RpcAddEmoji, approveuser::RpcApproveUser, createinvite::RpcCreateInvite,
+    deliveries::RpcDeliveries, invites::RpcInvites, pendingusers::RpcPendingUsers,
+    register::RpcRegisterUser, rejectuser::RpcRejectUser, removeemoji::RpcRemoveEmoji,
+    userfetch::RpcUserFetch, RpcCommandData, RpcCommandResponse,
 };
 
 pub async fn process(stream: UnixStream, data: Arc<Data<Arc<AppState>>>) -> anyhow::Result<()> {
@@ -47,6 +50,30 @@ pub async fn process(stream: UnixStream, data: Arc<Data<Arc<AppState>>>) -> anyh
                 RpcCommandData::RegisterUser(request) => {
                     RpcCommandResponse::RegisterUser(RpcRegisterUser::call(request, &data).await)
                 },
+                RpcCommandData::AddEmoji(request) => {
+                    RpcCommandResponse::AddEmoji(RpcAddEmoji::call(request, &data).await)
+                },
+                RpcCommandData::RemoveEmoji(request) => {
+                    RpcCommandResponse::RemoveEmoji(RpcRemoveEmoji::call(request, &data).await)
+                },
+                RpcCommandData::PendingUsers => {
+                    RpcCommandResponse::PendingUsers(RpcPendingUsers::call(&data).await)
+                },
+                RpcCommandData::ApproveUser(request) => {
+                    RpcCommandResponse::ApproveUser(RpcApproveUser::call(request, &data).await)
+                },
+                RpcCommandData::RejectUser(request) => {
+                    RpcCommandResponse::RejectUser(RpcRejectUser::call(request, &data).await)
+                },
+                RpcCommandData::CreateInvite(request) => {
+                    RpcCommandResponse::CreateInvite(RpcCreateInvite::call(request, &data).await)
+                },
+                RpcCommandData::Invites => {
+                    RpcCommandResponse::Invites(RpcInvites::call(&data).await)
+                },
+                RpcCommandData::Deliveries => {
+                    RpcCommandResponse::Deliveries(RpcDeliveries::call(&data).await)
+                },
             };
 
             loop {
@@ -14,6 +14,18 @@ pub struct Web {
     pub port: u16,
     #[serde(default = "host_default")]
     pub host: String,
+    #[serde(default)]
+    pub streaming_backend: StreamingBackend,
+}
+
+/// Where streaming events go through. With Redis, several processes behind a load balancer get
+/// each other's events
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum StreamingBackend {
+    #[default]
+    Memory,
+    Redis,
 }
 
 fn host_default() -> String {
@@ -44,6 +56,35 @@ pub struct Instance {
     pub display_name_max_characters: i32,
     #[serde(default = "bio_max_characters_default")]
     pub bio_max_characters: i32,
+    #[serde(default = "max_profile_fields_default")]
+    pub max_profile_fields: i32,
+    #[serde(default)]
+    pub registrations: RegistrationMode,
+    /// Whether users other than admins can create invites
+    #[serde(default)]
+    pub users_can_invite: bool,
+    /// Whether admins have to set up two-factor authentication before they can sign in
+    #[serde(default)]
+    pub require_two_factor_for_admins: bool,
+    /// Seconds until tokens issued to applications expire and have to be refreshed. They never
+    /// expire when it's left out
+    #[serde(default)]
+    pub access_token_lifetime: Option<i64>,
+    /// Whether public and hashtag streams can be used without an access token
+    #[serde(default)]
+    pub allow_unauthenticated_streaming: bool,
+}
+
+/// Who can sign up through the API and the sign-up page. Admins can always create accounts over
+/// RPC
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RegistrationMode {
+    Open,
+    /// New accounts can't sign in until an admin approves them
+    ApprovalRequired,
+    #[default]
+    Closed,
 }
 
 fn description_default() -> String {
@@ -65,3 +106,7 @@ fn display_name_max_characters_default() -> i32 {
 fn bio_max_characters_default() -> i32 {
     500
 }
+
+fn max_profile_fields_default() -> i32 {
+    4
+}
@@ -10,4 +10,6 @@ pub struct AppState {
     pub redis: ConnectionManager,
     pub config: Config,
     pub local_pool: LocalPoolHandle,
+    /// Base64url-encoded P-256 private key that signs Web Push messages (VAPID)
+    pub vapid_key: String,
 }
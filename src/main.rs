@@ -13,7 +13,10 @@ use activitypub_federation::{
     config::FederationConfig,
     http_signatures::{generate_actor_keypair, Keypair},
 };
-use ap::objects::service_actor::ServiceActor;
+use ap::{
+    common::streaming::{self, EVENT_BUS},
+    objects::service_actor::ServiceActor,
+};
 use axum::ServiceExt;
 use diesel_async::pooled_connection::{deadpool::Pool, AsyncDieselConnectionManager};
 use listenfd::ListenFd;
@@ -21,7 +24,7 @@ use redis::aio::ConnectionManager;
 use serde::{Deserialize, Serialize};
 use tokio_util::task::LocalPoolHandle;
 use url::Url;
-use web::AppState;
+use web::{config::StreamingBackend, AppState};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ServiceActorData {
@@ -63,6 +66,8 @@ async fn main() -> anyhow::Result<()> {
         },
     };
 
+    let vapid_key = api::common::push::load_vapid_key("vapid.keys")?;
+
     let config = match config::process_config() {
         Ok(config) => config,
         Err(err) => {
@@ -86,11 +91,18 @@ async fn main() -> anyhow::Result<()> {
     let redis_client = redis::Client::open(config.redis.uri.clone())?;
     let state = Arc::new(AppState {
         db_pool,
-        redis: ConnectionManager::new(redis_client).await?,
+        redis: ConnectionManager::new(redis_client.clone()).await?,
         config,
         local_pool: LocalPoolHandle::new(20),
+        vapid_key,
     });
 
+    if state.config.web.streaming_backend == StreamingBackend::Redis {
+        EVENT_BUS.use_redis(state.redis.clone());
+        let db_pool = state.db_pool.clone();
+        tokio::spawn(async move { streaming::listen_redis(redis_client, db_pool).await });
+    }
+
     let data = FederationConfig::builder()
         .domain(&state.config.web.domain)
         .app_data(Arc::clone(&state))
@@ -102,13 +114,30 @@ async fn main() -> anyhow::Result<()> {
     let rpc_data = Arc::new(data.to_request_data());
     tokio::spawn(async move { rpc::start(rpc_data).await });
 
+    let scheduled_statuses_data = Arc::new(data.to_request_data());
+    tokio::spawn(
+        async move { api::workers::scheduled_statuses::start(scheduled_statuses_data).await },
+    );
+
+    let imports_data = Arc::new(data.to_request_data());
+    tokio::spawn(async move { api::workers::imports::start(imports_data).await });
+
+    let archives_data = Arc::new(data.to_request_data());
+    tokio::spawn(async move { api::workers::archives::start(archives_data).await });
+
+    let push_data = Arc::new(data.to_request_data());
+    tokio::spawn(async move { api::workers::push::start(push_data).await });
+
+    let deliveries_data = Arc::new(data.to_request_data());
+    tokio::spawn(async move { api::workers::deliveries::start(deliveries_data).await });
+
     let app = router::app(data, service_actor.clone());
 
     match tcp_socket {
         // cargo-watch thing
         Some(listener) => axum::Server::from_tcp(listener)
             .unwrap()
-            .serve(app.into_make_service())
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
             .await
             .unwrap(),
         None => {
@@ -117,7 +146,7 @@ async fn main() -> anyhow::Result<()> {
                 state.config.web.port,
             ));
             axum::Server::bind(&addr)
-                .serve(app.into_make_service())
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
                 .await
                 .unwrap()
         },